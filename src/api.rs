@@ -0,0 +1,534 @@
+// Versie 1 van de JSON API onder /api/v1, voor eigen scripts en clients. Anders dan de oude routes van de web interface
+// geeft deze echte status codes terug en bij fouten altijd een ApiFout. De beschrijving staat in openapi.rs.
+
+use crate::{Advertentie, Configuratie, Monitor};
+use crate::opslag::{self, Staat};
+use crate::web::{self, ZoekQuery};
+use crate::{export, openapi, statistieken, toestand, wenslijst};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use warp::filters::BoxedFilter;
+use warp::http::StatusCode;
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
+
+// Instellingen die niet via de API te lezen of aan te passen zijn
+pub const GEHEIME_INSTELLINGEN: &[&str] = &["web_token", "web_wachtwoord", "web_wachtwoord_hash", "api_tokens", "feed_token"];
+// Paden en adressen, via de API zou je daarmee overal bestanden kunnen laten schrijven of de monitor ergens anders laten luisteren
+const VASTE_INSTELLINGEN: &[&str] = &[
+    "data_map", "wenslijst_bestand", "resultaten_bestand", "opslag_bestand", "status_bestand", "prijzen_bestand",
+    "tls_certificaat", "tls_sleutel", "web_adres", "web_poort", "api_basis_url",
+];
+
+#[derive(Debug, Serialize)]
+pub struct ApiFout {
+    pub fout: String, // Vaste code zoals "niet_gevonden", om in een client op te kunnen reageren
+    pub bericht: String,
+}
+
+pub fn fout(status: StatusCode, code: &str, bericht: impl Into<String>) -> Response {
+    let fout = ApiFout { fout: code.to_string(), bericht: bericht.into() };
+    warp::reply::with_status(warp::reply::json(&fout), status).into_response()
+}
+
+fn interne_fout(bericht: impl std::fmt::Display) -> Response {
+    fout(StatusCode::INTERNAL_SERVER_ERROR, "interne_fout", bericht.to_string())
+}
+
+fn json_met_status<T: Serialize>(waarde: &T, status: StatusCode) -> Response {
+    warp::reply::with_status(warp::reply::json(waarde), status).into_response()
+}
+
+fn geen_inhoud() -> Response {
+    warp::reply::with_status(warp::reply(), StatusCode::NO_CONTENT).into_response()
+}
+
+#[derive(Debug, Serialize)]
+struct ResultatenPagina {
+    totaal: usize,
+    offset: usize,
+    limit: Option<usize>,
+    resultaten: Vec<web::ResultaatArtikel>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ResultaatAanpassing {
+    staat: Option<Staat>,
+    favoriet: Option<bool>,
+    notitie: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WenslijstInvoer {
+    zoekwoord: String,
+    max_prijs: i32,
+    #[serde(default)]
+    max_percentiel: Option<u8>,
+    #[serde(default)]
+    interval_seconden: Option<u64>,
+    #[serde(default)]
+    venster: Option<String>,
+}
+
+impl From<WenslijstInvoer> for wenslijst::Zoekopdracht {
+    fn from(invoer: WenslijstInvoer) -> Self {
+        wenslijst::Zoekopdracht {
+            zoekwoord: invoer.zoekwoord,
+            max_prijs: invoer.max_prijs,
+            max_percentiel: invoer.max_percentiel,
+            interval_seconden: invoer.interval_seconden,
+            venster: invoer.venster,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StatistiekenQuery {
+    zoekwoord: Option<String>, // Anders alle zoekwoorden van de wenslijst
+}
+
+#[derive(Debug, Deserialize)]
+struct ZoekParameters {
+    q: String,
+    max_prijs: Option<i32>,
+}
+
+// Een advertentie zoals Marktplaats die teruggeeft, maar in dezelfde vorm als de opgeslagen resultaten
+#[derive(Debug, Serialize)]
+pub struct ZoekTreffer {
+    item_id: String,
+    titel: String,
+    prijs: String,
+    prijs_centen: Option<i32>,
+    prijs_type: String,
+    locatie: String,
+    afstand: String,
+    afstand_meters: Option<i32>,
+    link: String,
+    beschrijving: String,
+    afbeelding: Option<String>,
+    verkoper_id: Option<String>,
+    verkoper_naam: Option<String>,
+}
+
+impl ZoekTreffer {
+    pub fn nieuw(advertentie: &Advertentie, monitor: &Monitor) -> Self {
+        ZoekTreffer {
+            item_id: advertentie.item_id.clone(),
+            titel: advertentie.titel.clone(),
+            prijs: format!("{}{}", advertentie.prijs_weergave(), advertentie.prijs_type_label()),
+            prijs_centen: advertentie.prijs_info.prijs_centen,
+            prijs_type: advertentie.prijs_info.prijs_type.clone(),
+            locatie: advertentie.locatie.stad_naam.clone().unwrap_or_else(|| "Onbekend".to_string()),
+            afstand: advertentie.afstand_weergave(),
+            afstand_meters: advertentie.locatie.afstand_meters,
+            link: monitor.volledige_url(advertentie),
+            beschrijving: advertentie.korte_beschrijving(),
+            afbeelding: advertentie.afbeelding_urls.as_ref().and_then(|urls| urls.first()).cloned(),
+            verkoper_id: advertentie.verkoper_id(),
+            verkoper_naam: advertentie.verkoper_naam().map(String::from),
+        }
+    }
+}
+
+// Zonder login, zodat een client weet wat er kan voordat er een token is
+pub fn openapi_route() -> BoxedFilter<(Response,)> {
+    warp::path!("api" / "v1" / "openapi.json")
+        .and(warp::get())
+        .map(|| warp::reply::json(&openapi::document()).into_response())
+        .boxed()
+}
+
+pub fn routes(config: Arc<Mutex<Configuratie>>) -> BoxedFilter<(Response,)> {
+    let config_filter = warp::any().map(move || config.clone());
+
+    let resultaten_lijst = warp::path!("resultaten")
+        .and(warp::get())
+        .and(warp::query::<ZoekQuery>())
+        .and(config_filter.clone())
+        .map(lijst_resultaten);
+
+    let resultaten_wis = warp::path!("resultaten")
+        .and(warp::delete())
+        .and(config_filter.clone())
+        .map(wis_resultaten);
+
+    let resultaat_get = warp::path!("resultaten" / String)
+        .and(warp::get())
+        .and(config_filter.clone())
+        .map(haal_resultaat);
+
+    let resultaat_patch = warp::path!("resultaten" / String)
+        .and(warp::patch())
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .map(pas_resultaat_aan);
+
+    let zoek = warp::path!("zoek")
+        .and(warp::get())
+        .and(warp::query::<ZoekParameters>())
+        .and(config_filter.clone())
+        .then(zoek);
+
+    let wenslijst_lijst = warp::path!("wenslijst")
+        .and(warp::get())
+        .and(config_filter.clone())
+        .map(lijst_wenslijst);
+
+    let wenslijst_post = warp::path!("wenslijst")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("if-match"))
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .map(maak_wenslijst_regel);
+
+    let wenslijst_get = warp::path!("wenslijst" / usize)
+        .and(warp::get())
+        .and(config_filter.clone())
+        .map(haal_wenslijst_regel);
+
+    let wenslijst_put = warp::path!("wenslijst" / usize)
+        .and(warp::put())
+        .and(warp::header::optional::<String>("if-match"))
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .map(vervang_wenslijst_regel);
+
+    let wenslijst_delete = warp::path!("wenslijst" / usize)
+        .and(warp::delete())
+        .and(warp::header::optional::<String>("if-match"))
+        .and(config_filter.clone())
+        .map(verwijder_wenslijst_regel);
+
+    let status_get = warp::path!("status")
+        .and(warp::get())
+        .and(config_filter.clone())
+        .map(haal_status);
+
+    let statistieken_get = warp::path!("statistieken")
+        .and(warp::get())
+        .and(warp::query::<StatistiekenQuery>())
+        .and(config_filter.clone())
+        .map(haal_statistieken);
+
+    let export_get = warp::path!("export")
+        .and(warp::get())
+        .and(warp::query::<export::Filter>())
+        .and(config_filter.clone())
+        .map(exporteer);
+
+    let config_get = warp::path!("config")
+        .and(warp::get())
+        .and(config_filter.clone())
+        .map(|config: Arc<Mutex<Configuratie>>| warp::reply::json(&web::zonder_geheimen(&config.lock().unwrap())).into_response());
+
+    let config_patch = warp::path!("config")
+        .and(warp::patch())
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .map(pas_config_aan);
+
+    let api = resultaten_lijst
+        .or(resultaten_wis).unify()
+        .or(resultaat_get).unify()
+        .or(resultaat_patch).unify()
+        .or(zoek).unify()
+        .or(wenslijst_lijst).unify()
+        .or(wenslijst_post).unify()
+        .or(wenslijst_get).unify()
+        .or(wenslijst_put).unify()
+        .or(wenslijst_delete).unify()
+        .boxed(); // Anders worden de types zo diep dat compileren minuten duurt
+    let api = api
+        .or(status_get).unify()
+        .or(statistieken_get).unify()
+        .or(export_get).unify()
+        .or(config_get).unify()
+        .or(config_patch).unify();
+
+    // Eerst het pad en dan de methode, anders wordt een onbekende route een 405 i.p.v. een 404
+    let api = api.recover(|weigering: Rejection| async move { fout_voor_weigering(&weigering).ok_or(weigering) }).unify();
+    warp::path("api").and(warp::path("v1")).and(api).boxed()
+}
+
+// Alle fouten die warp zelf geeft (verkeerde body, onbekende route etc.) ook als ApiFout
+pub fn fout_voor_weigering(weigering: &Rejection) -> Option<Response> {
+    use warp::reject::{InvalidQuery, LengthRequired, MethodNotAllowed, PayloadTooLarge, UnsupportedMediaType};
+
+    let antwoord = if weigering.is_not_found() {
+        fout(StatusCode::NOT_FOUND, "niet_gevonden", "Deze route bestaat niet")
+    } else if let Some(e) = weigering.find::<warp::filters::body::BodyDeserializeError>() {
+        fout(StatusCode::BAD_REQUEST, "ongeldige_body", e.to_string())
+    } else if let Some(e) = weigering.find::<InvalidQuery>() {
+        fout(StatusCode::BAD_REQUEST, "ongeldige_query", e.to_string())
+    } else if weigering.find::<UnsupportedMediaType>().is_some() {
+        fout(StatusCode::UNSUPPORTED_MEDIA_TYPE, "ongeldig_type", "Stuur de body als application/json")
+    } else if weigering.find::<LengthRequired>().is_some() {
+        fout(StatusCode::LENGTH_REQUIRED, "lengte_vereist", "Content-Length ontbreekt")
+    } else if weigering.find::<PayloadTooLarge>().is_some() {
+        fout(StatusCode::PAYLOAD_TOO_LARGE, "te_groot", "De body is te groot")
+    } else if weigering.find::<MethodNotAllowed>().is_some() {
+        fout(StatusCode::METHOD_NOT_ALLOWED, "methode_niet_toegestaan", "Deze methode kan niet op deze route")
+    } else {
+        return None;
+    };
+    Some(antwoord)
+}
+
+fn lijst_resultaten(query: ZoekQuery, config: Arc<Mutex<Configuratie>>) -> Response {
+    let configuratie = config.lock().unwrap().clone();
+    let (offset, limit) = (query.offset.unwrap_or(0), query.limit);
+    let (resultaten, totaal) = web::zoek_resultaten(&configuratie, query);
+    warp::reply::json(&ResultatenPagina { totaal, offset, limit, resultaten }).into_response()
+}
+
+fn wis_resultaten(config: Arc<Mutex<Configuratie>>) -> Response {
+    let configuratie = config.lock().unwrap().clone();
+
+    if let Err(e) = wis_alles(&configuratie) {
+        return interne_fout(format!("Kon resultaten niet wissen: {}", e));
+    }
+
+    geen_inhoud()
+}
+
+// Ook de geziene prijzen, en de check lus laten weten dat hij opnieuw moet beginnen
+pub fn wis_alles(configuratie: &Configuratie) -> Result<(), Box<dyn Error>> {
+    std::fs::write(&configuratie.resultaten_bestand, "")?;
+    opslag::wis_resultaten(&configuratie.opslag_bestand)?;
+    opslag::wis_statussen(&configuratie.status_bestand)?;
+    if Path::new(&configuratie.prijzen_bestand).exists() {
+        std::fs::remove_file(&configuratie.prijzen_bestand)?;
+    }
+    toestand::vraag_om_wissen();
+    Ok(())
+}
+
+fn haal_resultaat(item_id: String, config: Arc<Mutex<Configuratie>>) -> Response {
+    let configuratie = config.lock().unwrap().clone();
+    match web::haal_resultaat(&configuratie, &item_id) {
+        Some(resultaat) => warp::reply::json(&resultaat).into_response(),
+        None => fout(StatusCode::NOT_FOUND, "niet_gevonden", format!("Geen resultaat met id {}", item_id)),
+    }
+}
+
+fn pas_resultaat_aan(item_id: String, aanpassing: ResultaatAanpassing, config: Arc<Mutex<Configuratie>>) -> Response {
+    let configuratie = config.lock().unwrap().clone();
+    if web::haal_resultaat(&configuratie, &item_id).is_none() {
+        return fout(StatusCode::NOT_FOUND, "niet_gevonden", format!("Geen resultaat met id {}", item_id));
+    }
+
+    let aangepast = opslag::pas_statussen_aan(&configuratie.status_bestand, std::slice::from_ref(&item_id), |status| {
+        if let Some(staat) = aanpassing.staat {
+            status.staat = staat;
+        }
+        if let Some(favoriet) = aanpassing.favoriet {
+            status.favoriet = favoriet;
+        }
+        if let Some(notitie) = &aanpassing.notitie {
+            status.notitie = notitie.trim().to_string();
+        }
+    });
+    if let Err(e) = aangepast {
+        return interne_fout(format!("Kon status niet opslaan: {}", e));
+    }
+
+    haal_resultaat(item_id, config)
+}
+
+async fn zoek(parameters: ZoekParameters, config: Arc<Mutex<Configuratie>>) -> Response {
+    let zoekwoord = parameters.q.trim();
+    if zoekwoord.is_empty() {
+        return fout(StatusCode::BAD_REQUEST, "ongeldige_query", "q mag niet leeg zijn");
+    }
+    let max_prijs = match parameters.max_prijs {
+        None | Some(-1) => i32::MAX,
+        Some(prijs) if prijs >= 0 => prijs,
+        Some(_) => return fout(StatusCode::BAD_REQUEST, "ongeldige_query", "max_prijs moet -1, 0 of hoger zijn"),
+    };
+
+    let configuratie = config.lock().unwrap().clone();
+    let monitor = match Monitor::nieuw(configuratie) {
+        Ok(monitor) => monitor,
+        Err(e) => return interne_fout(e),
+    };
+
+    match monitor.zoek_artikel(zoekwoord, max_prijs).await {
+        Ok(advertenties) => {
+            let treffers: Vec<ZoekTreffer> = advertenties.iter().map(|a| ZoekTreffer::nieuw(a, &monitor)).collect();
+            warp::reply::json(&treffers).into_response()
+        }
+        Err(e) => fout(StatusCode::BAD_GATEWAY, "marktplaats_fout", format!("Zoeken bij Marktplaats mislukt: {}", e)),
+    }
+}
+
+// De versie van de wenslijst gaat als ETag mee, met If-Match wordt een aanpassing geweigerd als iemand
+// anders de wenslijst intussen heeft aangepast
+fn met_versie(antwoord: Response, versie: &str) -> Response {
+    warp::reply::with_header(antwoord, "ETag", format!("\"{}\"", versie)).into_response()
+}
+
+// Het id van een regel is zijn plek, na een verwijdering schuift de rest op. Aanpassen of verwijderen zonder
+// versie zou dan zomaar een andere regel kunnen raken, dus daar is If-Match verplicht ("*" als het echt niet uitmaakt)
+fn zonder_versie(if_match: &Option<String>) -> Option<Response> {
+    if_match.is_none().then(|| fout(StatusCode::PRECONDITION_REQUIRED, "versie_vereist", "Stuur de ETag van de wenslijst mee als If-Match, de id's kunnen intussen verschoven zijn"))
+}
+
+fn verwachte_versie(if_match: &Option<String>) -> Option<String> {
+    let waarde = if_match.as_deref()?.trim();
+    if waarde == "*" {
+        return None;
+    }
+    Some(waarde.trim_start_matches("W/").trim_matches('"').to_string())
+}
+
+fn wenslijst_fout(e: Box<dyn Error>) -> Response {
+    match e.downcast_ref::<wenslijst::Conflict>() {
+        Some(conflict) => fout(StatusCode::PRECONDITION_FAILED, "versie_conflict", conflict.to_string()),
+        None => interne_fout(e),
+    }
+}
+
+fn wenslijst_regel_onbekend(id: usize) -> Response {
+    fout(StatusCode::NOT_FOUND, "niet_gevonden", format!("Geen wenslijst regel met id {}", id))
+}
+
+fn lijst_wenslijst(config: Arc<Mutex<Configuratie>>) -> Response {
+    let pad = config.lock().unwrap().wenslijst_bestand.clone();
+    match wenslijst::lees(&pad) {
+        Ok((regels, versie)) => met_versie(warp::reply::json(&regels).into_response(), &versie),
+        Err(e) => interne_fout(e),
+    }
+}
+
+// Wat de check lus doet, voor de balk bovenaan de web interface en de planning in de wenslijst tab. Ook op /status
+pub fn haal_status(config: Arc<Mutex<Configuratie>>) -> Response {
+    let opslag_bestand = config.lock().unwrap().opslag_bestand.clone();
+    let vandaag = Local::now().format("%Y-%m-%d").to_string();
+    let treffers_vandaag = opslag::lees_resultaten(&opslag_bestand)
+        .unwrap_or_default()
+        .iter()
+        .filter(|r| r.tijdstempel.starts_with(&vandaag))
+        .count();
+    warp::reply::json(&toestand::status(treffers_vandaag)).into_response()
+}
+
+fn haal_statistieken(query: StatistiekenQuery, config: Arc<Mutex<Configuratie>>) -> Response {
+    let configuratie = config.lock().unwrap().clone();
+    let zoekwoorden: Vec<String> = match query.zoekwoord {
+        Some(zoekwoord) => vec![zoekwoord.trim().to_string()],
+        None => match wenslijst::lees(&configuratie.wenslijst_bestand) {
+            Ok((regels, _)) => {
+                let mut zoekwoorden: Vec<String> = Vec::new();
+                for regel in regels {
+                    if !zoekwoorden.contains(&regel.zoekopdracht.zoekwoord) {
+                        zoekwoorden.push(regel.zoekopdracht.zoekwoord);
+                    }
+                }
+                zoekwoorden
+            }
+            Err(e) => return interne_fout(e),
+        },
+    };
+
+    let resultaten = match opslag::lees_resultaten(&configuratie.opslag_bestand) {
+        Ok(resultaten) => resultaten,
+        Err(e) => return interne_fout(e),
+    };
+    let statistieken: Vec<statistieken::Statistieken> = zoekwoorden.iter()
+        .map(|zoekwoord| statistieken::bereken(&resultaten, zoekwoord))
+        .collect();
+    warp::reply::json(&statistieken).into_response()
+}
+
+// De body komt er later in, zie export.rs
+fn exporteer(filter: export::Filter, config: Arc<Mutex<Configuratie>>) -> Response {
+    let opslag_bestand = config.lock().unwrap().opslag_bestand.clone();
+    let bestandsnaam = format!("resultaten-{}.{}", Local::now().format("%Y-%m-%d"), filter.formaat.extensie());
+
+    let mut antwoord = Response::default();
+    let headers = antwoord.headers_mut();
+    headers.insert("content-type", filter.formaat.content_type().parse().unwrap());
+    headers.insert("content-disposition", format!("attachment; filename=\"{}\"", bestandsnaam).parse().unwrap());
+    antwoord.extensions_mut().insert(export::Opdracht { opslag_bestand, filter });
+    antwoord
+}
+
+fn haal_wenslijst_regel(id: usize, config: Arc<Mutex<Configuratie>>) -> Response {
+    let pad = config.lock().unwrap().wenslijst_bestand.clone();
+    match wenslijst::lees(&pad) {
+        Ok((regels, versie)) => match regels.into_iter().find(|r| r.id == id) {
+            Some(regel) => met_versie(warp::reply::json(&regel).into_response(), &versie),
+            None => wenslijst_regel_onbekend(id),
+        },
+        Err(e) => interne_fout(e),
+    }
+}
+
+fn maak_wenslijst_regel(if_match: Option<String>, invoer: WenslijstInvoer, config: Arc<Mutex<Configuratie>>) -> Response {
+    let zoekopdracht = wenslijst::Zoekopdracht::from(invoer);
+    if let Err(bericht) = wenslijst::controleer(&zoekopdracht) {
+        return fout(StatusCode::BAD_REQUEST, "ongeldige_invoer", bericht);
+    }
+
+    let pad = config.lock().unwrap().wenslijst_bestand.clone();
+    match wenslijst::voeg_toe(&pad, &zoekopdracht, verwachte_versie(&if_match).as_deref()) {
+        Ok((regel, versie)) => {
+            let locatie = format!("/api/v1/wenslijst/{}", regel.id);
+            let antwoord = warp::reply::with_header(json_met_status(&regel, StatusCode::CREATED), "Location", locatie);
+            met_versie(antwoord.into_response(), &versie)
+        }
+        Err(e) => wenslijst_fout(e),
+    }
+}
+
+fn vervang_wenslijst_regel(id: usize, if_match: Option<String>, invoer: WenslijstInvoer, config: Arc<Mutex<Configuratie>>) -> Response {
+    let zoekopdracht = wenslijst::Zoekopdracht::from(invoer);
+    if let Err(bericht) = wenslijst::controleer(&zoekopdracht) {
+        return fout(StatusCode::BAD_REQUEST, "ongeldige_invoer", bericht);
+    }
+
+    if let Some(antwoord) = zonder_versie(&if_match) {
+        return antwoord;
+    }
+    let pad = config.lock().unwrap().wenslijst_bestand.clone();
+    match wenslijst::pas_aan(&pad, id, &zoekopdracht, verwachte_versie(&if_match).as_deref()) {
+        Ok(Some((regel, versie))) => met_versie(warp::reply::json(&regel).into_response(), &versie),
+        Ok(None) => wenslijst_regel_onbekend(id),
+        Err(e) => wenslijst_fout(e),
+    }
+}
+
+fn verwijder_wenslijst_regel(id: usize, if_match: Option<String>, config: Arc<Mutex<Configuratie>>) -> Response {
+    if let Some(antwoord) = zonder_versie(&if_match) {
+        return antwoord;
+    }
+    let pad = config.lock().unwrap().wenslijst_bestand.clone();
+    match wenslijst::verwijder(&pad, id, verwachte_versie(&if_match).as_deref()) {
+        Ok(Some(versie)) => met_versie(geen_inhoud(), &versie),
+        Ok(None) => wenslijst_regel_onbekend(id),
+        Err(e) => wenslijst_fout(e),
+    }
+}
+
+// Alleen de meegestuurde instellingen veranderen, de rest blijft zoals het was
+fn pas_config_aan(aanpassing: serde_json::Map<String, serde_json::Value>, config: Arc<Mutex<Configuratie>>) -> Response {
+    let mut configuratie = config.lock().unwrap();
+
+    if let Some(sleutel) = aanpassing.keys().find(|sleutel| GEHEIME_INSTELLINGEN.contains(&sleutel.as_str()) || VASTE_INSTELLINGEN.contains(&sleutel.as_str())) {
+        return fout(StatusCode::BAD_REQUEST, "ongeldige_invoer", format!("{} kan alleen in config.toml aangepast worden", sleutel));
+    }
+    let nieuw = match configuratie.met_aanpassingen(aanpassing) {
+        Ok(nieuw) => nieuw,
+        Err(bericht) => return fout(StatusCode::BAD_REQUEST, "ongeldige_invoer", bericht),
+    };
+
+    *configuratie = nieuw;
+    web::bewaar_config(&configuratie);
+    warp::reply::json(&web::zonder_geheimen(&configuratie)).into_response()
+}
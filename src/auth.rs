@@ -0,0 +1,168 @@
+// Optionele login voor de web interface. Staat aan zodra er een web_token of web_wachtwoord(_hash) in config.toml staat.
+// Browsers loggen in en krijgen een sessie cookie + CSRF token, scripts sturen een van de api_tokens mee
+// als "Authorization: Bearer <token>" en hebben geen sessie of CSRF token nodig.
+
+use crate::Configuratie;
+use argon2::Argon2;
+use password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use password_hash::rand_core::{OsRng, RngCore};
+use chrono::{DateTime, Duration, Local};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use warp::http::Method;
+use warp::{Filter, Rejection};
+
+pub const SESSIE_COOKIE: &str = "mpm_sessie";
+const SESSIE_DUUR_DAGEN: i64 = 7;
+
+#[derive(Debug, Clone)]
+pub struct Sessie {
+    pub csrf_token: String,
+    verloopt: DateTime<Local>,
+}
+
+pub type Sessies = Arc<Mutex<HashMap<String, Sessie>>>;
+
+#[derive(Debug)]
+pub struct NietIngelogd;
+impl warp::reject::Reject for NietIngelogd {}
+
+#[derive(Debug)]
+pub struct OngeldigCsrf;
+impl warp::reject::Reject for OngeldigCsrf {}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginVerzoek {
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub gebruiker: String,
+    #[serde(default)]
+    pub wachtwoord: String,
+}
+
+pub fn staat_aan(configuratie: &Configuratie) -> bool {
+    !configuratie.web_token.is_empty() || !configuratie.web_wachtwoord_hash.is_empty()
+}
+
+pub fn willekeurig_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn hash_wachtwoord(wachtwoord: &str) -> Result<String, Box<dyn Error>> {
+    let zout = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(wachtwoord.as_bytes(), &zout)
+        .map_err(|e| format!("Kon wachtwoord niet hashen: {}", e))?;
+    Ok(hash.to_string())
+}
+
+fn controleer_wachtwoord(wachtwoord: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|hash| Argon2::default().verify_password(wachtwoord.as_bytes(), &hash).is_ok())
+        .unwrap_or(false)
+}
+
+// Zodat de tijd die een vergelijking kost niet verraadt hoeveel tekens er goed waren
+fn gelijk(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |verschil, (x, y)| verschil | (x ^ y)) == 0
+}
+
+pub fn controleer_login(configuratie: &Configuratie, verzoek: &LoginVerzoek) -> bool {
+    let token_goed = !configuratie.web_token.is_empty() && gelijk(&verzoek.token, &configuratie.web_token);
+    let wachtwoord_goed = !configuratie.web_wachtwoord_hash.is_empty()
+        && gelijk(&verzoek.gebruiker, &configuratie.web_gebruiker)
+        && controleer_wachtwoord(&verzoek.wachtwoord, &configuratie.web_wachtwoord_hash);
+    token_goed || wachtwoord_goed
+}
+
+pub fn nieuwe_sessie(sessies: &Sessies) -> String {
+    let id = willekeurig_token();
+    let nu = Local::now();
+    let mut sessies = sessies.lock().unwrap();
+    sessies.retain(|_, sessie| sessie.verloopt > nu);
+    sessies.insert(id.clone(), Sessie {
+        csrf_token: willekeurig_token(),
+        verloopt: nu + Duration::days(SESSIE_DUUR_DAGEN),
+    });
+    id
+}
+
+pub fn zoek_sessie(sessies: &Sessies, id: Option<&str>) -> Option<Sessie> {
+    let sessie = sessies.lock().unwrap().get(id?).cloned()?;
+    (sessie.verloopt > Local::now()).then_some(sessie)
+}
+
+pub fn beeindig_sessie(sessies: &Sessies, id: Option<&str>) {
+    if let Some(id) = id {
+        sessies.lock().unwrap().remove(id);
+    }
+}
+
+pub fn sessie_cookie(id: &str, tls: bool) -> String {
+    format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}{}",
+        SESSIE_COOKIE, id, SESSIE_DUUR_DAGEN * 24 * 3600, if tls { "; Secure" } else { "" }
+    )
+}
+
+pub fn verlopen_cookie() -> String {
+    format!("{}=; Path=/; HttpOnly; SameSite=Strict; Max-Age=0", SESSIE_COOKIE)
+}
+
+// Feed readers kunnen niet inloggen. Met de login aan is er dus een feed_token nodig, anders staan de feeds dicht
+pub fn feed_toegestaan(configuratie: &Configuratie, token: Option<&str>) -> bool {
+    if configuratie.feed_token.is_empty() {
+        return !staat_aan(configuratie);
+    }
+    token.is_some_and(|token| gelijk(token, &configuratie.feed_token))
+}
+
+fn controleer_toegang(
+    configuratie: &Configuratie,
+    sessies: &Sessies,
+    methode: &Method,
+    cookie: Option<&str>,
+    autorisatie: Option<&str>,
+    csrf: Option<&str>,
+) -> Result<(), Rejection> {
+    if !staat_aan(configuratie) {
+        return Ok(());
+    }
+
+    if let Some(token) = autorisatie.and_then(|a| a.strip_prefix("Bearer ")) {
+        return match configuratie.api_tokens.iter().any(|t| !t.is_empty() && gelijk(token.trim(), t)) {
+            true => Ok(()),
+            false => Err(warp::reject::custom(NietIngelogd)),
+        };
+    }
+
+    let sessie = zoek_sessie(sessies, cookie).ok_or_else(|| warp::reject::custom(NietIngelogd))?;
+    if methode == Method::GET || methode == Method::HEAD {
+        return Ok(());
+    }
+
+    // Alles wat iets aanpast moet het CSRF token van de sessie meesturen
+    match csrf {
+        Some(csrf) if gelijk(csrf, &sessie.csrf_token) => Ok(()),
+        _ => Err(warp::reject::custom(OngeldigCsrf)),
+    }
+}
+
+// Zet dit voor alle routes die achter de login horen
+pub fn vereis_toegang(config: Arc<Mutex<Configuratie>>, sessies: Sessies) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::method()
+        .and(warp::cookie::optional::<String>(SESSIE_COOKIE))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::header::optional::<String>("x-csrf-token"))
+        .and_then(move |methode: Method, cookie: Option<String>, autorisatie: Option<String>, csrf: Option<String>| {
+            let configuratie = config.lock().unwrap().clone();
+            let resultaat = controleer_toegang(&configuratie, &sessies, &methode, cookie.as_deref(), autorisatie.as_deref(), csrf.as_deref());
+            async move { resultaat }
+        })
+        .untuple_one()
+}
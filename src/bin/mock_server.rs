@@ -0,0 +1,122 @@
+// Een nep versie van Marktplaats' zoek API, om de monitor te testen zonder netwerk.
+// Gebruik: mock_server [poort] [advertenties.json]
+// Serveert /lrp/api/search en de advertentie pagina's (/v/...) voor het ophalen van details.
+// Zet daarna api_basis_url (en link_basis_url) in config.toml op http://127.0.0.1:<poort>
+
+use std::error::Error;
+use std::sync::Arc;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use warp::Filter;
+
+const STANDAARD_ADVERTENTIES: &str = include_str!("mock_advertenties.json");
+
+#[derive(Debug, Deserialize)]
+struct ZoekQuery {
+    limit: Option<usize>,
+    offset: Option<usize>,
+    #[serde(rename = "priceFrom")]
+    prijs_van: Option<i64>,
+    #[serde(rename = "priceTo")]
+    prijs_tot: Option<i64>,
+    query: Option<String>,
+}
+
+// "Steam Deck" en "steamdeck" moeten hetzelfde vinden, net zoals bij de echte site
+fn normaliseer(tekst: &str) -> String {
+    tekst.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn komt_overeen(advertentie: &Value, query: &ZoekQuery) -> bool {
+    // Zonder prijs (bieden etc.) filtert Marktplaats ook niet
+    if let Some(centen) = advertentie["priceInfo"]["priceCents"].as_i64()
+        && (centen < query.prijs_van.unwrap_or(0) || centen > query.prijs_tot.unwrap_or(i64::MAX)) {
+        return false;
+    }
+
+    match query.query.as_deref().map(normaliseer) {
+        Some(zoekwoord) if !zoekwoord.is_empty() => {
+            let titel = normaliseer(advertentie["title"].as_str().unwrap_or(""));
+            let beschrijving = normaliseer(advertentie["description"].as_str().unwrap_or(""));
+            titel.contains(&zoekwoord) || beschrijving.contains(&zoekwoord)
+        }
+        _ => true,
+    }
+}
+
+// De echte zoek API geeft een ingekorte beschrijving zonder HTML en geen statistieken.
+// Velden in "alleenOpPagina" (bijv. ["sellerInformation"]) staan alleen op de advertentie pagina
+fn als_zoekresultaat(advertentie: &Value) -> Value {
+    let mut resultaat = advertentie.clone();
+    if let Some(object) = resultaat.as_object_mut() {
+        object.remove("stats");
+        if let Some(Value::Array(velden)) = object.remove("alleenOpPagina") {
+            for veld in velden.iter().filter_map(Value::as_str) {
+                object.remove(veld);
+            }
+        }
+        if let Some(beschrijving) = object.get("description").and_then(|d| d.as_str()) {
+            let kort: String = beschrijving.split("<br>").next().unwrap_or("").chars().take(60).collect();
+            object.insert("description".to_string(), Value::String(kort));
+        }
+    }
+    resultaat
+}
+
+fn zoek(query: ZoekQuery, advertenties: &[Value]) -> Value {
+    let gevonden: Vec<&Value> = advertenties.iter()
+        .filter(|a| komt_overeen(a, &query))
+        .collect();
+
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(30);
+    let pagina: Vec<Value> = gevonden.iter().skip(offset).take(limit).map(|a| als_zoekresultaat(a)).collect();
+
+    json!({
+        "listings": pagina,
+        "totalResultCount": gevonden.len(),
+    })
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut argumenten = std::env::args().skip(1);
+    let poort: u16 = match argumenten.next() {
+        Some(p) => p.parse()?,
+        None => 6601,
+    };
+    let inhoud = match argumenten.next() {
+        Some(pad) => std::fs::read_to_string(pad)?,
+        None => STANDAARD_ADVERTENTIES.to_string(),
+    };
+
+    let data: Value = serde_json::from_str(&inhoud)?;
+    let advertenties = Arc::new(data["listings"].as_array().cloned().unwrap_or_default());
+    println!("Mock server met {} advertenties op http://127.0.0.1:{}", advertenties.len(), poort);
+
+    let zoek_advertenties = advertenties.clone();
+    let zoek_route = warp::get()
+        .and(warp::path!("lrp" / "api" / "search"))
+        .and(warp::query::<ZoekQuery>())
+        .map(move |query: ZoekQuery| warp::reply::json(&zoek(query, &zoek_advertenties)));
+
+    let pagina_route = warp::get()
+        .and(warp::path("v"))
+        .and(warp::path::tail())
+        .map(move |pad: warp::path::Tail| {
+            let vip_url = format!("/v/{}", pad.as_str());
+            match advertenties.iter().find(|a| a["vipUrl"].as_str() == Some(vip_url.as_str())) {
+                Some(advertentie) => {
+                    let html = format!(
+                        "<html><head><script>window.__CONFIG__ = {};</script></head><body></body></html>",
+                        json!({ "listing": advertentie })
+                    );
+                    warp::reply::with_status(warp::reply::html(html), warp::http::StatusCode::OK)
+                }
+                None => warp::reply::with_status(warp::reply::html("Niet gevonden".to_string()), warp::http::StatusCode::NOT_FOUND),
+            }
+        });
+
+    warp::serve(zoek_route.or(pagina_route)).run(([127, 0, 0, 1], poort)).await;
+    Ok(())
+}
@@ -0,0 +1,326 @@
+// Alle (of gefilterde) resultaten exporteren als CSV, JSON of NDJSON, voor spreadsheets en notebooks.
+// Regel voor regel uit results.jsonl, zodat een grote geschiedenis nooit helemaal in het geheugen hoeft.
+//
+// warp 0.4 kan zelf geen body streamen. De export route geeft daarom een leeg antwoord met een Opdracht als
+// extension terug, en de server (zie web.rs) vervangt de body door `stream`.
+
+use crate::{Configuratie, web};
+use crate::opslag::Resultaat;
+use bytes::Bytes;
+use http_body::{Body, Frame};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+const KOLOMMEN: &[&str] = &[
+    "item_id", "titel", "beschrijving", "prijs_centen", "prijs_type", "stad", "afstand_meters", "link", "vip_url",
+    "afbeelding_urls", "verkoper_id", "verkoper_naam", "zoekwoord", "zoekwoorden", "max_prijs", "tijdstempel", "herplaatsing_van",
+];
+
+const BLOK_GROOTTE: usize = 64 * 1024; // Zoveel bytes per stuk naar de client
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Formaat {
+    #[default]
+    Csv,
+    Json,
+    Ndjson,
+}
+
+impl Formaat {
+    pub fn parseer(tekst: &str) -> Option<Formaat> {
+        match tekst.to_lowercase().as_str() {
+            "csv" => Some(Formaat::Csv),
+            "json" => Some(Formaat::Json),
+            "ndjson" | "jsonl" => Some(Formaat::Ndjson),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Formaat::Csv => "text/csv; charset=utf-8",
+            Formaat::Json => "application/json",
+            Formaat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    pub fn extensie(&self) -> &'static str {
+        match self {
+            Formaat::Csv => "csv",
+            Formaat::Json => "json",
+            Formaat::Ndjson => "ndjson",
+        }
+    }
+}
+
+// Query van /api/v1/export, de filters zijn dezelfde als bij /resultaten
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Filter {
+    #[serde(default)]
+    pub formaat: Formaat,
+    pub zoekwoord: Option<String>,
+    pub van: Option<String>, // Datum als 2025-10-01, op de tijdstempel van het vinden
+    pub tot: Option<String>,
+    pub prijs_type: Option<String>, // Bijv. "FIXED,BID"
+    pub prijs_min: Option<f64>,     // In euro's
+    pub prijs_max: Option<f64>,
+}
+
+impl Filter {
+    fn laat_door(&self, resultaat: &Resultaat) -> bool {
+        let datum = resultaat.tijdstempel.get(..10).unwrap_or(&resultaat.tijdstempel);
+        let prijs_info = &resultaat.advertentie.prijs_info;
+
+        if let Some(zoekwoord) = self.zoekwoord.as_deref().filter(|z| !z.is_empty())
+            && !resultaat.zoekwoorden.iter().any(|z| z == zoekwoord) {
+            return false;
+        }
+        if self.van.as_deref().is_some_and(|van| !van.is_empty() && datum < van) {
+            return false;
+        }
+        if self.tot.as_deref().is_some_and(|tot| !tot.is_empty() && datum > tot) {
+            return false;
+        }
+        if let Some(prijs_types) = self.prijs_type.as_deref().filter(|p| !p.is_empty())
+            && !prijs_types.split(',').any(|p| p.trim().eq_ignore_ascii_case(&prijs_info.prijs_type)) {
+            return false;
+        }
+        // Zonder prijs valt af zodra er op prijs gefilterd wordt, net als bij /resultaten
+        if self.prijs_min.is_some() || self.prijs_max.is_some() {
+            let min = (self.prijs_min.unwrap_or(0.0) * 100.0).round() as i32;
+            let max = self.prijs_max.map(|p| (p * 100.0).round() as i32).unwrap_or(i32::MAX);
+            if !prijs_info.prijs_centen.is_some_and(|c| c >= min && c <= max) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// Elk veld van de advertentie, plat, met het zoekwoord en wanneer hij gevonden is
+#[derive(Debug, Serialize)]
+struct ExportRegel<'a> {
+    item_id: &'a str,
+    titel: &'a str,
+    beschrijving: Option<&'a str>,
+    prijs_centen: Option<i32>,
+    prijs_type: &'a str,
+    stad: Option<&'a str>,
+    afstand_meters: Option<i32>,
+    link: &'a str,
+    vip_url: &'a str,
+    afbeelding_urls: &'a [String],
+    verkoper_id: Option<String>, // Net als in de web interface uit de details als de zoek API hem niet gaf
+    verkoper_naam: Option<String>,
+    zoekwoord: &'a str,
+    zoekwoorden: &'a [String],
+    max_prijs: Option<i32>, // Leeg is geen maximum
+    tijdstempel: &'a str,
+    herplaatsing_van: Option<&'a str>,
+}
+
+impl<'a> ExportRegel<'a> {
+    fn van(resultaat: &'a Resultaat) -> Self {
+        let advertentie = &resultaat.advertentie;
+        ExportRegel {
+            item_id: &advertentie.item_id,
+            titel: &advertentie.titel,
+            beschrijving: advertentie.beschrijving.as_deref(),
+            prijs_centen: advertentie.prijs_info.prijs_centen,
+            prijs_type: &advertentie.prijs_info.prijs_type,
+            stad: advertentie.locatie.stad_naam.as_deref(),
+            afstand_meters: advertentie.locatie.afstand_meters,
+            link: &resultaat.link,
+            vip_url: &advertentie.vip_url,
+            afbeelding_urls: advertentie.afbeelding_urls.as_deref().unwrap_or_default(),
+            verkoper_id: web::verkoper_id(resultaat),
+            verkoper_naam: web::verkoper_naam(resultaat),
+            zoekwoord: &resultaat.zoekwoord,
+            zoekwoorden: &resultaat.zoekwoorden,
+            max_prijs: (resultaat.max_prijs != i32::MAX).then_some(resultaat.max_prijs),
+            tijdstempel: &resultaat.tijdstempel,
+            herplaatsing_van: resultaat.herplaatsing_van.as_deref(),
+        }
+    }
+
+    // Zelfde volgorde als KOLOMMEN, lijsten gescheiden door " | "
+    fn csv_velden(&self) -> Vec<String> {
+        let getal = |g: Option<i64>| g.map(|g| g.to_string()).unwrap_or_default();
+        vec![
+            self.item_id.to_string(),
+            self.titel.to_string(),
+            self.beschrijving.unwrap_or_default().to_string(),
+            getal(self.prijs_centen.map(i64::from)),
+            self.prijs_type.to_string(),
+            self.stad.unwrap_or_default().to_string(),
+            getal(self.afstand_meters.map(i64::from)),
+            self.link.to_string(),
+            self.vip_url.to_string(),
+            self.afbeelding_urls.join(" | "),
+            self.verkoper_id.clone().unwrap_or_default(),
+            self.verkoper_naam.clone().unwrap_or_default(),
+            self.zoekwoord.to_string(),
+            self.zoekwoorden.join(" | "),
+            getal(self.max_prijs.map(i64::from)),
+            self.tijdstempel.to_string(),
+            self.herplaatsing_van.unwrap_or_default().to_string(),
+        ]
+    }
+}
+
+fn csv_veld(veld: &str) -> String {
+    if veld.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", veld.replace('"', "\"\""))
+    } else {
+        veld.to_string()
+    }
+}
+
+fn schrijf_csv_regel(uit: &mut impl Write, velden: &[String]) -> io::Result<()> {
+    let regel: Vec<String> = velden.iter().map(|v| csv_veld(v)).collect();
+    uit.write_all(regel.join(",").as_bytes())?;
+    uit.write_all(b"\r\n")
+}
+
+// Schrijft de export naar `uit` en geeft het aantal regels terug. Kapotte regels slaat lees_resultaten
+// ook over, dus hier net zo.
+pub fn schrijf(opslag_bestand: &str, filter: &Filter, uit: &mut impl Write) -> Result<usize, Box<dyn Error>> {
+    let lezer = if Path::new(opslag_bestand).exists() {
+        Some(BufReader::new(File::open(opslag_bestand)?))
+    } else {
+        None
+    };
+
+    match filter.formaat {
+        Formaat::Csv => schrijf_csv_regel(uit, &KOLOMMEN.iter().map(|k| k.to_string()).collect::<Vec<_>>())?,
+        Formaat::Json => uit.write_all(b"[")?,
+        Formaat::Ndjson => {}
+    }
+
+    let mut aantal = 0;
+    for lijn in lezer.into_iter().flat_map(|lezer| lezer.lines()) {
+        let lijn = lijn?;
+        let Ok(mut resultaat) = serde_json::from_str::<Resultaat>(&lijn) else {
+            continue;
+        };
+        if resultaat.zoekwoorden.is_empty() {
+            resultaat.zoekwoorden.push(resultaat.zoekwoord.clone());
+        }
+        if !filter.laat_door(&resultaat) {
+            continue;
+        }
+
+        let regel = ExportRegel::van(&resultaat);
+        match filter.formaat {
+            Formaat::Csv => schrijf_csv_regel(uit, &regel.csv_velden())?,
+            Formaat::Json => {
+                uit.write_all(if aantal == 0 { b"\n" } else { b",\n" })?;
+                serde_json::to_writer(&mut *uit, &regel)?;
+            }
+            Formaat::Ndjson => {
+                serde_json::to_writer(&mut *uit, &regel)?;
+                uit.write_all(b"\n")?;
+            }
+        }
+        aantal += 1;
+    }
+
+    if filter.formaat == Formaat::Json {
+        uit.write_all(if aantal == 0 { b"]\n" } else { b"\n]\n" })?;
+    }
+    uit.flush()?;
+    Ok(aantal)
+}
+
+// Wat de export route aan de server doorgeeft
+#[derive(Debug, Clone)]
+pub struct Opdracht {
+    pub opslag_bestand: String,
+    pub filter: Filter,
+}
+
+// Schrijft in blokken naar de body, blokkeert als de client niet bijhoudt
+struct KanaalSchrijver {
+    buffer: Vec<u8>,
+    zender: mpsc::Sender<io::Result<Bytes>>,
+}
+
+impl Write for KanaalSchrijver {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(data);
+        if self.buffer.len() >= BLOK_GROOTTE {
+            self.flush()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let blok = Bytes::from(std::mem::take(&mut self.buffer));
+        self.zender.blocking_send(Ok(blok)).map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client is weg"))
+    }
+}
+
+pub struct ExportBody {
+    ontvanger: mpsc::Receiver<io::Result<Bytes>>,
+}
+
+impl Body for ExportBody {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, io::Error>>> {
+        self.ontvanger.poll_recv(cx).map(|blok| blok.map(|blok| blok.map(Frame::data)))
+    }
+}
+
+// Een fout halverwege breekt de verbinding af, zodat de client geen half bestand voor een heel bestand aanziet
+pub fn stream(opdracht: Opdracht) -> ExportBody {
+    let (zender, ontvanger) = mpsc::channel(4);
+    tokio::task::spawn_blocking(move || {
+        let mut schrijver = KanaalSchrijver { buffer: Vec::with_capacity(BLOK_GROOTTE), zender: zender.clone() };
+        if let Err(e) = schrijf(&opdracht.opslag_bestand, &opdracht.filter, &mut schrijver) {
+            eprintln!("Export afgebroken: {}", e);
+            zender.blocking_send(Err(io::Error::other(e.to_string()))).ok();
+        }
+    });
+    ExportBody { ontvanger }
+}
+
+// `marktplaats-monitor export [csv|json|ndjson] [--zoekwoord Z] [--van DATUM] [--tot DATUM] [--prijs-type T] [--prijs-min EURO] [--prijs-max EURO] [--uitvoer PAD]`
+pub fn opdrachtregel(configuratie: &Configuratie, argumenten: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut filter = Filter::default();
+    let mut uitvoer = None;
+    let prijs = |tekst: String| tekst.parse::<f64>().map_err(|_| format!("Ongeldige prijs '{}'", tekst)); // In euro's, zoals prijs_min
+
+    let mut argumenten = argumenten.iter();
+    while let Some(argument) = argumenten.next() {
+        let mut waarde = || argumenten.next().cloned().ok_or_else(|| format!("{} heeft een waarde nodig", argument));
+        match argument.as_str() {
+            "--zoekwoord" => filter.zoekwoord = Some(waarde()?),
+            "--van" => filter.van = Some(waarde()?),
+            "--tot" => filter.tot = Some(waarde()?),
+            "--prijs-type" => filter.prijs_type = Some(waarde()?),
+            "--prijs-min" => filter.prijs_min = Some(prijs(waarde()?)?),
+            "--prijs-max" => filter.prijs_max = Some(prijs(waarde()?)?),
+            "--uitvoer" | "-o" => uitvoer = Some(waarde()?),
+            formaat => filter.formaat = Formaat::parseer(formaat).ok_or_else(|| format!("Onbekend formaat of optie '{}'", formaat))?,
+        }
+    }
+
+    let aantal = match &uitvoer {
+        Some(pad) => schrijf(&configuratie.opslag_bestand, &filter, &mut BufWriter::new(File::create(pad)?))?,
+        None => schrijf(&configuratie.opslag_bestand, &filter, &mut BufWriter::new(io::stdout().lock()))?,
+    };
+    eprintln!("{} resultaten geëxporteerd{}", aantal, uitvoer.map(|pad| format!(" naar {}", pad)).unwrap_or_default());
+    Ok(())
+}
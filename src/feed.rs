@@ -0,0 +1,93 @@
+// Atom feeds van de nieuwste resultaten, alles samen of per wenslijst zoekwoord, voor feed readers.
+// Gemaakt uit results.jsonl, dus precies wat bewaar_resultaat heeft opgeslagen.
+
+use crate::opslag::{ArtikelStatus, Resultaat, Staat};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use std::collections::HashMap;
+
+const MAX_ITEMS: usize = 50;
+
+fn xml(tekst: &str) -> String {
+    tekst.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// Tijdstempels zijn lokale tijd als "%Y-%m-%d %H:%M:%S", Atom wil RFC 3339 met tijdzone
+fn rfc3339(tijdstempel: &str) -> String {
+    NaiveDateTime::parse_from_str(tijdstempel, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .and_then(|tijd| Local.from_local_datetime(&tijd).earliest())
+        .unwrap_or_else(Local::now)
+        .to_rfc3339()
+}
+
+// Marktplaats geeft afbeeldingen zonder schema ("//images...."), niet elke feed reader snapt dat
+fn volledige_url(url: &str) -> String {
+    match url.strip_prefix("//") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    }
+}
+
+fn inhoud(resultaat: &Resultaat) -> String {
+    let advertentie = &resultaat.advertentie;
+    let mut html = String::new();
+    if let Some(afbeelding) = advertentie.afbeelding_urls.as_ref().and_then(|urls| urls.first()) {
+        html.push_str(&format!("<p><img src=\"{}\" alt=\"{}\"></p>", xml(&volledige_url(afbeelding)), xml(&advertentie.titel)));
+    }
+    html.push_str(&format!("<p><b>Prijs:</b> {}{}</p>", xml(&advertentie.prijs_weergave()), advertentie.prijs_type_label()));
+    if let Some(deal) = &resultaat.deal {
+        html.push_str(&format!("<p><b>Deal:</b> {}</p>", xml(&deal.omschrijving())));
+    }
+    html.push_str(&format!(
+        "<p><b>Locatie:</b> {} ({})</p>",
+        xml(advertentie.locatie.stad_naam.as_deref().unwrap_or("Onbekend")),
+        advertentie.afstand_weergave()
+    ));
+    html.push_str(&format!("<p>{}</p>", xml(&advertentie.korte_beschrijving())));
+    html.push_str(&format!("<p>Gevonden met: {}</p>", xml(&resultaat.zoekwoorden.join(", "))));
+    html
+}
+
+fn entry(resultaat: &Resultaat) -> String {
+    let advertentie = &resultaat.advertentie;
+    let categorieen: String = resultaat.zoekwoorden.iter()
+        .map(|zoekwoord| format!("    <category term=\"{}\"/>\n", xml(zoekwoord)))
+        .collect();
+    format!(
+        "  <entry>\n    <id>urn:marktplaats:{}</id>\n    <title>{}</title>\n    <link href=\"{}\"/>\n    <updated>{}</updated>\n{}    <content type=\"html\">{}</content>\n  </entry>\n",
+        xml(&advertentie.item_id),
+        xml(&format!("{} - {}", advertentie.titel, advertentie.prijs_weergave())),
+        xml(&resultaat.link),
+        rfc3339(&resultaat.tijdstempel),
+        categorieen,
+        xml(&inhoud(resultaat)),
+    )
+}
+
+// `basis_url` is bijv. "http://127.0.0.1:6600", `pad` het pad van de feed zelf. Verborgen resultaten komen er niet in
+pub fn atom(resultaten: &[Resultaat], statussen: &HashMap<String, ArtikelStatus>, zoekwoord: Option<&str>, basis_url: &str, pad: &str) -> String {
+    let nieuwste: Vec<&Resultaat> = resultaten.iter()
+        .rev()
+        .filter(|r| zoekwoord.is_none_or(|zoekwoord| r.zoekwoorden.iter().any(|z| z == zoekwoord)))
+        .filter(|r| statussen.get(&r.advertentie.item_id).is_none_or(|s| s.staat != Staat::Verborgen))
+        .take(MAX_ITEMS)
+        .collect();
+
+    let titel = match zoekwoord {
+        Some(zoekwoord) => format!("Marktplaats Monitor: {}", zoekwoord),
+        None => "Marktplaats Monitor".to_string(),
+    };
+    let bijgewerkt = nieuwste.first()
+        .map(|r| rfc3339(&r.tijdstempel))
+        .unwrap_or_else(|| Local::now().to_rfc3339());
+    let entries: String = nieuwste.iter().map(|r| entry(r)).collect();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n  <id>{}{}</id>\n  <title>{}</title>\n  <updated>{}</updated>\n  <author><name>Marktplaats Monitor</name></author>\n  <link rel=\"self\" href=\"{}{}\"/>\n  <link rel=\"alternate\" href=\"{}/\"/>\n{}</feed>\n",
+        xml(basis_url), xml(pad), xml(&titel), bijgewerkt, xml(basis_url), xml(pad), xml(basis_url), entries
+    )
+}
@@ -1,8 +1,8 @@
-// _      ____  ____  _  __ _____ ____  _     ____  ____ _____ ____    _      ____  _      _ _____ ____  ____ 
-/// \__/|/  _ \/  __\/ |/ //__ __Y  __\/ \   /  _ \/  _ Y__ __Y ___\  / \__/|/  _ \/ \  /|/ Y__ __Y  _ \/  __\
-//| |\/||| / \||  \/||   /   / \ |  \/|| |   | / \|| / \| / \ |    \  | |\/||| / \|| |\ ||| | / \ | / \||  \/|
-//| |  ||| |-|||    /|   \   | | |  __/| |_/\| |-||| |-|| | | \___ |  | |  ||| \_/|| | \||| | | | | \_/||    /
-//\_/  \|\_/ \|\_/\_\\_|\_\  \_/ \_/   \____/\_/ \|\_/ \| \_/ \____/  \_/  \|\____/\_/  \|\_/ \_/ \____/\_/\_\
+//  _      ____  ____  _  __ _____ ____  _     ____  ____ _____ ____    _      ____  _      _ _____ ____  ____ 
+// / \__/|/  _ \/  __\/ |/ //__ __Y  __\/ \   /  _ \/  _ Y__ __Y ___\  / \__/|/  _ \/ \  /|/ Y__ __Y  _ \/  __\
+// | |\/||| / \||  \/||   /   / \ |  \/|| |   | / \|| / \| / \ |    \  | |\/||| / \|| |\ ||| | / \ | / \||  \/|
+// | |  ||| |-|||    /|   \   | | |  __/| |_/\| |-||| |-|| | | \___ |  | |  ||| \_/|| | \||| | | | | \_/||    /
+// \_/  \|\_/ \|\_/\_\\_|\_\  \_/ \_/   \____/\_/ \|\_/ \| \_/ \____/  \_/  \|\____/\_/  \|\_/ \_/ \____/\_/\_\
                                                                                                             
   
 // Dit programma is niet geschreven met slechte bedoelingen.
@@ -14,25 +14,44 @@
 // The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
 // THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
+mod api;
+mod auth;
+mod export;
+mod feed;
+mod metrieken;
+mod opdrachtregel;
+mod openapi;
+mod opslag;
+mod planning;
+mod prijzen;
+mod statistieken;
+mod tls;
+mod toestand;
 mod web;
+mod wenslijst;
+mod zoekindex;
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{File, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::thread;
 use std::time::Duration;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::error::Error;
 use serde::{Deserialize, Serialize};
-use reqwest;
+use serde_json::Value;
 use chrono::Local;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)] // Oudere config.toml bestanden missen nieuwere velden
 pub struct Configuratie {
     pub postcode: String, 
     pub afstand_km: u32,
     pub check_interval_seconden: u64,
+    pub adaptief_interval: bool, // Interval per zoekwoord aanpassen aan hoe vaak er iets nieuws is
+    pub min_interval_seconden: u64,
+    pub max_interval_seconden: u64,
     pub max_advertenties_per_zoekopdracht: u32,
     pub wenslijst_bestand: String,
     pub resultaten_bestand: String,
@@ -41,7 +60,52 @@ pub struct Configuratie {
     pub toon_gratis: bool,
     pub toon_zie_beschrijving: bool,
     pub web_poort: u16,
+    pub web_adres: String, // 0.0.0.0 om ook vanaf andere apparaten in het netwerk bij te kunnen, kan alleen met login
+    pub web_tls: bool,
+    pub tls_certificaat: String, // Worden zelf ondertekend aangemaakt als ze allebei nog niet bestaan
+    pub tls_sleutel: String,
     pub web_interface_aan: bool,
+    pub api_basis_url: String,
+    pub link_basis_url: String,
+    pub opslag_bestand: String,
+    pub status_bestand: String,
+    pub prijzen_bestand: String, // Geziene prijzen per zoekwoord, voor de deal score
+    pub haal_details_op: bool,
+    pub geblokkeerde_verkopers: Vec<String>, // Verkoper id of naam
+    pub vertrouwde_verkopers: Vec<String>,
+    pub alleen_vertrouwde_verkopers: bool,
+    pub onderdruk_herplaatsingen: bool,
+    pub web_token: String, // Gedeeld token om in te loggen, leeg is uit
+    pub web_gebruiker: String,
+    pub web_wachtwoord: String, // Wordt bij het starten gehasht naar web_wachtwoord_hash en dan leeg gemaakt
+    pub web_wachtwoord_hash: String,
+    pub api_tokens: Vec<String>, // Voor scripts, als "Authorization: Bearer <token>"
+    pub feed_token: String, // Voor feed readers, als ?token=... achter de feed url. Leeg is geen token nodig zolang de login uit staat
+    pub publieke_url: String, // Waar de monitor van buitenaf te bereiken is, voor de links in de feeds. Leeg is web_adres:web_poort
+    pub data_map: String, // Hier staan de wenslijst, resultaten enz. als ze geen absoluut pad hebben. Leeg is op Linux de XDG data map
+    #[serde(skip)]
+    pub config_bestand: String, // Waar de config vandaan kwam, daar worden aanpassingen ook weer opgeslagen
+    #[serde(skip)]
+    pub herkomst: Herkomst,
+}
+
+// Welke instellingen uit config.toml kwamen en welke uit MPM_* of de opdrachtregel, voor `config show --effective`
+// en zodat die laatste niet in config.toml belanden als er iets anders opgeslagen wordt
+#[derive(Debug, Clone, Default)]
+pub struct Herkomst {
+    pub bestand: toml::Table,                            // Zoals config.toml bij het laden was
+    pub overschreven: BTreeMap<String, (String, Value)>, // Instelling -> (MPM_... of --..., waarde)
+    pub paden: BTreeMap<String, (String, String)>,       // Bestand -> (zoals ingesteld, pad in de data map)
+}
+
+impl Herkomst {
+    pub fn van(&self, sleutel: &str, config_bestand: &str) -> String {
+        match self.overschreven.get(sleutel) {
+            Some((bron, _)) => bron.clone(),
+            None if self.bestand.contains_key(sleutel) => config_bestand.to_string(),
+            None => "standaard".to_string(),
+        }
+    }
 }
 
 
@@ -51,6 +115,9 @@ impl Default for Configuratie {
             postcode: "3032SG".to_string(), // Dit is een verzonnen postcode 
             afstand_km: 8,
             check_interval_seconden: 300, 
+            adaptief_interval: false,
+            min_interval_seconden: 60,
+            max_interval_seconden: 3600,
             max_advertenties_per_zoekopdracht: 50,
             wenslijst_bestand: "wishlist.txt".to_string(),
             resultaten_bestand: "results.txt".to_string(),
@@ -59,11 +126,183 @@ impl Default for Configuratie {
             toon_gratis: true,
             toon_zie_beschrijving: true,
             web_poort: 6600, // Willekeurig, ik heb niet gecheckt of dit conflict veroorzaakt
+            web_adres: "127.0.0.1".to_string(),
+            web_tls: false,
+            tls_certificaat: "cert.pem".to_string(),
+            tls_sleutel: "key.pem".to_string(),
             web_interface_aan: true,
+            api_basis_url: "https://www.marktplaats.nl".to_string(), // Zoek API zit onder /lrp/api/search
+            link_basis_url: "https://www.marktplaats.nl".to_string(),
+            opslag_bestand: "results.jsonl".to_string(),
+            status_bestand: "status.json".to_string(),
+            prijzen_bestand: "prices.json".to_string(),
+            haal_details_op: false, // Een extra verzoek per gevonden advertentie
+            geblokkeerde_verkopers: Vec::new(),
+            vertrouwde_verkopers: Vec::new(),
+            alleen_vertrouwde_verkopers: false,
+            onderdruk_herplaatsingen: false,
+            web_token: String::new(),
+            web_gebruiker: String::new(),
+            web_wachtwoord: String::new(),
+            web_wachtwoord_hash: String::new(),
+            api_tokens: Vec::new(),
+            feed_token: String::new(),
+            publieke_url: String::new(),
+            data_map: String::new(),
+            config_bestand: "config.toml".to_string(),
+            herkomst: Herkomst::default(),
+        }
+    }
+}
+
+// Instellingen met een bestand, een relatief pad hoort in de data map
+const BESTANDEN: &[&str] = &["wenslijst_bestand", "resultaten_bestand", "opslag_bestand", "status_bestand", "prijzen_bestand", "tls_certificaat", "tls_sleutel"];
+
+impl Configuratie {
+    // Een relatieve data_map is relatief aan waar het programma gestart is. Leeg is op Linux de XDG data map,
+    // behalve bij een config.toml in de huidige map (zoals het altijd ging), dan blijft alles daarnaast staan
+    pub fn data_map(&self) -> Result<PathBuf, Box<dyn Error>> {
+        let config_map = Path::new(&self.config_bestand).parent().map(Path::to_path_buf).unwrap_or_default();
+        let map = match self.data_map.as_str() {
+            "" if std::path::absolute("config.toml")? == Path::new(&self.config_bestand) => config_map,
+            "" => xdg_map("XDG_DATA_HOME", ".local/share").unwrap_or(config_map),
+            map => PathBuf::from(map),
+        };
+        Ok(std::path::absolute(map)?)
+    }
+
+    // Zet de bestanden om naar paden in de data map. Bij het opslaan komen de ingestelde waarden weer terug
+    fn in_data_map(mut self) -> Result<Configuratie, Box<dyn Error>> {
+        let data_map = self.data_map()?;
+        std::fs::create_dir_all(&data_map).map_err(|e| format!("Kon data map {} niet aanmaken: {}", data_map.display(), e))?;
+        let Value::Object(mut waarden) = serde_json::to_value(&self)? else {
+            return Err("Kon de configuratie niet omzetten".into());
+        };
+        let mut paden = BTreeMap::new();
+        for sleutel in BESTANDEN {
+            let Some(ingesteld) = waarden[*sleutel].as_str().map(str::to_string) else { continue };
+            let pad = data_map.join(&ingesteld).to_string_lossy().into_owned(); // Een absoluut pad blijft zoals het is
+            waarden.insert(sleutel.to_string(), Value::String(pad.clone()));
+            paden.insert(sleutel.to_string(), (ingesteld, pad));
+        }
+        let mut nieuw: Configuratie = serde_json::from_value(Value::Object(waarden))?;
+        nieuw.config_bestand = std::mem::take(&mut self.config_bestand);
+        nieuw.herkomst = std::mem::take(&mut self.herkomst);
+        nieuw.herkomst.paden = paden;
+        Ok(nieuw)
+    }
+
+    // Instellingen die elkaar of de check lus in de weg zitten
+    pub fn controleer(&self) -> Result<(), String> {
+        if self.check_interval_seconden == 0 {
+            return Err("check_interval_seconden moet minstens 1 zijn".to_string());
+        }
+        if self.min_interval_seconden == 0 || self.min_interval_seconden > self.max_interval_seconden {
+            return Err("min_interval_seconden moet minstens 1 zijn en niet boven max_interval_seconden".to_string());
+        }
+        Ok(())
+    }
+
+    // Voor PATCH /api/v1/config en `config set`, onbekende instellingen en verkeerde types geven een fout
+    pub fn met_aanpassingen(&self, aanpassing: serde_json::Map<String, Value>) -> Result<Configuratie, String> {
+        let Ok(Value::Object(mut waarden)) = serde_json::to_value(self) else {
+            return Err("Kon de configuratie niet omzetten".to_string());
+        };
+        for (sleutel, waarde) in aanpassing {
+            if !waarden.contains_key(&sleutel) {
+                return Err(format!("Onbekende instelling {}", sleutel));
+            }
+            waarden.insert(sleutel, waarde);
         }
+        let mut nieuw: Configuratie = serde_json::from_value(Value::Object(waarden)).map_err(|e| e.to_string())?;
+        nieuw.controleer()?;
+        nieuw.config_bestand = self.config_bestand.clone();
+        nieuw.herkomst = self.herkomst.clone();
+        Ok(nieuw)
+    }
+
+    // Waarden uit MPM_* of van de opdrachtregel blijven uit config.toml, tenzij ze daarna (bijv. in de web interface) aangepast zijn
+    pub fn bewaar(&self) -> Result<(), Box<dyn Error>> {
+        let Value::Object(mut waarden) = serde_json::to_value(self)? else {
+            return Err("Kon de configuratie niet omzetten".into());
+        };
+        let standaard = serde_json::to_value(Configuratie::default())?;
+        for (sleutel, (_, waarde)) in &self.herkomst.overschreven {
+            if waarden.get(sleutel) == Some(waarde) {
+                let origineel = match self.herkomst.bestand.get(sleutel) {
+                    Some(origineel) => serde_json::to_value(origineel)?,
+                    None => standaard[sleutel].clone(),
+                };
+                waarden.insert(sleutel.clone(), origineel);
+            }
+        }
+        for (sleutel, (ingesteld, pad)) in &self.herkomst.paden {
+            if waarden.get(sleutel).and_then(Value::as_str) == Some(pad.as_str()) {
+                waarden.insert(sleutel.clone(), Value::String(ingesteld.clone()));
+            }
+        }
+        let bestand: Configuratie = serde_json::from_value(Value::Object(waarden))?;
+        std::fs::write(&self.config_bestand, toml::to_string_pretty(&bestand)?)?;
+        Ok(())
     }
 }
 
+// Tekst uit een omgevingsvariabele of van de opdrachtregel, naar het type dat de instelling al heeft.
+// Tekst blijft tekst, de rest als JSON. Lijsten mogen ook met komma's: `geblokkeerde_verkopers 1004,Henk`.
+// Een instelling die nu leeg is (null) is altijd een Option<String> zoals api_key, dus ook `12345` of `true` is tekst
+pub fn tekst_als_waarde(huidig: &Value, tekst: &str) -> Result<Value, String> {
+    Ok(match huidig {
+        Value::String(_) => Value::String(tekst.to_string()),
+        Value::Null if tekst.is_empty() => Value::Null,
+        Value::Null => Value::String(tekst.to_string()),
+        Value::Array(_) if !tekst.trim_start().starts_with('[') => Value::Array(
+            tekst.split(',').map(str::trim).filter(|deel| !deel.is_empty()).map(|deel| Value::String(deel.to_string())).collect()
+        ),
+        _ => serde_json::from_str(tekst).map_err(|_| format!("Ongeldige waarde '{}'", tekst))?,
+    })
+}
+
+// Na config.toml: elke instelling uit MPM_<INSTELLING> en daarna van de opdrachtregel als --instelling waarde
+fn met_lagen(configuratie: Configuratie, opdrachtregel: &[(String, String)]) -> Result<Configuratie, Box<dyn Error>> {
+    let Value::Object(velden) = serde_json::to_value(&configuratie)? else {
+        return Err("Kon de configuratie niet omzetten".into());
+    };
+    let mut lagen: Vec<(String, String, String)> = velden.keys() // Instelling, bron, tekst
+        .filter_map(|sleutel| {
+            let variabele = format!("MPM_{}", sleutel.to_uppercase());
+            std::env::var(&variabele).ok().map(|tekst| (sleutel.clone(), variabele, tekst))
+        })
+        .collect();
+    lagen.extend(opdrachtregel.iter().map(|(sleutel, tekst)| (sleutel.clone(), format!("--{}", sleutel.replace('_', "-")), tekst.clone())));
+
+    let mut aanpassing = serde_json::Map::new();
+    let mut overschreven = BTreeMap::new();
+    for (sleutel, bron, tekst) in lagen {
+        let waarde = tekst_als_waarde(&velden[&sleutel], &tekst).map_err(|e| format!("{}: {}", bron, e))?;
+        // Los proberen, zodat een fout zegt welke variabele of optie het was
+        let mut los = velden.clone();
+        los.insert(sleutel.clone(), waarde.clone());
+        serde_json::from_value::<Configuratie>(Value::Object(los)).map_err(|e| format!("{}: {}", bron, e))?;
+        aanpassing.insert(sleutel.clone(), waarde.clone());
+        overschreven.insert(sleutel, (bron, waarde));
+    }
+    if aanpassing.is_empty() {
+        return Ok(configuratie);
+    }
+
+    let mut nieuw = configuratie.met_aanpassingen(aanpassing)?;
+    // Een wachtwoord uit de omgeving wordt alleen in het geheugen gehasht
+    if !nieuw.web_wachtwoord.is_empty() {
+        let bron = overschreven["web_wachtwoord"].0.clone();
+        nieuw.web_wachtwoord_hash = auth::hash_wachtwoord(&nieuw.web_wachtwoord)?;
+        nieuw.web_wachtwoord.clear();
+        overschreven.insert("web_wachtwoord".to_string(), (bron.clone(), Value::String(String::new())));
+        overschreven.insert("web_wachtwoord_hash".to_string(), (bron, Value::String(nieuw.web_wachtwoord_hash.clone())));
+    }
+    nieuw.herkomst.overschreven = overschreven;
+    Ok(nieuw)
+}
+
 #[derive(Debug, Deserialize)]
 struct ZoekResultaten { 
     listings: Vec<Advertentie>,
@@ -85,6 +324,8 @@ pub struct Advertentie {
     pub vip_url: String, // vip: View Item Page
     #[serde(rename = "imageUrls")]
     pub afbeelding_urls: Option<Vec<String>>,
+    #[serde(rename = "sellerInformation", default)]
+    pub verkoper: Option<Verkoper>,
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
@@ -95,6 +336,14 @@ pub struct PrijsInfo {
     pub prijs_type: String,
 }
 
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct Verkoper {
+    #[serde(rename = "sellerId")]
+    pub verkoper_id: Option<u64>,
+    #[serde(rename = "sellerName")]
+    pub verkoper_naam: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct Locatie {
     #[serde(rename = "cityName")]
@@ -103,15 +352,227 @@ pub struct Locatie {
     pub afstand_meters: Option<i32>,
 }
 
-#[derive(Debug)]
+impl Advertentie {
+    pub fn prijs_weergave(&self) -> String {
+        match self.prijs_info.prijs_centen {
+            Some(0) => "Gratis".to_string(),
+            Some(centen) => format!("€{:.2}", centen as f64 / 100.0),
+            None => match self.prijs_info.prijs_type.as_str() {
+                "BID" => "Bieden".to_string(),
+                "FREE" => "Gratis".to_string(),
+                "SEE_DESCRIPTION" => "Zie beschrijving".to_string(),
+                "RESERVED" => "Gereserveerd".to_string(),
+                "NOTK" => "Nader overeen te komen".to_string(),
+                "MIN_BID" => "Minimumbod".to_string(),
+                "SWAP" => "Ruilen".to_string(),
+                _ => "Zie beschrijving/anders".to_string(),
+            }
+        }
+    }
+
+    pub fn prijs_type_label(&self) -> &'static str {
+        match self.prijs_info.prijs_type.as_str() {
+            "BID" => " [BIEDEN]",
+            "FREE" => " [GRATIS]",
+            "RESERVED" => " [GERESERVEERD]",
+            "NOTK" => " [NOTK]",
+            "MIN_BID" => " [MIN. BOD]",
+            "SWAP" => " [RUILEN]",
+            _ => "",
+        }
+    }
+
+    pub fn afstand_weergave(&self) -> String {
+        match self.locatie.afstand_meters {
+            Some(meters) => format!("{:.1} km", meters as f64 / 1000.0),
+            None => "Onbekend".to_string(),
+        }
+    }
+
+    pub fn verkoper_id(&self) -> Option<String> {
+        self.verkoper.as_ref()?.verkoper_id.map(|id| id.to_string())
+    }
+
+    pub fn verkoper_naam(&self) -> Option<&str> {
+        self.verkoper.as_ref()?.verkoper_naam.as_deref()
+    }
+
+    // Verkopers verwijderen en plaatsen dezelfde advertentie opnieuw met een nieuw id,
+    // maar titel, prijs, plaats, verkoper en foto blijven hetzelfde
+    pub fn vingerafdruk(&self) -> String {
+        let titel: String = self.titel.to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|woord| !woord.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let prijs = match self.prijs_info.prijs_centen {
+            Some(centen) => centen.to_string(),
+            None => self.prijs_info.prijs_type.clone(),
+        };
+
+        format!(
+            "{}|{}|{}|{}|{}",
+            titel,
+            prijs,
+            self.locatie.stad_naam.as_deref().unwrap_or("").to_lowercase(),
+            self.verkoper_id().or_else(|| self.verkoper_naam().map(|n| n.to_lowercase())).unwrap_or_default(),
+            self.afbeelding_urls.as_ref().and_then(|urls| urls.first()).map(|u| u.as_str()).unwrap_or(""),
+        )
+    }
+
+    pub fn korte_beschrijving(&self) -> String {
+        self.beschrijving.as_ref().map(|d| {
+            let afgekapt: String = d.chars().take(100).collect();
+            if d.chars().count() > 100 {
+                format!("{}...", afgekapt)
+            } else {
+                afgekapt
+            }
+        }).unwrap_or_else(|| "Geen beschrijving".to_string())
+    }
+}
+
+// Wat de advertentie pagina zelf meer weet dan de zoekresultaten
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+pub struct AdvertentieDetails {
+    pub beschrijving: Option<String>,
+    pub afbeelding_urls: Vec<String>,
+    pub verkoper_naam: Option<String>,
+    pub verkoper_id: Option<String>,
+    pub geplaatst_op: Option<String>,
+    pub kenmerken: Vec<Kenmerk>,
+    pub bezorging: Option<String>,
+    pub aantal_bekeken: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct Kenmerk {
+    pub naam: String,
+    pub waarde: String,
+}
+
+const USER_AGENTS: [&str; 20] = [
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36",
+    "Mozilla/5.0 (X11; Ubuntu; Linux x86_64) AppleWebKit/537.36",
+    "Mozilla/5.0 (Linux; Android 14; Pixel 7) AppleWebKit/537.36",
+    "Mozilla/5.0 (iPhone; CPU iPhone OS 17_2 like Mac OS X) AppleWebKit/537.36",
+    "Mozilla/5.0 (Windows NT 6.1; Win64; x64) AppleWebKit/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 11_6) AppleWebKit/537.36",
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36",
+    "Mozilla/5.0 (Linux; Android 13; SM-G991B) AppleWebKit/537.36",
+    "Mozilla/5.0 (iPad; CPU OS 16_6 like Mac OS X) AppleWebKit/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 12_5_1) AppleWebKit/537.36",
+    "Mozilla/5.0 (X11; Fedora; Linux x86_64) AppleWebKit/537.36",
+    "Mozilla/5.0 (Linux; Android 12; OnePlus 9) AppleWebKit/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_14_6) AppleWebKit/537.36",
+    "Mozilla/5.0 (Linux; Android 11; Nokia X20) AppleWebKit/537.36",
+    "Mozilla/5.0 (Windows NT 6.3; Win64; x64) AppleWebKit/537.36",
+    "Mozilla/5.0 (X11; CrOS x86_64 15604.45.0) AppleWebKit/537.36",
+    "Mozilla/5.0 (Windows NT 10.0) AppleWebKit/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_13_6) AppleWebKit/537.36",
+];
+
+fn willekeurige_user_agent() -> &'static str {
+    USER_AGENTS[fastrand::usize(..USER_AGENTS.len())]
+}
+
+// Getallen, tekst en lijsten komen allemaal voor in de pagina data
+fn als_tekst(waarde: &Value) -> Option<String> {
+    match waarde {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Array(lijst) => {
+            let delen: Vec<String> = lijst.iter().filter_map(als_tekst).collect();
+            Some(delen.join(", "))
+        }
+        _ => None,
+    }
+}
+
+fn eerste_veld<'a>(waarde: &'a Value, paden: &[&str]) -> Option<&'a Value> {
+    paden.iter().filter_map(|pad| waarde.pointer(pad)).find(|v| !v.is_null())
+}
+
+fn verwijder_html(tekst: &str) -> String {
+    let tekst = tekst.replace("<br>", "\n").replace("<br/>", "\n").replace("<br />", "\n").replace("</p>", "\n");
+    let mut schoon = String::new();
+    let mut in_tag = false;
+    for c in tekst.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => schoon.push(c),
+            _ => {}
+        }
+    }
+    schoon.replace("&amp;", "&").replace("&nbsp;", " ").trim().to_string()
+}
+
+// De advertentie pagina heeft de gegevens als JSON in `window.__CONFIG__ = {...};`
+fn parseer_details(html: &str) -> Option<AdvertentieDetails> {
+    let begin = html.find("window.__CONFIG__")?;
+    let rest = &html[begin..];
+    let rest = &rest[rest.find('{')?..];
+    let config: Value = serde_json::Deserializer::from_str(rest).into_iter::<Value>().next()?.ok()?;
+    let advertentie = config.get("listing")?;
+
+    let kenmerken: Vec<Kenmerk> = advertentie.get("attributes")
+        .and_then(|a| a.as_array())
+        .map(|lijst| lijst.iter().filter_map(|kenmerk| {
+            Some(Kenmerk {
+                naam: als_tekst(eerste_veld(kenmerk, &["/label", "/key"])?)?,
+                waarde: als_tekst(kenmerk.get("value")?)?,
+            })
+        }).collect())
+        .unwrap_or_default();
+
+    let bezorging = advertentie.get("attributes")
+        .and_then(|a| a.as_array())
+        .and_then(|lijst| lijst.iter().find(|k| k.get("key").and_then(|s| s.as_str()) == Some("delivery")))
+        .and_then(|k| k.get("value"))
+        .and_then(als_tekst);
+
+    Some(AdvertentieDetails {
+        beschrijving: eerste_veld(advertentie, &["/description"]).and_then(|d| d.as_str()).map(verwijder_html),
+        afbeelding_urls: eerste_veld(advertentie, &["/gallery/imageUrls", "/imageUrls"])
+            .and_then(|urls| urls.as_array())
+            .map(|urls| urls.iter().filter_map(|u| u.as_str().map(String::from)).collect())
+            .unwrap_or_default(),
+        verkoper_naam: eerste_veld(advertentie, &["/seller/name", "/sellerInformation/sellerName"]).and_then(als_tekst),
+        verkoper_id: eerste_veld(advertentie, &["/seller/id", "/sellerInformation/sellerId"]).and_then(als_tekst),
+        geplaatst_op: eerste_veld(advertentie, &["/stats/since", "/date"]).and_then(als_tekst),
+        kenmerken,
+        bezorging,
+        aantal_bekeken: eerste_veld(advertentie, &["/stats/viewCount"]).and_then(|v| v.as_u64()),
+    })
+}
+
+// Lijsten in de config mogen zowel het verkoper id als de naam bevatten
+pub fn verkoper_op_lijst(lijst: &[String], verkoper_id: Option<&str>, verkoper_naam: Option<&str>) -> bool {
+    lijst.iter().map(|v| v.trim()).any(|v| {
+        Some(v) == verkoper_id || verkoper_naam.is_some_and(|naam| naam.to_lowercase() == v.to_lowercase())
+    })
+}
+
+#[derive(Debug, Clone)]
 struct WenslijstItem {
     zoekwoord: String,
     max_prijs: i32,
+    max_percentiel: Option<u8>,     // "p25": alleen advertenties onder het 25e percentiel van eerder geziene prijzen
+    interval_seconden: Option<u64>, // Anders check_interval_seconden
+    venster: Option<planning::Venster>,
 }
 
 pub struct Monitor {
     pub configuratie: Configuratie,
     pub gezien_advertenties: HashSet<String>,
+    pub vingerafdrukken: HashMap<String, String>, // Vingerafdruk -> item id van het origineel
+    pub zoekwoorden_per_link: HashMap<String, HashSet<String>>,
+    pub extra_zoekwoorden: HashMap<String, Vec<String>>, // Link -> zoekwoorden die nog in de opslag moeten
+    pub prijzen: prijzen::Historie,
 }
 
 
@@ -120,9 +581,21 @@ impl Monitor {
     pub fn nieuw(configuratie: Configuratie) -> Result<Self, Box<dyn Error>> {
         let mut monitor = Monitor {
             configuratie,
-            gezien_advertenties: HashSet::new()
+            gezien_advertenties: HashSet::new(),
+            vingerafdrukken: HashMap::new(),
+            zoekwoorden_per_link: HashMap::new(),
+            extra_zoekwoorden: HashMap::new(),
+            prijzen: prijzen::Historie::default(),
         };
+        let geimporteerd = opslag::importeer_tekst_resultaten(
+            &monitor.configuratie.resultaten_bestand,
+            &monitor.configuratie.opslag_bestand,
+        )?;
+        if geimporteerd > 0 {
+            eprintln!("Oude resultaten overgezet naar {}: {}", monitor.configuratie.opslag_bestand, geimporteerd);
+        }
         monitor.laad_bestaande_resultaten()?;
+        monitor.prijzen = prijzen::Historie::laad(&monitor.configuratie.prijzen_bestand)?;
         Ok(monitor)
     }
 
@@ -133,64 +606,76 @@ impl Monitor {
 
             for lijn in lezer.lines() {
                 let lijn = lijn?;
-                if lijn.contains("Link: ") {
-                    if let Some(url) = lijn.split("Link: ").nth(1) {
-                        self.gezien_advertenties.insert(url.trim().to_string()); // Juist formateren
-                    }
+                if let Some(url) = lijn.split("Link: ").nth(1) {
+                    self.gezien_advertenties.insert(url.trim().to_string()); // Juist formateren
                 }
             }
             
-            println!("Bestaande resultaten doorgenomen: {}", self.gezien_advertenties.len());
         }
+
+        for resultaat in opslag::lees_resultaten(&self.configuratie.opslag_bestand)? {
+            let origineel = resultaat.herplaatsing_van.clone().unwrap_or_else(|| resultaat.advertentie.item_id.clone());
+            self.vingerafdrukken.entry(resultaat.advertentie.vingerafdruk()).or_insert(origineel);
+            self.zoekwoorden_per_link.insert(resultaat.link.clone(), resultaat.zoekwoorden.into_iter().collect());
+            self.gezien_advertenties.insert(resultaat.link);
+            self.gezien_advertenties.extend(resultaat.herplaatsingen);
+        }
+
+        eprintln!("Bestaande resultaten doorgenomen: {}", self.gezien_advertenties.len());
         Ok(())
     }
 
+    // Na het wissen via de web interface of de API, wat daarna al bewaard is telt wel als gezien
+    fn vergeet_resultaten(&mut self) -> Result<(), Box<dyn Error>> {
+        self.gezien_advertenties.clear();
+        self.vingerafdrukken.clear();
+        self.zoekwoorden_per_link.clear();
+        self.extra_zoekwoorden.clear();
+        self.prijzen = prijzen::Historie::default();
+        self.prijzen.bewaar(&self.configuratie.prijzen_bestand)?;
+        self.laad_bestaande_resultaten()
+    }
+
+    // Met dezelfde regels als de web interface en de API, zodat die het eens zijn over wat er gezocht wordt
     fn parseer_wenslijst(&self) -> Result<Vec<WenslijstItem>, Box<dyn Error>> {
-        let mut wenslijst = Vec::new();
-        
         if !Path::new(&self.configuratie.wenslijst_bestand).exists() {
             return Err(format!("{} niet gevonden!", self.configuratie.wenslijst_bestand).into()); 
         }
 
-        let bestand = File::open(&self.configuratie.wenslijst_bestand)?;
-        let lezer = BufReader::new(bestand);
-
-        for (lijn_num, lijn) in lezer.lines().enumerate() {
-            let lijn = lijn?; // controlle
-            let lijn = lijn.trim();
-
-            if lijn.is_empty() || lijn.starts_with("#") { 
-                continue; // overslaan
-            }
-
-            if !lijn.contains(";") {
-                eprintln!("Geen puntkomma (;) gevonden op lijn: {}, het wordt overgeslagen \nAls u geen maximum prijs wilt stellen voor een product kunt u -1 schrijven i.p.v {}", lijn_num + 1, lijn);
-                continue;
-            }
-
-            let onderdelen: Vec<&str> = lijn.splitn(2, ";").collect();
-            let zoekwoord = onderdelen[0].trim().to_string();
-
-            let geparseerd = onderdelen[1].trim().parse::<i32>();
-            let max_prijs = match geparseerd {
-                Ok(n) if n > 0  => Some(n),
-                Ok(-1)         => Some(i32::MAX), // Oneindig. Geen zin om een extra case toetevoegen in het zoeken
-                Ok(0)          => Some(0),
-                _              => None,
-            };
-
-            if let Some(prijs) = max_prijs {
-                wenslijst.push(WenslijstItem { zoekwoord, max_prijs: prijs });
-            } else {
-                eprintln!("Probleem op lijn: {}, de prijs ({}) is ongeldig!", lijn_num + 1, lijn);
-            }
-            
+        let (regels, problemen) = wenslijst::lees_met_problemen(&self.configuratie.wenslijst_bestand)?;
+        for probleem in problemen {
+            eprintln!("{}, de regel wordt overgeslagen", probleem);
         }
 
+        let wenslijst: Vec<WenslijstItem> = regels.into_iter()
+            .map(|regel| {
+                let zoekopdracht = regel.zoekopdracht;
+                WenslijstItem {
+                    // -1 is oneindig. Geen zin om een extra case toetevoegen in het zoeken
+                    max_prijs: if zoekopdracht.max_prijs == -1 { i32::MAX } else { zoekopdracht.max_prijs },
+                    max_percentiel: zoekopdracht.max_percentiel,
+                    interval_seconden: zoekopdracht.interval_seconden,
+                    venster: zoekopdracht.venster.as_deref().and_then(|venster| planning::Venster::parseer(venster).ok()),
+                    zoekwoord: zoekopdracht.zoekwoord,
+                }
+            })
+            .collect();
+
         println!("Artikelen doorgenomen van uw wensenlijst: {}", wenslijst.len());
         Ok(wenslijst)
     }
 
+    // Voor "p25" op de wenslijst. Zolang er te weinig prijzen bekend zijn komt er niks door
+    fn onder_percentiel(&self, advertentie: &Advertentie, verdeling: Option<&prijzen::Verdeling>, percentiel: u8) -> bool {
+        if advertentie.prijs_info.prijs_centen == Some(0) {
+            return self.configuratie.toon_gratis;
+        }
+        match (prijzen::vaste_prijs(advertentie), verdeling) {
+            (Some(centen), Some(verdeling)) => centen < verdeling.percentiel(percentiel as f64),
+            _ => false,
+        }
+    }
+
     fn advertentie_komt_overeen(&self, advertentie: &Advertentie, max_prijs: i32) -> bool {
         let prijs_type = advertentie.prijs_info.prijs_type.as_str();
         
@@ -214,40 +699,23 @@ impl Monitor {
         }
         
         match prijs_type {
-            "FREE" => {
-                if !self.configuratie.toon_gratis {
-                    return false;
-                }
-                return true;
-            },
-            "BID" => {
-                if !self.configuratie.toon_bieden {
-                    return false;
-                }
-                if max_prijs == 0 {
-                    return false;
-                }
-                return true;
-            },
+            "FREE" => self.configuratie.toon_gratis,
+            "BID" => self.configuratie.toon_bieden && max_prijs != 0,
             "SEE_DESCRIPTION" | "RESERVED" | "NOTK" | "MIN_BID" | "SWAP" => {
-                if !self.configuratie.toon_zie_beschrijving {
-                    return false;
-                }
-                if max_prijs == 0 {
-                    return false;
-                }
-                return true;
+                self.configuratie.toon_zie_beschrijving && max_prijs != 0
             },
-            _ => {
-                if !self.configuratie.toon_zie_beschrijving {
-                    return false;
-                }
-                if max_prijs == 0 {
-                    return false;
-                }
-                return true;
-            }
+            _ => self.configuratie.toon_zie_beschrijving && max_prijs != 0,
+        }
+    }
+
+    fn verkoper_toegestaan(&self, verkoper_id: Option<&str>, verkoper_naam: Option<&str>) -> bool {
+        if verkoper_op_lijst(&self.configuratie.geblokkeerde_verkopers, verkoper_id, verkoper_naam) {
+            return false;
         }
+        if self.configuratie.alleen_vertrouwde_verkopers {
+            return verkoper_op_lijst(&self.configuratie.vertrouwde_verkopers, verkoper_id, verkoper_naam);
+        }
+        true
     }
 
     pub async fn zoek_artikel(&self, zoekwoord: &str, max_prijs: i32) -> Result<Vec<Advertentie>, Box<dyn Error>> {
@@ -261,7 +729,8 @@ impl Monitor {
         };
 
         let url = format!(
-            "https://www.marktplaats.nl/lrp/api/search?limit={}&offset=0&postcode={}&distanceMeters={}&priceFrom=0&priceTo={}&query={}",
+            "{}/lrp/api/search?limit={}&offset=0&postcode={}&distanceMeters={}&priceFrom=0&priceTo={}&query={}",
+            self.configuratie.api_basis_url.trim_end_matches('/'),
             self.configuratie.max_advertenties_per_zoekopdracht,
             self.configuratie.postcode, 
             self.configuratie.afstand_km * 1000, // km -> m
@@ -269,69 +738,61 @@ impl Monitor {
             urlencoding::encode(zoekwoord)
         );
 
-        let user_agents = [
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36",
-            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36",
-            "Mozilla/5.0 (X11; Ubuntu; Linux x86_64) AppleWebKit/537.36",
-            "Mozilla/5.0 (Linux; Android 14; Pixel 7) AppleWebKit/537.36",
-            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_2 like Mac OS X) AppleWebKit/537.36",
-            "Mozilla/5.0 (Windows NT 6.1; Win64; x64) AppleWebKit/537.36",
-            "Mozilla/5.0 (Macintosh; Intel Mac OS X 11_6) AppleWebKit/537.36",
-            "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36",
-            "Mozilla/5.0 (Linux; Android 13; SM-G991B) AppleWebKit/537.36",
-            "Mozilla/5.0 (iPad; CPU OS 16_6 like Mac OS X) AppleWebKit/537.36",
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36",
-            "Mozilla/5.0 (Macintosh; Intel Mac OS X 12_5_1) AppleWebKit/537.36",
-            "Mozilla/5.0 (X11; Fedora; Linux x86_64) AppleWebKit/537.36",
-            "Mozilla/5.0 (Linux; Android 12; OnePlus 9) AppleWebKit/537.36",
-            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_14_6) AppleWebKit/537.36",
-            "Mozilla/5.0 (Linux; Android 11; Nokia X20) AppleWebKit/537.36",
-            "Mozilla/5.0 (Windows NT 6.3; Win64; x64) AppleWebKit/537.36",
-            "Mozilla/5.0 (X11; CrOS x86_64 15604.45.0) AppleWebKit/537.36",
-            "Mozilla/5.0 (Windows NT 10.0) AppleWebKit/537.36",
-            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_13_6) AppleWebKit/537.36",
-        ];
-
-        let user_agent = user_agents[fastrand::usize(..user_agents.len())];
         let mut request_builder = client
             .get(&url)
-            .header("User-Agent", user_agent);
+            .header("User-Agent", willekeurige_user_agent());
 
         if let Some(api_key) = &self.configuratie.api_key {
             request_builder = request_builder.header("X-MP-Api-Key", api_key);
         }
 
-        let antwoord = request_builder.send().await?;
+        let metrieken = &metrieken::METRIEKEN;
+        metrieken.zoekopdrachten.inc();
+        let _timer = metrieken.api_duur.start_timer();
+
+        let antwoord = request_builder.send().await
+            .inspect_err(|_| metrieken.api_fouten.with_label_values(&["verbinding"]).inc())?;
 
         if !antwoord.status().is_success() {
+            metrieken.api_fouten.with_label_values(&[antwoord.status().as_str()]).inc();
             return Err(format!("HTTP probleem: {}", antwoord.status()).into())
         }
 
-        let zoek_resultaten: ZoekResultaten = antwoord.json().await?;
+        let zoek_resultaten: ZoekResultaten = antwoord.json().await
+            .inspect_err(|_| metrieken.api_fouten.with_label_values(&["ongeldig_antwoord"]).inc())?;
         Ok(zoek_resultaten.listings)
     }
+
+    pub fn volledige_url(&self, advertentie: &Advertentie) -> String {
+        format!("{}{}", self.configuratie.link_basis_url.trim_end_matches('/'), advertentie.vip_url)
+    }
+
     pub fn formatteer_prijs(&self, advertentie: &Advertentie) -> String {
-        match advertentie.prijs_info.prijs_centen {
-            Some(centen) if centen == 0 => "Gratis".to_string(),
-            Some(centen) => format!("€{:.2}", centen as f64 / 100.0),
-            None => match advertentie.prijs_info.prijs_type.as_str() {
-                "BID" => "Bieden".to_string(),
-                "FREE" => "Gratis".to_string(),
-                "SEE_DESCRIPTION" => "Zie beschrijving".to_string(),
-                "RESERVED" => "Gereserveerd".to_string(),
-                "NOTK" => "Nader overeen te komen".to_string(),
-                "MIN_BID" => "Minimumbod".to_string(),
-                "SWAP" => "Ruilen".to_string(),
-                _ => "Zie beschrijving/anders".to_string(),
-            }
+        advertentie.prijs_weergave()
+    }
+
+    pub async fn haal_details(&self, advertentie: &Advertentie) -> Result<AdvertentieDetails, Box<dyn Error>> {
+        let url = format!("{}{}", self.configuratie.api_basis_url.trim_end_matches('/'), advertentie.vip_url);
+
+        let antwoord = reqwest::Client::new()
+            .get(&url)
+            .header("User-Agent", willekeurige_user_agent())
+            .send()
+            .await?;
+
+        if !antwoord.status().is_success() {
+            return Err(format!("HTTP probleem: {}", antwoord.status()).into())
         }
+
+        let html = antwoord.text().await?;
+        parseer_details(&html).ok_or_else(|| "Geen advertentie gegevens gevonden op de pagina".into())
     }
 
-    fn bewaar_resultaat(&mut self, zoekwoord: &str, max_prijs: i32, advertentie: &Advertentie) -> Result<(), Box<dyn Error>> {
-        let tijdstempel = Local::now().format("%Y-%m-%d %H:%M:%S");
+    fn bewaar_resultaat(&mut self, zoekwoord: &str, max_prijs: i32, advertentie: &Advertentie, details: Option<AdvertentieDetails>, herplaatsing_van: Option<String>, deal: Option<prijzen::Deal>) -> Result<(), Box<dyn Error>> {
+        let tijdstempel = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
         let prijs_str = self.formatteer_prijs(advertentie);
         let locatie = advertentie.locatie.stad_naam.as_deref().unwrap_or("Onbekend");
-        let volledige_url = format!("https://www.marktplaats.nl{}", advertentie.vip_url);
+        let volledige_url = self.volledige_url(advertentie);
 
         let max_prijs_str = if max_prijs == i32::MAX {
             "onbeperkt".to_string()
@@ -339,38 +800,27 @@ impl Monitor {
             max_prijs.to_string()
         };
 
-        let afstand = match advertentie.locatie.afstand_meters {
-            Some(meters) => format!("{:.1} km", meters as f64 / 1000.0),
-            None => "Onbekend".to_string(),
-        };
-
-        let beschrijving = advertentie.beschrijving.as_ref().map(|d| {
-            let afgekapt: String = d.chars().take(100).collect();
-            if d.chars().count() > 100 {
-                format!("{}...", afgekapt)
-            } else {
-                afgekapt
-            }
-        }).unwrap_or_else(|| "Geen beschrijving".to_string());
-
-        let prijs_type_info = match advertentie.prijs_info.prijs_type.as_str() {
-            "BID" => " [BIEDEN]",
-            "FREE" => " [GRATIS]",
-            "RESERVED" => " [GERESERVEERD]",
-            "NOTK" => " [NOTK]",
-            "MIN_BID" => " [MIN. BOD]",
-            "SWAP" => " [RUILEN]",
-            _ => "",
-        };
+        let afstand = advertentie.afstand_weergave();
+        let beschrijving = advertentie.korte_beschrijving();
+        let prijs_type_info = advertentie.prijs_type_label();
 
         let afbeelding_url = advertentie.afbeelding_urls.as_ref()
             .and_then(|urls| urls.first())
             .map(|url| url.as_str())
             .unwrap_or("Geen afbeelding");
 
+        let herplaatsing_info = match &herplaatsing_van {
+            Some(origineel) => format!("  Herplaatsing van: {}\n", origineel),
+            None => String::new(),
+        };
+        let deal_info = match &deal {
+            Some(deal) => format!("  Deal: {}\n", deal.omschrijving()),
+            None => String::new(),
+        };
+
         let resultaat = format!(
-            "[{}] Gevonden: \'{}\' (max €{})\n  Titel: {}\n  Prijs: {}{}\n  Locatie: {} ({})\n  Link: {}\n  Afbeelding: {}\n  Beschrijving: {}\n{}\n\n",
-            tijdstempel, zoekwoord, max_prijs_str, advertentie.titel, prijs_str, prijs_type_info, locatie, afstand, volledige_url, afbeelding_url, beschrijving, "=".repeat(60)
+            "[{}] Gevonden: \'{}\' (max €{})\n  Titel: {}\n  Prijs: {}{}\n  Locatie: {} ({})\n  Link: {}\n  Afbeelding: {}\n  Beschrijving: {}\n{}{}{}\n\n",
+            tijdstempel, zoekwoord, max_prijs_str, advertentie.titel, prijs_str, prijs_type_info, locatie, afstand, volledige_url, afbeelding_url, beschrijving, herplaatsing_info, deal_info, "=".repeat(60)
         );
 
         let mut bestand = OpenOptions::new()
@@ -380,41 +830,137 @@ impl Monitor {
 
         bestand.write_all(resultaat.as_bytes())?;
 
-        println!("NIEUW: {} - {}{} - {}", advertentie.titel, prijs_str, prijs_type_info, volledige_url);
+        let opgeslagen = opslag::Resultaat {
+            tijdstempel,
+            zoekwoord: zoekwoord.to_string(),
+            zoekwoorden: vec![zoekwoord.to_string()],
+            max_prijs,
+            link: volledige_url.clone(),
+            advertentie: advertentie.clone(),
+            details,
+            herplaatsing_van: herplaatsing_van.clone(),
+            herplaatsingen: Vec::new(),
+            deal: deal.clone(),
+        };
+        opslag::voeg_resultaat_toe(&self.configuratie.opslag_bestand, &opgeslagen)?;
+        zoekindex::voeg_toe(&self.configuratie.opslag_bestand, &opgeslagen);
+
+        let deal_weergave = deal.map(|deal| format!(" ({})", deal.omschrijving())).unwrap_or_default();
+        match herplaatsing_van {
+            Some(origineel) => println!("HERPLAATST ({}): {} - {}{}{} - {}", origineel, advertentie.titel, prijs_str, prijs_type_info, deal_weergave, volledige_url),
+            None => println!("NIEUW: {} - {}{}{} - {}", advertentie.titel, prijs_str, prijs_type_info, deal_weergave, volledige_url),
+        }
 
         Ok(())
     }
 
-    async fn check_wenslijst(&mut self) -> Result<(), Box<dyn Error>> {
-        let wenslijst = self.parseer_wenslijst()?;
-
-        if wenslijst.is_empty() {
-            eprintln!("Geen geldige artikelen gevonden in uw wensenlijst.");
-            return Ok(());
-        }
-
+    // Alleen de zoekwoorden die aan de beurt zijn. Geeft het aantal nieuwe advertenties per gelukte zoekopdracht terug
+    async fn check_wenslijst(&mut self, wenslijst: Vec<WenslijstItem>) -> Result<HashMap<String, usize>, Box<dyn Error>> {
+        self.bewaar_extra_zoekwoorden()?; // Van een ronde die met een fout gestopt is
         let mut nieuw_aantal = 0;
+        let mut nieuw_per_zoekwoord = HashMap::new();
 
         for item in wenslijst {
-            let prijs_weergave = if item.max_prijs == i32::MAX {
-                "onbeperkt".to_string()
+            let prijs_weergave = if let Some(percentiel) = item.max_percentiel {
+                format!("onder het {}e percentiel", percentiel)
+            } else if item.max_prijs == i32::MAX {
+                "max onbeperkt EUR".to_string()
             } else {
-                format!("{}", item.max_prijs)
+                format!("max {} EUR", item.max_prijs)
             };
 
-            println!("Aan het zoeken voor: \'{}\' (max {} EUR)", item.zoekwoord, prijs_weergave);
+            println!("Aan het zoeken voor: \'{}\' ({})", item.zoekwoord, prijs_weergave);
+            let nieuwe_treffers = metrieken::METRIEKEN.nieuwe_treffers.with_label_values(&[&item.zoekwoord]); // Ook 0 laten zien
 
-            match self.zoek_artikel(&item.zoekwoord, item.max_prijs).await {
+            let gezocht = self.zoek_artikel(&item.zoekwoord, item.max_prijs).await;
+            toestand::item_gecheckt(&item.zoekwoord, gezocht.as_ref().err().map(|e| e.to_string()));
+
+            match gezocht {
                 Ok(advertenties) => {
+                    nieuw_per_zoekwoord.entry(item.zoekwoord.clone()).or_insert(0);
+
+                    // Scoren tegen de prijzen van eerdere checks, niet tegen zichzelf
+                    let verdeling = self.prijzen.verdeling(&item.zoekwoord);
+
+                    // Met een maximum prijs geeft de zoekopdracht alleen goedkopere advertenties terug, die zouden de
+                    // verdeling te laag maken. Voor de prijzen dan nog een keer zonder maximum zoeken (gratis heeft geen prijs)
+                    let zonder_maximum = if item.max_prijs != i32::MAX && item.max_prijs > 0 {
+                        self.zoek_artikel(&item.zoekwoord, i32::MAX).await.unwrap_or_else(|e| {
+                            eprintln!("Kon de prijzen voor \'{}\' niet ophalen: {}", item.zoekwoord, e);
+                            Vec::new()
+                        })
+                    } else {
+                        Vec::new()
+                    };
+                    let voor_prijzen = if item.max_prijs == i32::MAX { &advertenties } else { &zonder_maximum };
+                    if self.prijzen.neem_op(&item.zoekwoord, voor_prijzen)
+                        && let Err(e) = self.prijzen.bewaar(&self.configuratie.prijzen_bestand) {
+                        eprintln!("Kon prijzen niet opslaan in {}: {}", self.configuratie.prijzen_bestand, e);
+                    }
+
                     for advertentie in advertenties {
-                        let volledige_url = format!("https://www.marktplaats.nl{}", advertentie.vip_url);
+                        let volledige_url = self.volledige_url(&advertentie);
 
-                        let binnen_budget = self.advertentie_komt_overeen(&advertentie, item.max_prijs);
+                        let binnen_budget = match item.max_percentiel {
+                            Some(percentiel) => self.onder_percentiel(&advertentie, verdeling.as_ref(), percentiel),
+                            None => self.advertentie_komt_overeen(&advertentie, item.max_prijs),
+                        }
+                            && self.verkoper_toegestaan(advertentie.verkoper_id().as_deref(), advertentie.verkoper_naam());
+
+                        // Al gevonden met een ander zoekwoord: alleen het zoekwoord erbij zetten
+                        if binnen_budget
+                            && let Some(zoekwoorden) = self.zoekwoorden_per_link.get_mut(&volledige_url)
+                            && zoekwoorden.insert(item.zoekwoord.clone()) {
+                            self.extra_zoekwoorden.entry(volledige_url.clone()).or_default().push(item.zoekwoord.clone());
+                            println!("Ook gevonden met \'{}\': {}", item.zoekwoord, advertentie.titel);
+                            continue;
+                        }
 
                         if !self.gezien_advertenties.contains(&volledige_url) && binnen_budget {
-                            self.bewaar_resultaat(&item.zoekwoord, item.max_prijs, &advertentie)?;
+                            let details = if self.configuratie.haal_details_op {
+                                match self.haal_details(&advertentie).await {
+                                    Ok(details) => Some(details),
+                                    Err(e) => {
+                                        eprintln!("Kon details van \'{}\' niet ophalen: {}", advertentie.titel, e);
+                                        None
+                                    }
+                                }
+                            } else {
+                                None
+                            };
+
+                            // Niet elke zoekopdracht geeft de verkoper mee, de pagina wel
+                            if advertentie.verkoper.is_none()
+                                && let Some(details) = &details
+                                && !self.verkoper_toegestaan(details.verkoper_id.as_deref(), details.verkoper_naam.as_deref()) {
+                                continue;
+                            }
+
+                            let vingerafdruk = advertentie.vingerafdruk();
+                            let herplaatsing_van = self.vingerafdrukken.get(&vingerafdruk)
+                                .filter(|origineel| **origineel != advertentie.item_id)
+                                .cloned();
+
+                            match herplaatsing_van {
+                                Some(origineel) if self.configuratie.onderdruk_herplaatsingen => {
+                                    // Alleen aan het origineel koppelen, geen nieuwe melding
+                                    opslag::voeg_herplaatsing_toe(&self.configuratie.opslag_bestand, &origineel, &volledige_url)?;
+                                    println!("Herplaatsing van {} overgeslagen: {}", origineel, volledige_url);
+                                }
+                                herplaatsing_van => {
+                                    let deal = prijzen::vaste_prijs(&advertentie)
+                                        .zip(verdeling.as_ref())
+                                        .map(|(centen, verdeling)| verdeling.score(centen));
+                                    self.bewaar_resultaat(&item.zoekwoord, item.max_prijs, &advertentie, details, herplaatsing_van.clone(), deal)?;
+                                    self.zoekwoorden_per_link.insert(volledige_url.clone(), HashSet::from([item.zoekwoord.clone()]));
+                                    self.vingerafdrukken.entry(vingerafdruk)
+                                        .or_insert_with(|| herplaatsing_van.unwrap_or_else(|| advertentie.item_id.clone()));
+                                    nieuw_aantal += 1;
+                                    *nieuw_per_zoekwoord.entry(item.zoekwoord.clone()).or_insert(0) += 1;
+                                    nieuwe_treffers.inc();
+                                }
+                            }
                             self.gezien_advertenties.insert(volledige_url);
-                            nieuw_aantal += 1;
                         }
                     }
                 }
@@ -426,21 +972,33 @@ impl Monitor {
             thread::sleep(Duration::from_millis(500));
         }
 
+        metrieken::METRIEKEN.gezien.set(self.gezien_advertenties.len() as i64);
+
         if nieuw_aantal > 0 {
             println!("Nieuwe resultaten: {}", nieuw_aantal);
         } else {
             println!("Niks nieuws gevonden.");
         }
 
+        self.bewaar_extra_zoekwoorden()?;
+        Ok(nieuw_per_zoekwoord)
+    }
+
+    fn bewaar_extra_zoekwoorden(&mut self) -> Result<(), Box<dyn Error>> {
+        if !self.extra_zoekwoorden.is_empty() {
+            opslag::voeg_zoekwoorden_toe(&self.configuratie.opslag_bestand, &self.extra_zoekwoorden)?;
+            self.extra_zoekwoorden.clear();
+        }
         Ok(())
     }
 
-    pub async fn draai(&mut self) -> Result<(), Box<dyn Error>> {
+    pub async fn draai(&mut self, gedeelde_configuratie: Arc<Mutex<Configuratie>>) -> Result<(), Box<dyn Error>> {
         println!("Marktplaats Monitor");
         println!("Afstand: {}km van {} af", self.configuratie.afstand_km, self.configuratie.postcode);
         println!("Tussentijd: {} seconden(s)", self.configuratie.check_interval_seconden);
         println!("Resultaten Bestand: {}", self.configuratie.resultaten_bestand);
-        println!("Wenslijst Bestand: {}\n", self.configuratie.wenslijst_bestand);
+        println!("Wenslijst Bestand: {}", self.configuratie.wenslijst_bestand);
+        println!("Data Map: {}\n", self.configuratie.data_map()?.display());
 
         if !Path::new(&self.configuratie.resultaten_bestand).exists() {
             let mut bestand = File::create(&self.configuratie.resultaten_bestand)?;
@@ -454,33 +1012,146 @@ impl Monitor {
             bestand.write_all(koptekst.as_bytes())?;
         }
 
+        let mut planner = planning::Planner::nieuw();
+
         loop {
-            println!("Wenslijst Checken [{}]", Local::now().format("%H:%M:%S"));
-            if let Err(e) = self.check_wenslijst().await {
-                eprintln!("Probleem aangekomen tijdens het checken: {}", e);
+            // Wijzigingen via de web interface meenemen
+            self.configuratie = gedeelde_configuratie.lock().unwrap().clone();
+            if toestand::wissen_gevraagd() && let Err(e) = self.vergeet_resultaten() {
+                eprintln!("Kon de gewiste resultaten niet vergeten: {}", e);
             }
-            
-            println!("Volgende check in {} seconden(s)...\n", self.configuratie.check_interval_seconden);
-            thread::sleep(Duration::from_secs(self.configuratie.check_interval_seconden));
+            let standaard_interval = self.configuratie.check_interval_seconden;
+
+            let wenslijst = match self.parseer_wenslijst() {
+                Ok(wenslijst) if !wenslijst.is_empty() => wenslijst,
+                resultaat => {
+                    let fout = match resultaat {
+                        Err(e) => e.to_string(),
+                        Ok(_) => "Geen geldige artikelen gevonden in uw wensenlijst.".to_string(),
+                    };
+                    eprintln!("Probleem aangekomen tijdens het checken: {}", fout);
+                    toestand::ronde_begonnen();
+                    toestand::ronde_klaar(Some(fout), Some(planning::na(Local::now(), standaard_interval)));
+                    println!("Volgende check in {} seconden(s)...\n", standaard_interval);
+                    thread::sleep(Duration::from_secs(standaard_interval));
+                    continue;
+                }
+            };
+            toestand::wenslijst_gelezen(&wenslijst.iter().map(|item| item.zoekwoord.clone()).collect::<Vec<_>>());
+
+            // Een eigen interval op de wenslijst blijft vast
+            let grenzen = planning::Grenzen {
+                min_seconden: self.configuratie.min_interval_seconden.max(1),
+                max_seconden: self.configuratie.max_interval_seconden.max(self.configuratie.min_interval_seconden.max(1)),
+            };
+            planner.werk_bij(wenslijst.iter().map(|item| planning::Instelling {
+                zoekwoord: item.zoekwoord.clone(),
+                interval_seconden: item.interval_seconden.unwrap_or(standaard_interval),
+                venster: item.venster.clone(),
+                adaptief: (self.configuratie.adaptief_interval && item.interval_seconden.is_none()).then_some(grenzen),
+            }).collect(), Local::now());
+
+            let beurt = planner.aan_de_beurt(Local::now());
+            if !beurt.is_empty() {
+                let items: Vec<WenslijstItem> = beurt.iter()
+                    .flat_map(|zoekwoord| wenslijst.iter().filter(move |item| item.zoekwoord == *zoekwoord))
+                    .cloned()
+                    .collect();
+
+                println!("Wenslijst Checken [{}]", Local::now().format("%H:%M:%S"));
+                let ronde_timer = metrieken::METRIEKEN.ronde_duur.start_timer();
+                toestand::ronde_begonnen();
+                let (nieuw, fout) = match self.check_wenslijst(items).await {
+                    Ok(nieuw) => (nieuw, None),
+                    Err(e) => {
+                        eprintln!("Probleem aangekomen tijdens het checken: {}", e);
+                        (HashMap::new(), Some(e.to_string()))
+                    }
+                };
+                ronde_timer.observe_duration();
+                metrieken::METRIEKEN.rondes.inc();
+
+                planner.gecheckt(&beurt, &nieuw, Local::now());
+                toestand::ronde_klaar(fout, planner.eerstvolgende());
+
+                let wachten = planner.eerstvolgende().map_or(0, |volgende| (volgende - Local::now()).num_seconds().max(0));
+                println!("Volgende check in {} seconden(s)...\n", wachten);
+            }
+            for gepland in planner.overzicht() {
+                toestand::item_gepland(gepland);
+            }
+
+            // Tussendoor de wenslijst opnieuw lezen, voor nieuwe zoekwoorden of een korter interval
+            let wachten = planner.eerstvolgende()
+                .map_or(MAX_SLAAP, |volgende| (volgende - Local::now()).to_std().unwrap_or_default())
+                .min(MAX_SLAAP);
+            thread::sleep(wachten);
         }
     }
 }
 
-fn laad_of_maak_configuratie() -> Result<Configuratie, Box<dyn Error>> {
-    let configuratie_bestand = "config.toml";
-    
-    if Path::new(configuratie_bestand).exists() {
+// Zo lang slaapt de check lus hooguit voordat hij de wenslijst opnieuw leest
+const MAX_SLAAP: Duration = Duration::from_secs(60);
+
+// Op Linux volgens de XDG spec, bijv. ~/.config/marktplaats-monitor. Elders (of zonder HOME) None
+fn xdg_map(variabele: &str, standaard: &str) -> Option<PathBuf> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let basis = std::env::var_os(variabele)
+        .filter(|map| !map.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(standaard)))?;
+    Some(basis.join("marktplaats-monitor"))
+}
+
+fn xdg_config_bestand() -> Option<PathBuf> {
+    xdg_map("XDG_CONFIG_HOME", ".config").map(|map| map.join("config.toml"))
+}
+
+// --config, dan MPM_CONFIG, dan een config.toml in de huidige map (zoals het altijd ging), anders de XDG plek
+fn zoek_config_bestand(opgegeven: Option<String>) -> Result<String, Box<dyn Error>> {
+    let pad = match opgegeven.or_else(|| std::env::var("MPM_CONFIG").ok().filter(|pad| !pad.is_empty())) {
+        Some(pad) => PathBuf::from(pad),
+        None if Path::new("config.toml").exists() => PathBuf::from("config.toml"),
+        None => xdg_config_bestand().unwrap_or_else(|| PathBuf::from("config.toml")),
+    };
+    // Absoluut, zodat het te vergelijken is met de XDG plek en config.toml in de huidige map
+    Ok(std::path::absolute(pad)?.to_string_lossy().into_owned())
+}
+
+fn laad_of_maak_configuratie(configuratie_bestand: &str, instellingen: &[(String, String)]) -> Result<Configuratie, Box<dyn Error>> {
+    let configuratie = if Path::new(configuratie_bestand).exists() {
         let inhoud = std::fs::read_to_string(configuratie_bestand)?;
-        let configuratie: Configuratie = toml::from_str(&inhoud)?;
-        println!("Config geladen van {}", configuratie_bestand);
-        Ok(configuratie)
+        let mut configuratie: Configuratie = toml::from_str(&inhoud)?;
+        configuratie.herkomst.bestand = toml::from_str(&inhoud)?;
+        eprintln!("Config geladen van {}", configuratie_bestand); // Op stderr, zodat een export naar stdout schoon blijft
+
+        // Geen wachtwoorden in platte tekst laten staan
+        if !configuratie.web_wachtwoord.is_empty() {
+            configuratie.web_wachtwoord_hash = auth::hash_wachtwoord(&configuratie.web_wachtwoord)?;
+            configuratie.web_wachtwoord.clear();
+            std::fs::write(configuratie_bestand, toml::to_string_pretty(&configuratie)?)?;
+            eprintln!("Wachtwoord gehasht en opgeslagen in {}", configuratie_bestand);
+        }
+
+        configuratie
     } else {
-        let configuratie = Configuratie::default();
-        let toml_string = toml::to_string_pretty(&configuratie)?;
-        std::fs::write(configuratie_bestand, toml_string)?;
-        println!("Standaard config aangemaakt: {}", configuratie_bestand);
-        Ok(configuratie)
-    }
+        let mut configuratie = Configuratie::default();
+        // Een nieuwe config op de XDG plek krijgt ook de XDG data map, niet ~/.config
+        if xdg_config_bestand().is_some_and(|xdg| std::path::absolute(xdg).is_ok_and(|xdg| xdg == Path::new(configuratie_bestand)))
+            && let Some(data_map) = xdg_map("XDG_DATA_HOME", ".local/share") {
+            configuratie.data_map = data_map.to_string_lossy().into_owned();
+        }
+        if let Some(map) = Path::new(configuratie_bestand).parent() {
+            std::fs::create_dir_all(map)?;
+        }
+        std::fs::write(configuratie_bestand, toml::to_string_pretty(&configuratie)?)?;
+        eprintln!("Standaard config aangemaakt: {}", configuratie_bestand);
+        configuratie
+    };
+
+    met_lagen(Configuratie { config_bestand: configuratie_bestand.to_string(), ..configuratie }, instellingen)?.in_data_map()
 }
 
 fn maak_voorbeeld_wenslijst(bestandsnaam: &str) -> Result<(), Box<dyn Error>> {
@@ -489,6 +1160,8 @@ fn maak_voorbeeld_wenslijst(bestandsnaam: &str) -> Result<(), Box<dyn Error>> {
 # Om te commenteren gebruikt u #
 # Als u geen maximale prijs wilt, stelt u de prijs in als -1
 # Wilt u gratis producten, doe 0 als de prijs
+# Met p25 als prijs krijgt u alleen advertenties onder het 25e percentiel van eerder geziene prijzen
+# Optioneel een eigen interval en wanneer er gecheckt mag worden: zoekwoord;maximaleprijs;5m;ma-vr 08-23
 
 rx 6600;150
 stoel;0
@@ -501,24 +1174,41 @@ steam deck;-1
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let configuratie = laad_of_maak_configuratie()?;
+    toestand::start();
+    let mut argumenten: Vec<String> = std::env::args().skip(1).collect();
+    let config_pad = zoek_config_bestand(opdrachtregel::neem_config_pad(&mut argumenten)?)?;
+    let instellingen = opdrachtregel::neem_instellingen(&mut argumenten)?;
+
+    match argumenten.first().map(String::as_str) {
+        None => {}
+        Some("run") if argumenten.len() == 1 => {}
+        Some(_) => {
+            // Zelf afdrukken, zodat de uitleg leesbaar blijft in plaats van Debug met \n
+            if let Err(e) = opdrachtregel::voer_uit(&config_pad, &instellingen, &argumenten).await {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+    }
+
+    let configuratie = laad_of_maak_configuratie(&config_pad, &instellingen)?;
     
     if !Path::new(&configuratie.wenslijst_bestand).exists() {
         maak_voorbeeld_wenslijst(&configuratie.wenslijst_bestand)?;
     }
     
     let monitor = Monitor::nieuw(configuratie.clone())?;
+    let gedeelde_configuratie = Arc::new(Mutex::new(configuratie.clone()));
     
     if configuratie.web_interface_aan {
         let web_poort = configuratie.web_poort;
-        let config_for_web = configuratie.clone();
-        let web_config = Arc::new(std::sync::Mutex::new(config_for_web));
-        let web_monitor = Arc::new(std::sync::Mutex::new(Monitor::nieuw(configuratie.clone())?));
+        let web_config = gedeelde_configuratie.clone();
         
         tokio::task::spawn_blocking(move || {
             tokio::runtime::Runtime::new().unwrap().block_on(async {
                 println!("Web interface wordt gestart op poort {}...", web_poort);
-                web::start_web_server(web_poort, web_config, web_monitor).await;
+                web::start_web_server(web_poort, web_config).await;
             });
         });
         
@@ -526,5 +1216,5 @@ async fn main() -> Result<(), Box<dyn Error>> {
     }
 
     let mut monitor = monitor;
-    monitor.draai().await
+    monitor.draai(gedeelde_configuratie).await
 }
\ No newline at end of file
@@ -0,0 +1,59 @@
+// Prometheus metrieken, te lezen op /metrics. De check lus en de web interface tellen in dezelfde registry.
+
+use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+use std::sync::LazyLock;
+
+pub struct Metrieken {
+    register: Registry,
+    pub zoekopdrachten: IntCounter,
+    pub api_fouten: IntCounterVec,      // Per HTTP status, of "verbinding" / "ongeldig_antwoord"
+    pub api_duur: Histogram,            // Hoe lang een zoekopdracht bij Marktplaats duurt
+    pub nieuwe_treffers: IntCounterVec, // Per wenslijst zoekwoord
+    pub rondes: IntCounter,
+    pub ronde_duur: Histogram,
+    pub gezien: IntGauge,
+}
+
+pub static METRIEKEN: LazyLock<Metrieken> = LazyLock::new(Metrieken::nieuw);
+
+impl Metrieken {
+    fn nieuw() -> Self {
+        let zoekopdrachten = IntCounter::new("mpm_zoekopdrachten_totaal", "Aantal zoekopdrachten naar Marktplaats").unwrap();
+        let api_fouten = IntCounterVec::new(
+            Opts::new("mpm_api_fouten_totaal", "Mislukte zoekopdrachten naar Marktplaats, per status"),
+            &["status"],
+        ).unwrap();
+        let api_duur = Histogram::with_opts(
+            HistogramOpts::new("mpm_api_verzoek_seconden", "Duur van een zoekopdracht naar Marktplaats")
+                .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]),
+        ).unwrap();
+        let nieuwe_treffers = IntCounterVec::new(
+            Opts::new("mpm_nieuwe_treffers_totaal", "Nieuw gevonden advertenties, per wenslijst zoekwoord"),
+            &["zoekwoord"],
+        ).unwrap();
+        let rondes = IntCounter::new("mpm_rondes_totaal", "Aantal keer dat de hele wenslijst gecheckt is").unwrap();
+        let ronde_duur = Histogram::with_opts(
+            HistogramOpts::new("mpm_ronde_seconden", "Duur van een ronde door de wenslijst")
+                .buckets(vec![1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0]),
+        ).unwrap();
+        let gezien = IntGauge::new("mpm_gezien_advertenties", "Aantal advertenties in de gezien lijst van de check lus").unwrap();
+
+        let register = Registry::new();
+        register.register(Box::new(zoekopdrachten.clone())).unwrap();
+        register.register(Box::new(api_fouten.clone())).unwrap();
+        register.register(Box::new(api_duur.clone())).unwrap();
+        register.register(Box::new(nieuwe_treffers.clone())).unwrap();
+        register.register(Box::new(rondes.clone())).unwrap();
+        register.register(Box::new(ronde_duur.clone())).unwrap();
+        register.register(Box::new(gezien.clone())).unwrap();
+
+        Metrieken { register, zoekopdrachten, api_fouten, api_duur, nieuwe_treffers, rondes, ronde_duur, gezien }
+    }
+
+    // In het tekst formaat dat Prometheus verwacht
+    pub fn tekst(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.register.gather(), &mut buffer).ok();
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
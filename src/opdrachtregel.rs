@@ -0,0 +1,371 @@
+// De opdrachten naast `run`, zodat scripts de monitor kunnen gebruiken zonder de web interface.
+// Ze gebruiken dezelfde Configuratie, Monitor en bestanden als de monitor zelf, die mag intussen gewoon draaien.
+
+use crate::{Configuratie, Monitor, api, auth, export, opslag, web, wenslijst};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+pub const GEBRUIK: &str = "Gebruik: marktplaats-monitor [--config <pad>] [--<instelling> <waarde>...] <opdracht>
+
+Opdrachten:
+  run                                   De monitor en web interface starten (standaard)
+  search <zoekwoord> [--max-prijs N]    Eenmalig zoeken en de advertenties als tabel laten zien
+  wishlist list                         De zoekwoorden op de wenslijst
+  wishlist add <zoekwoord> [prijs] [--interval 5m] [--venster \"ma-vr 08-23\"]
+  wishlist remove <id of zoekwoord>
+  results list [--zoekwoord X] [--q X] [--van 2025-10-01] [--sorteer prijs] [--limit N] ...
+  results export [csv|json|ndjson] [--zoekwoord X] [--van X] [--tot X] [--prijs-min N] [--prijs-max N] [--uitvoer pad]
+  results clear --ja                    Alle resultaten en statussen wissen
+  config get [instelling]
+  config set <instelling> <waarde>
+  config show [--effective]             Alle instellingen, met --effective ook waar ze vandaan komen
+  config validate
+
+Elke instelling uit config.toml kan ook als MPM_<INSTELLING> in de omgeving staan of op de
+opdrachtregel als --<instelling> <waarde>, bijv. MPM_WEB_POORT=6601 of --web-poort 6601.
+
+Met --json geven search, wishlist list en results list JSON in plaats van een tabel.";
+
+// Losse woorden, opties met een waarde en vlaggen zonder waarde
+struct Argumenten {
+    los: Vec<String>,
+    opties: HashMap<String, String>,
+    vlaggen: HashSet<String>,
+}
+
+impl Argumenten {
+    fn ontleed(argumenten: &[String], met_waarde: &[&str], vlaggen: &[&str]) -> Result<Argumenten, Box<dyn Error>> {
+        let mut ontleed = Argumenten { los: Vec::new(), opties: HashMap::new(), vlaggen: HashSet::new() };
+        let mut argumenten = argumenten.iter();
+        while let Some(argument) = argumenten.next() {
+            if met_waarde.contains(&argument.as_str()) {
+                let waarde = argumenten.next().ok_or_else(|| format!("{} heeft een waarde nodig", argument))?;
+                ontleed.opties.insert(argument.clone(), waarde.clone());
+            } else if vlaggen.contains(&argument.as_str()) {
+                ontleed.vlaggen.insert(argument.clone());
+            } else if argument.starts_with("--") {
+                return Err(format!("Onbekende optie '{}'\n\n{}", argument, GEBRUIK).into());
+            } else {
+                ontleed.los.push(argument.clone());
+            }
+        }
+        Ok(ontleed)
+    }
+
+    fn optie(&self, naam: &str) -> Option<&str> {
+        self.opties.get(naam).map(String::as_str)
+    }
+}
+
+// `--config pad` mag overal staan en wordt eruit gehaald, de rest is de opdracht
+pub fn neem_config_pad(argumenten: &mut Vec<String>) -> Result<Option<String>, Box<dyn Error>> {
+    let Some(positie) = argumenten.iter().position(|a| a == "--config") else {
+        return Ok(None);
+    };
+    if positie + 1 >= argumenten.len() {
+        return Err("--config heeft een pad nodig".into());
+    }
+    let pad = argumenten.remove(positie + 1);
+    argumenten.remove(positie);
+    Ok(Some(pad))
+}
+
+// `--web-poort 6601` en zo voor elke instelling, net als --config overal toegestaan
+pub fn neem_instellingen(argumenten: &mut Vec<String>) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let bekend = serde_json::to_value(Configuratie::default())?;
+    let mut instellingen = Vec::new();
+    let mut i = 0;
+    while i < argumenten.len() {
+        match argumenten[i].strip_prefix("--").map(|naam| naam.replace('-', "_")) {
+            Some(sleutel) if bekend.get(&sleutel).is_some() => {
+                if i + 1 >= argumenten.len() {
+                    return Err(format!("{} heeft een waarde nodig", argumenten[i]).into());
+                }
+                let waarde = argumenten.remove(i + 1);
+                argumenten.remove(i);
+                instellingen.push((sleutel, waarde));
+            }
+            _ => i += 1,
+        }
+    }
+    Ok(instellingen)
+}
+
+pub async fn voer_uit(config_pad: &str, instellingen: &[(String, String)], argumenten: &[String]) -> Result<(), Box<dyn Error>> {
+    let laad = || crate::laad_of_maak_configuratie(config_pad, instellingen);
+    let opdracht = argumenten[0].as_str();
+    let sub = argumenten.get(1).map(String::as_str);
+    let rest = argumenten.get(2..).unwrap_or_default();
+
+    match (opdracht, sub) {
+        ("help" | "--help" | "-h", _) => {
+            println!("{}", GEBRUIK);
+            Ok(())
+        }
+        ("search", _) => zoek(&laad()?, &argumenten[1..]).await,
+        ("wishlist", Some("list")) => toon_wenslijst(&laad()?, rest),
+        ("wishlist", Some("add")) => voeg_zoekwoord_toe(&laad()?, rest),
+        ("wishlist", Some("remove")) => verwijder_zoekwoord(&laad()?, rest),
+        ("results", Some("list")) => toon_resultaten(&laad()?, rest),
+        ("results", Some("export")) => export::opdrachtregel(&laad()?, rest),
+        ("export", _) => export::opdrachtregel(&laad()?, &argumenten[1..]), // Van voor de andere opdrachten
+        ("results", Some("clear")) => wis_resultaten(&laad()?, rest),
+        ("config", Some("get")) => toon_instelling(&laad()?, rest),
+        ("config", Some("set")) => zet_instelling(&laad()?, rest),
+        ("config", Some("show")) => toon_config(&laad()?, rest),
+        ("config", Some("validate")) => valideer_config(config_pad, instellingen),
+        _ => Err(format!("Onbekende opdracht '{}'\n\n{}", argumenten.join(" "), GEBRUIK).into()),
+    }
+}
+
+// Kolommen zo breed als de langste waarde, lange titels worden afgekapt
+fn druk_tabel(rijen: &[Value], kolommen: &[(&str, &str)]) {
+    let cellen: Vec<Vec<String>> = rijen.iter()
+        .map(|rij| kolommen.iter().map(|(veld, _)| {
+            let tekst = match &rij[*veld] {
+                Value::String(tekst) => tekst.clone(),
+                Value::Null => String::new(),
+                Value::Array(lijst) => lijst.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(", "),
+                waarde => waarde.to_string(),
+            };
+            if tekst.chars().count() > 60 {
+                format!("{}...", tekst.chars().take(57).collect::<String>())
+            } else {
+                tekst
+            }
+        }).collect())
+        .collect();
+
+    let breedtes: Vec<usize> = kolommen.iter().enumerate()
+        .map(|(i, (_, kop))| cellen.iter().map(|rij| rij[i].chars().count()).chain([kop.chars().count()]).max().unwrap_or(0))
+        .collect();
+    let regel = |waarden: Vec<&str>| {
+        let delen: Vec<String> = waarden.iter().zip(&breedtes)
+            .map(|(waarde, breedte)| format!("{:<breedte$}", waarde, breedte = breedte))
+            .collect();
+        println!("{}", delen.join("  ").trim_end());
+    };
+
+    regel(kolommen.iter().map(|(_, kop)| *kop).collect());
+    regel(breedtes.iter().map(|breedte| "-".repeat(*breedte)).collect::<Vec<_>>().iter().map(String::as_str).collect());
+    for rij in &cellen {
+        regel(rij.iter().map(String::as_str).collect());
+    }
+}
+
+fn druk_af(items: &[impl Serialize], json: bool, kolommen: &[(&str, &str)]) -> Result<(), Box<dyn Error>> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(items)?);
+    } else {
+        druk_tabel(&items.iter().map(serde_json::to_value).collect::<Result<Vec<_>, _>>()?, kolommen);
+    }
+    Ok(())
+}
+
+async fn zoek(configuratie: &Configuratie, argumenten: &[String]) -> Result<(), Box<dyn Error>> {
+    let argumenten = Argumenten::ontleed(argumenten, &["--max-prijs"], &["--json"])?;
+    let zoekwoord = argumenten.los.join(" ");
+    if zoekwoord.trim().is_empty() {
+        return Err(format!("Geen zoekwoord opgegeven\n\n{}", GEBRUIK).into());
+    }
+    let max_prijs = match argumenten.optie("--max-prijs").map(str::parse::<i32>) {
+        None | Some(Ok(-1)) => i32::MAX,
+        Some(Ok(prijs)) if prijs >= 0 => prijs,
+        _ => return Err("--max-prijs moet -1, 0 of hoger zijn".into()),
+    };
+
+    let monitor = Monitor::nieuw(configuratie.clone())?;
+    let advertenties = monitor.zoek_artikel(zoekwoord.trim(), max_prijs).await?;
+    let treffers: Vec<api::ZoekTreffer> = advertenties.iter().map(|a| api::ZoekTreffer::nieuw(a, &monitor)).collect();
+    druk_af(&treffers, argumenten.vlaggen.contains("--json"), &[
+        ("titel", "Titel"), ("prijs", "Prijs"), ("locatie", "Locatie"), ("afstand", "Afstand"), ("link", "Link"),
+    ])
+}
+
+fn toon_wenslijst(configuratie: &Configuratie, argumenten: &[String]) -> Result<(), Box<dyn Error>> {
+    let argumenten = Argumenten::ontleed(argumenten, &[], &["--json"])?;
+    let (regels, _) = wenslijst::lees(&configuratie.wenslijst_bestand)?;
+    druk_af(&regels, argumenten.vlaggen.contains("--json"), &[
+        ("id", "Id"), ("zoekwoord", "Zoekwoord"), ("max_prijs", "Max prijs"), ("max_percentiel", "Percentiel"),
+        ("interval_seconden", "Interval (s)"), ("venster", "Venster"),
+    ])
+}
+
+// Prijs zoals in het bestand: een bedrag, -1, 0 of p25
+fn parseer_prijs(prijs: &str) -> Result<(i32, Option<u8>), Box<dyn Error>> {
+    if let Some(percentiel) = prijs.strip_prefix('p') {
+        return Ok((-1, Some(percentiel.parse().map_err(|_| format!("Ongeldig percentiel '{}'", prijs))?)));
+    }
+    Ok((prijs.parse().map_err(|_| format!("Ongeldige prijs '{}'", prijs))?, None))
+}
+
+fn voeg_zoekwoord_toe(configuratie: &Configuratie, argumenten: &[String]) -> Result<(), Box<dyn Error>> {
+    let argumenten = Argumenten::ontleed(argumenten, &["--interval", "--venster"], &[])?;
+    let (zoekwoord, prijs) = match argumenten.los.as_slice() {
+        [zoekwoord] => (zoekwoord, "-1"),
+        [zoekwoord, prijs] => (zoekwoord, prijs.as_str()),
+        _ => return Err("Gebruik: wishlist add <zoekwoord> [prijs], met aanhalingstekens om een zoekwoord met spaties".into()),
+    };
+    let (max_prijs, max_percentiel) = parseer_prijs(prijs)?;
+    let interval_seconden = argumenten.optie("--interval").map(crate::planning::parseer_interval).transpose()?;
+
+    let zoekopdracht = wenslijst::Zoekopdracht {
+        zoekwoord: zoekwoord.clone(),
+        max_prijs,
+        max_percentiel,
+        interval_seconden,
+        venster: argumenten.optie("--venster").map(String::from),
+    };
+    wenslijst::controleer(&zoekopdracht)?;
+    let (regel, _) = wenslijst::voeg_toe(&configuratie.wenslijst_bestand, &zoekopdracht, None)?;
+    println!("Toegevoegd aan {} met id {}: {}", configuratie.wenslijst_bestand, regel.id, regel.zoekopdracht.zoekwoord);
+    Ok(())
+}
+
+fn verwijder_zoekwoord(configuratie: &Configuratie, argumenten: &[String]) -> Result<(), Box<dyn Error>> {
+    let [wat] = argumenten else {
+        return Err("Gebruik: wishlist remove <id of zoekwoord>".into());
+    };
+    let (regels, versie) = wenslijst::lees(&configuratie.wenslijst_bestand)?;
+    let regel = regels.iter()
+        .find(|regel| regel.id.to_string() == *wat || regel.zoekopdracht.zoekwoord.eq_ignore_ascii_case(wat.trim()))
+        .ok_or_else(|| format!("'{}' staat niet op de wenslijst", wat))?;
+
+    // Met de versie, zodat er niet een andere regel verdwijnt als de wenslijst intussen is aangepast
+    wenslijst::verwijder(&configuratie.wenslijst_bestand, regel.id, Some(&versie))?;
+    println!("Verwijderd van {}: {}", configuratie.wenslijst_bestand, regel.zoekopdracht.zoekwoord);
+    Ok(())
+}
+
+// Dezelfde filters als /resultaten, als --prijs-max 150 in plaats van prijs_max=150
+const RESULTAAT_FILTERS: &[&str] = &[
+    "--q", "--zoekwoord", "--staat", "--favoriet", "--prijs-min", "--prijs-max", "--afstand-min", "--afstand-max",
+    "--van", "--tot", "--prijs-type", "--sorteer", "--richting", "--limit", "--offset",
+];
+
+fn toon_resultaten(configuratie: &Configuratie, argumenten: &[String]) -> Result<(), Box<dyn Error>> {
+    let argumenten = Argumenten::ontleed(argumenten, RESULTAAT_FILTERS, &["--json"])?;
+    if let Some(los) = argumenten.los.first() {
+        return Err(format!("Onverwacht argument '{}'", los).into());
+    }
+    let parameters: Vec<(String, &String)> = argumenten.opties.iter()
+        .map(|(optie, waarde)| (optie.trim_start_matches("--").replace('-', "_"), waarde))
+        .collect();
+    let query: web::ZoekQuery = serde_urlencoded::from_str(&serde_urlencoded::to_string(&parameters)?)?;
+
+    let (resultaten, totaal) = web::zoek_resultaten(configuratie, query);
+    druk_af(&resultaten, argumenten.vlaggen.contains("--json"), &[
+        ("item_id", "Id"), ("tijdstempel", "Gevonden"), ("titel", "Titel"), ("prijs", "Prijs"), ("locatie", "Locatie"), ("staat", "Staat"),
+    ])?;
+    eprintln!("{} van de {} resultaten", resultaten.len(), totaal);
+    Ok(())
+}
+
+fn wis_resultaten(configuratie: &Configuratie, argumenten: &[String]) -> Result<(), Box<dyn Error>> {
+    let argumenten = Argumenten::ontleed(argumenten, &[], &["--ja"])?;
+    if !argumenten.vlaggen.contains("--ja") {
+        return Err("Dit wist alle resultaten en statussen permanent, voeg --ja toe als u het zeker weet".into());
+    }
+    fs::write(&configuratie.resultaten_bestand, "")?;
+    opslag::wis_resultaten(&configuratie.opslag_bestand)?;
+    opslag::wis_statussen(&configuratie.status_bestand)?;
+    println!("Alle resultaten en gezien artikelen permanent gewist");
+    Ok(())
+}
+
+fn toon_instelling(configuratie: &Configuratie, argumenten: &[String]) -> Result<(), Box<dyn Error>> {
+    match argumenten {
+        [] => print!("{}", toml::to_string_pretty(configuratie)?),
+        [sleutel] => match serde_json::to_value(configuratie)?.get(sleutel) {
+            Some(Value::String(tekst)) => println!("{}", tekst),
+            Some(waarde) => println!("{}", waarde),
+            None => return Err(format!("Onbekende instelling {}", sleutel).into()),
+        },
+        _ => return Err("Gebruik: config get [instelling]".into()),
+    }
+    Ok(())
+}
+
+// Met --effective per instelling waar de waarde vandaan komt: standaard, config.toml, MPM_... of --...
+fn toon_config(configuratie: &Configuratie, argumenten: &[String]) -> Result<(), Box<dyn Error>> {
+    let argumenten = Argumenten::ontleed(argumenten, &[], &["--effective"])?;
+    if !argumenten.vlaggen.contains("--effective") {
+        return toon_instelling(configuratie, &[]);
+    }
+
+    let Value::Object(waarden) = serde_json::to_value(configuratie)? else {
+        return Err("Kon de configuratie niet omzetten".into());
+    };
+    let bestand = Path::new(&configuratie.config_bestand).file_name().map(|naam| naam.to_string_lossy().into_owned()).unwrap_or_default();
+    eprintln!("Config: {}", configuratie.config_bestand);
+    let rijen: Vec<Value> = waarden.into_iter()
+        .map(|(sleutel, waarde)| {
+            let geheim = api::GEHEIME_INSTELLINGEN.contains(&sleutel.as_str()) && waarde != "" && waarde != Value::Array(Vec::new());
+            serde_json::json!({
+                "instelling": sleutel,
+                "waarde": if geheim { Value::String("(verborgen)".to_string()) } else { waarde },
+                "bron": configuratie.herkomst.van(&sleutel, &bestand),
+            })
+        })
+        .collect();
+    druk_tabel(&rijen, &[("instelling", "Instelling"), ("waarde", "Waarde"), ("bron", "Bron")]);
+    Ok(())
+}
+
+fn zet_instelling(configuratie: &Configuratie, argumenten: &[String]) -> Result<(), Box<dyn Error>> {
+    let [sleutel, waarde] = argumenten else {
+        return Err("Gebruik: config set <instelling> <waarde>".into());
+    };
+    let huidig = serde_json::to_value(configuratie)?.get(sleutel).cloned()
+        .ok_or_else(|| format!("Onbekende instelling {}", sleutel))?;
+
+    let mut aanpassing = serde_json::Map::new();
+    aanpassing.insert(sleutel.clone(), crate::tekst_als_waarde(&huidig, waarde)?);
+    let mut nieuw = configuratie.met_aanpassingen(aanpassing)?;
+    if !nieuw.web_wachtwoord.is_empty() {
+        nieuw.web_wachtwoord_hash = auth::hash_wachtwoord(&nieuw.web_wachtwoord)?;
+        nieuw.web_wachtwoord.clear();
+    }
+
+    nieuw.bewaar()?;
+    println!("{} aangepast in {}, een draaiende monitor neemt het mee na een herstart", sleutel, nieuw.config_bestand);
+    if let Some((bron, _)) = configuratie.herkomst.overschreven.get(sleutel) {
+        eprintln!("Let op: {} gaat voor de waarde in {}", bron, nieuw.config_bestand);
+    }
+    Ok(())
+}
+
+// Zonder iets aan te maken of te hashen, alleen kijken. MPM_* en --instelling worden ook gecontroleerd
+fn valideer_config(config_pad: &str, instellingen: &[(String, String)]) -> Result<(), Box<dyn Error>> {
+    if !Path::new(config_pad).exists() {
+        return Err(format!("{} bestaat niet", config_pad).into());
+    }
+    let inhoud = fs::read_to_string(config_pad)?;
+    let tabel: toml::Table = toml::from_str(&inhoud).map_err(|e| format!("{} is geen geldige TOML: {}", config_pad, e))?;
+
+    let bekend = serde_json::to_value(Configuratie::default())?;
+    let mut problemen: Vec<String> = tabel.keys()
+        .filter(|sleutel| bekend.get(sleutel).is_none())
+        .map(|sleutel| format!("Onbekende instelling {}", sleutel))
+        .collect();
+    match toml::from_str::<Configuratie>(&inhoud) {
+        Ok(configuratie) => match crate::met_lagen(configuratie, instellingen) {
+            Ok(configuratie) => problemen.extend(configuratie.controleer().err()),
+            Err(e) => problemen.push(e.to_string()),
+        },
+        Err(e) => problemen.push(e.message().to_string()),
+    }
+
+    if problemen.is_empty() {
+        println!("{} is geldig", config_pad);
+        return Ok(());
+    }
+    for probleem in &problemen {
+        eprintln!("{}", probleem);
+    }
+    Err(format!("{} heeft {} probleem/problemen", config_pad, problemen.len()).into())
+}
@@ -0,0 +1,355 @@
+// OpenAPI 3 beschrijving van /api/v1, te vinden op /api/v1/openapi.json. Bij een nieuwe route in api.rs hier ook bijwerken.
+
+use serde_json::{Value, json};
+
+fn verwijzing(schema: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", schema) })
+}
+
+fn json_inhoud(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+fn antwoord(beschrijving: &str, schema: Option<Value>) -> Value {
+    match schema {
+        Some(schema) => json!({ "description": beschrijving, "content": json_inhoud(schema) }),
+        None => json!({ "description": beschrijving }),
+    }
+}
+
+fn fout_antwoord(beschrijving: &str) -> Value {
+    antwoord(beschrijving, Some(verwijzing("ApiFout")))
+}
+
+fn body(schema: &str) -> Value {
+    json!({ "required": true, "content": json_inhoud(verwijzing(schema)) })
+}
+
+fn parameter(naam: &str, plek: &str, schema: Value, beschrijving: &str) -> Value {
+    json!({ "name": naam, "in": plek, "required": plek == "path", "schema": schema, "description": beschrijving })
+}
+
+// Elke operatie kan ook 401/403 geven als de login aan staat
+fn operatie(id: &str, samenvatting: &str, parameters: Vec<Value>, body: Option<Value>, mut antwoorden: serde_json::Map<String, Value>) -> Value {
+    antwoorden.insert("401".to_string(), fout_antwoord("Niet ingelogd of ongeldig API token"));
+    antwoorden.insert("403".to_string(), fout_antwoord("Ongeldig of ontbrekend CSRF token (alleen bij een sessie)"));
+
+    let mut operatie = json!({
+        "operationId": id,
+        "summary": samenvatting,
+        "parameters": parameters,
+        "responses": antwoorden,
+    });
+    if let Some(body) = body {
+        operatie["requestBody"] = body;
+    }
+    operatie
+}
+
+fn antwoorden(lijst: &[(&str, Value)]) -> serde_json::Map<String, Value> {
+    lijst.iter().map(|(code, antwoord)| (code.to_string(), antwoord.clone())).collect()
+}
+
+fn resultaat_filters() -> Vec<Value> {
+    vec![
+        parameter("q", "query", json!({ "type": "string" }), "Zoeken in titel en beschrijving, sorteert standaard op relevantie"),
+        parameter("zoekwoord", "query", json!({ "type": "string" }), "Alleen resultaten van dit wenslijst zoekwoord"),
+        parameter("staat", "query", verwijzing("Staat"), "Verborgen resultaten komen alleen mee als hier om gevraagd wordt"),
+        parameter("favoriet", "query", json!({ "type": "boolean" }), ""),
+        parameter("prijs_min", "query", json!({ "type": "number" }), "In euro's"),
+        parameter("prijs_max", "query", json!({ "type": "number" }), "In euro's"),
+        parameter("afstand_min", "query", json!({ "type": "number" }), "In km"),
+        parameter("afstand_max", "query", json!({ "type": "number" }), "In km"),
+        parameter("van", "query", json!({ "type": "string", "format": "date" }), "Gevonden op of na deze datum"),
+        parameter("tot", "query", json!({ "type": "string", "format": "date" }), "Gevonden op of voor deze datum"),
+        parameter("prijs_type", "query", json!({ "type": "string" }), "Komma gescheiden, bijv. FIXED,BID"),
+        parameter("sorteer", "query", json!({ "type": "string", "enum": ["relevantie", "datum", "prijs", "afstand", "deal"] }), "deal sorteert op het verschil met de mediaan"),
+        parameter("richting", "query", json!({ "type": "string", "enum": ["op", "af"] }), ""),
+        parameter("limit", "query", json!({ "type": "integer", "minimum": 0 }), ""),
+        parameter("offset", "query", json!({ "type": "integer", "minimum": 0 }), ""),
+    ]
+}
+
+fn schemas() -> Value {
+    let tekst = json!({ "type": "string" });
+    let optionele_tekst = json!({ "type": "string", "nullable": true });
+    let optioneel_getal = json!({ "type": "integer", "nullable": true });
+
+    json!({
+        "ApiFout": {
+            "type": "object",
+            "required": ["fout", "bericht"],
+            "properties": {
+                "fout": { "type": "string", "description": "Vaste code, bijv. niet_gevonden, ongeldige_invoer, niet_ingelogd" },
+                "bericht": tekst,
+            },
+        },
+        "Staat": {
+            "type": "string",
+            "enum": ["nieuw", "gezien", "verborgen", "gecontacteerd", "onderhandelen", "gekocht", "afgezien"],
+        },
+        "Deal": {
+            "type": "object",
+            "description": "Hoe de prijs zich verhoudt tot eerder geziene vaste prijzen voor het zoekwoord",
+            "properties": {
+                "mediaan_centen": { "type": "integer" },
+                "percentiel": { "type": "number", "description": "Percentage van de geziene prijzen dat lager was, 0 is de goedkoopste" },
+                "verschil_procent": { "type": "number", "description": "Ten opzichte van de mediaan, negatief is goedkoper" },
+                "aantal_prijzen": { "type": "integer" },
+            },
+        },
+        "Resultaat": {
+            "type": "object",
+            "properties": {
+                "item_id": tekst,
+                "tijdstempel": tekst,
+                "zoekwoord": tekst,
+                "zoekwoorden": { "type": "array", "items": tekst },
+                "titel": tekst,
+                "prijs": tekst,
+                "prijs_centen": optioneel_getal,
+                "prijs_type": tekst,
+                "locatie": tekst,
+                "afstand": tekst,
+                "afstand_meters": optioneel_getal,
+                "link": tekst,
+                "beschrijving": tekst,
+                "afbeelding": optionele_tekst,
+                "verkoper_id": optionele_tekst,
+                "verkoper_naam": optionele_tekst,
+                "vertrouwde_verkoper": { "type": "boolean" },
+                "herplaatsing_van": optionele_tekst,
+                "herplaatsingen": { "type": "array", "items": tekst },
+                "staat": verwijzing("Staat"),
+                "favoriet": { "type": "boolean" },
+                "notitie": tekst,
+                "relevantie": { "type": "number", "description": "Alleen bij zoeken met q" },
+                "deal": { "allOf": [verwijzing("Deal")], "nullable": true, "description": "Leeg bij geen vaste prijs of te weinig geziene prijzen" },
+                "details": { "type": "object", "nullable": true },
+            },
+        },
+        "ResultatenPagina": {
+            "type": "object",
+            "properties": {
+                "totaal": { "type": "integer", "description": "Aantal resultaten dat aan de filters voldoet, zonder limit/offset" },
+                "offset": { "type": "integer" },
+                "limit": optioneel_getal,
+                "resultaten": { "type": "array", "items": verwijzing("Resultaat") },
+            },
+        },
+        "ResultaatAanpassing": {
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "staat": verwijzing("Staat"),
+                "favoriet": { "type": "boolean" },
+                "notitie": tekst,
+            },
+        },
+        "ZoekTreffer": {
+            "type": "object",
+            "properties": {
+                "item_id": tekst,
+                "titel": tekst,
+                "prijs": tekst,
+                "prijs_centen": optioneel_getal,
+                "prijs_type": tekst,
+                "locatie": tekst,
+                "afstand": tekst,
+                "afstand_meters": optioneel_getal,
+                "link": tekst,
+                "beschrijving": tekst,
+                "afbeelding": optionele_tekst,
+                "verkoper_id": optionele_tekst,
+                "verkoper_naam": optionele_tekst,
+            },
+        },
+        "WenslijstRegel": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "integer", "description": "Plek in de wenslijst, 1 is de eerste" },
+                "zoekwoord": tekst,
+                "max_prijs": { "type": "integer", "description": "In euro's, -1 is geen maximum en 0 alleen gratis" },
+                "max_percentiel": { "type": "integer", "description": "Alleen advertenties onder dit percentiel van eerder geziene prijzen (\"p25\" in de wenslijst)" },
+                "interval_seconden": { "type": "integer", "description": "Eigen check interval, zonder dit veld geldt check_interval_seconden" },
+                "venster": { "type": "string", "description": "Wanneer er gecheckt mag worden, bijv. \"ma-vr 08-23\" of \"za,zo\". Zonder dit veld altijd" },
+            },
+        },
+        "WenslijstInvoer": {
+            "type": "object",
+            "additionalProperties": false,
+            "required": ["zoekwoord", "max_prijs"],
+            "properties": {
+                "zoekwoord": tekst,
+                "max_prijs": { "type": "integer", "minimum": -1, "description": "Moet -1 zijn als max_percentiel gezet is" },
+                "max_percentiel": { "type": "integer", "minimum": 1, "maximum": 99, "nullable": true },
+                "interval_seconden": { "type": "integer", "minimum": 1, "maximum": 2592000, "nullable": true },
+                "venster": { "type": "string", "nullable": true },
+            },
+        },
+        "Statistieken": {
+            "type": "object",
+            "properties": {
+                "zoekwoord": tekst,
+                "alleen_treffers": { "type": "boolean", "description": "Altijd true: alles komt uit de bewaarde treffers, dus binnen de maximale prijs en de filters, niet uit alles wat de zoekopdracht gaf" },
+                "aantal": { "type": "integer", "description": "Bewaarde advertenties (treffers) voor dit zoekwoord" },
+                "mediaan_centen": { "type": "integer", "nullable": true, "description": "Van de vaste prijzen" },
+                "per_dag": { "type": "array", "description": "Van de eerste tot de laatste dag, ook dagen zonder advertenties", "items": {
+                    "type": "object",
+                    "properties": { "datum": tekst, "aantal": { "type": "integer" } },
+                } },
+                "histogram": { "type": "array", "description": "Vaste prijzen in staven van een ronde breedte", "items": {
+                    "type": "object",
+                    "properties": { "van_centen": { "type": "integer" }, "tot_centen": { "type": "integer" }, "aantal": { "type": "integer" } },
+                } },
+                "mediaan_per_week": { "type": "array", "items": {
+                    "type": "object",
+                    "properties": { "week": { "type": "string", "description": "ISO week, bijv. 2025-W41" }, "mediaan_centen": { "type": "integer" }, "aantal": { "type": "integer" } },
+                } },
+                "prijs_types": { "type": "array", "description": "Meeste eerst", "items": {
+                    "type": "object",
+                    "properties": { "prijs_type": tekst, "aantal": { "type": "integer" }, "aandeel_procent": { "type": "number" } },
+                } },
+            },
+        },
+        "Configuratie": {
+            "type": "object",
+            "description": "Alle instellingen uit config.toml, behalve tokens en wachtwoorden",
+            "additionalProperties": true,
+        },
+    })
+}
+
+pub fn document() -> Value {
+    let resultaat_id = parameter("item_id", "path", json!({ "type": "string" }), "Item id van Marktplaats, bijv. m2100000001");
+    let wenslijst_id = parameter("id", "path", json!({ "type": "integer", "minimum": 1 }), "");
+    let if_match = parameter("If-Match", "header", json!({ "type": "string" }), "ETag van de wenslijst, geeft 412 als die intussen veranderd is");
+    let mut verplichte_if_match = if_match.clone();
+    verplichte_if_match["required"] = json!(true);
+    verplichte_if_match["description"] = json!("ETag van de wenslijst, of \"*\". Verplicht omdat de ids na een verwijdering opschuiven");
+    let conflict = fout_antwoord("De wenslijst is intussen aangepast (versie_conflict)");
+    let versie_vereist = fout_antwoord("Geen If-Match meegestuurd (versie_vereist)");
+    let niet_gevonden = fout_antwoord("Bestaat niet");
+    let ongeldig = fout_antwoord("Ongeldige invoer");
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Marktplaats Monitor API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Met login aan: stuur een van de api_tokens uit config.toml mee als Authorization: Bearer <token>.",
+        },
+        "servers": [{ "url": "/api/v1" }],
+        "security": [{ "apiToken": [] }, { "sessie": [] }],
+        "paths": {
+            "/resultaten": {
+                "get": operatie("lijstResultaten", "Opgeslagen resultaten, gefilterd en gesorteerd", resultaat_filters(), None, antwoorden(&[
+                    ("200", antwoord("Een pagina resultaten", Some(verwijzing("ResultatenPagina")))),
+                    ("400", ongeldig.clone()),
+                ])),
+                "delete": operatie("wisResultaten", "Alle resultaten en statussen permanent wissen", vec![], None, antwoorden(&[
+                    ("204", antwoord("Gewist", None)),
+                ])),
+            },
+            "/resultaten/{item_id}": {
+                "get": operatie("haalResultaat", "Eén resultaat", vec![resultaat_id.clone()], None, antwoorden(&[
+                    ("200", antwoord("Het resultaat", Some(verwijzing("Resultaat")))),
+                    ("404", niet_gevonden.clone()),
+                ])),
+                "patch": operatie("pasResultaatAan", "Staat, favoriet of notitie aanpassen", vec![resultaat_id], Some(body("ResultaatAanpassing")), antwoorden(&[
+                    ("200", antwoord("Het aangepaste resultaat", Some(verwijzing("Resultaat")))),
+                    ("400", ongeldig.clone()),
+                    ("404", niet_gevonden.clone()),
+                ])),
+            },
+            "/zoek": {
+                "get": operatie("zoek", "Nu zoeken op Marktplaats, zonder iets op te slaan", vec![
+                    parameter("q", "query", json!({ "type": "string" }), "Zoekwoord"),
+                    parameter("max_prijs", "query", json!({ "type": "integer", "minimum": -1 }), "In euro's, -1 of weglaten is geen maximum"),
+                ], None, antwoorden(&[
+                    ("200", antwoord("Gevonden advertenties", Some(json!({ "type": "array", "items": verwijzing("ZoekTreffer") })))),
+                    ("400", ongeldig.clone()),
+                    ("502", fout_antwoord("Marktplaats gaf een fout")),
+                ])),
+            },
+            "/wenslijst": {
+                "get": operatie("lijstWenslijst", "Alle zoekwoorden op de wenslijst", vec![], None, antwoorden(&[
+                    ("200", antwoord("De wenslijst, met de versie in de ETag header", Some(json!({ "type": "array", "items": verwijzing("WenslijstRegel") })))),
+                ])),
+                "post": operatie("maakWenslijstRegel", "Zoekwoord achteraan toevoegen", vec![if_match.clone()], Some(body("WenslijstInvoer")), antwoorden(&[
+                    ("201", antwoord("Toegevoegd, met Location en ETag header", Some(verwijzing("WenslijstRegel")))),
+                    ("400", ongeldig.clone()),
+                    ("412", conflict.clone()),
+                ])),
+            },
+            "/wenslijst/{id}": {
+                "get": operatie("haalWenslijstRegel", "Eén zoekwoord", vec![wenslijst_id.clone()], None, antwoorden(&[
+                    ("200", antwoord("Het zoekwoord", Some(verwijzing("WenslijstRegel")))),
+                    ("404", niet_gevonden.clone()),
+                ])),
+                "put": operatie("vervangWenslijstRegel", "Zoekwoord en prijs vervangen", vec![wenslijst_id.clone(), verplichte_if_match.clone()], Some(body("WenslijstInvoer")), antwoorden(&[
+                    ("200", antwoord("Het aangepaste zoekwoord, met de nieuwe ETag", Some(verwijzing("WenslijstRegel")))),
+                    ("400", ongeldig.clone()),
+                    ("404", niet_gevonden.clone()),
+                    ("412", conflict.clone()),
+                    ("428", versie_vereist.clone()),
+                ])),
+                "delete": operatie("verwijderWenslijstRegel", "Zoekwoord verwijderen, de ids erna schuiven op", vec![wenslijst_id, verplichte_if_match], None, antwoorden(&[
+                    ("204", antwoord("Verwijderd, met de nieuwe ETag", None)),
+                    ("404", niet_gevonden),
+                    ("412", conflict),
+                    ("428", versie_vereist),
+                ])),
+            },
+            "/status": {
+                "get": operatie("haalStatus", "Wat de check lus doet: laatste en volgende check, treffers van vandaag, fouten en de planning per zoekwoord", vec![], None, antwoorden(&[
+                    ("200", antwoord("Status van de check lus", Some(json!({ "type": "object" })))),
+                ])),
+            },
+            "/statistieken": {
+                "get": operatie("haalStatistieken", "Statistieken per zoekwoord, alleen over de opgeslagen treffers", vec![
+                    parameter("zoekwoord", "query", json!({ "type": "string" }), "Weglaten is alle zoekwoorden van de wenslijst"),
+                ], None, antwoorden(&[
+                    ("200", antwoord("Per zoekwoord, in de volgorde van de wenslijst", Some(json!({ "type": "array", "items": verwijzing("Statistieken") })))),
+                ])),
+            },
+            "/export": {
+                "get": operatie("exporteer", "Alle resultaten als bestand, gestreamd in de volgorde van vinden", vec![
+                    parameter("formaat", "query", json!({ "type": "string", "enum": ["csv", "json", "ndjson"], "default": "csv" }), ""),
+                    parameter("zoekwoord", "query", json!({ "type": "string" }), ""),
+                    parameter("van", "query", json!({ "type": "string", "format": "date" }), ""),
+                    parameter("tot", "query", json!({ "type": "string", "format": "date" }), ""),
+                    parameter("prijs_type", "query", json!({ "type": "string" }), "Bijv. FIXED,BID"),
+                    parameter("prijs_min", "query", json!({ "type": "number" }), "In euro's"),
+                    parameter("prijs_max", "query", json!({ "type": "number" }), "In euro's"),
+                ], None, antwoorden(&[
+                    ("200", json!({
+                        "description": "Een regel per resultaat met alle velden van de advertentie, het zoekwoord en de tijdstempel",
+                        "content": {
+                            "text/csv": { "schema": { "type": "string" } },
+                            "application/json": { "schema": { "type": "array", "items": { "type": "object" } } },
+                            "application/x-ndjson": { "schema": { "type": "string" } },
+                        },
+                    })),
+                    ("400", fout_antwoord("Onbekend formaat of ongeldige filter")),
+                ])),
+            },
+            "/config": {
+                "get": operatie("haalConfig", "De huidige instellingen", vec![], None, antwoorden(&[
+                    ("200", antwoord("De instellingen", Some(verwijzing("Configuratie")))),
+                ])),
+                "patch": operatie("pasConfigAan", "Alleen de meegestuurde instellingen aanpassen", vec![], Some(body("Configuratie")), antwoorden(&[
+                    ("200", antwoord("De nieuwe instellingen", Some(verwijzing("Configuratie")))),
+                    ("400", ongeldig),
+                ])),
+            },
+        },
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "apiToken": { "type": "http", "scheme": "bearer" },
+                "sessie": { "type": "apiKey", "in": "cookie", "name": crate::auth::SESSIE_COOKIE },
+            },
+        },
+    })
+}
@@ -0,0 +1,335 @@
+// Gestructureerde opslag van gevonden advertenties, één JSON object per regel.
+// Het resultaten .txt bestand blijft bestaan als leesbaar logboek, maar de web interface leest hieruit.
+
+use crate::{Advertentie, AdvertentieDetails, Locatie, PrijsInfo, zoekindex};
+use crate::prijzen::Deal;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+// De check lus en de web interface schrijven allebei, zonder slot kan een herschrijving een toevoeging wissen
+static SCHRIJF_SLOT: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Resultaat {
+    pub tijdstempel: String,
+    pub zoekwoord: String, // Het zoekwoord waarmee de advertentie als eerste gevonden werd
+    #[serde(default)]
+    pub zoekwoorden: Vec<String>, // Alle wenslijst zoekwoorden waar de advertentie aan voldoet
+    pub max_prijs: i32,
+    pub link: String,
+    pub advertentie: Advertentie,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<AdvertentieDetails>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub herplaatsing_van: Option<String>, // Item id van de eerder geziene advertentie
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub herplaatsingen: Vec<String>, // Links van onderdrukte herplaatsingen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deal: Option<Deal>, // Vergeleken met eerder geziene prijzen voor het zoekwoord
+}
+
+pub fn lees_resultaten(pad: &str) -> Result<Vec<Resultaat>, Box<dyn Error>> {
+    let mut resultaten = Vec::new();
+    if !Path::new(pad).exists() {
+        return Ok(resultaten);
+    }
+
+    let lezer = BufReader::new(File::open(pad)?);
+    for (lijn_num, lijn) in lezer.lines().enumerate() {
+        let lijn = lijn?;
+        if lijn.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Resultaat>(&lijn) {
+            Ok(mut resultaat) => {
+                if resultaat.zoekwoorden.is_empty() {
+                    resultaat.zoekwoorden.push(resultaat.zoekwoord.clone()); // Van voor er meerdere konden zijn
+                }
+                resultaten.push(resultaat)
+            }
+            Err(e) => eprintln!("Kapotte regel {} in {}: {}", lijn_num + 1, pad, e),
+        }
+    }
+    Ok(resultaten)
+}
+
+pub fn voeg_resultaat_toe(pad: &str, resultaat: &Resultaat) -> Result<(), Box<dyn Error>> {
+    let _slot = SCHRIJF_SLOT.lock().unwrap();
+    let mut bestand = OpenOptions::new().create(true).append(true).open(pad)?;
+    writeln!(bestand, "{}", serde_json::to_string(resultaat)?)?;
+    Ok(())
+}
+
+pub fn herschrijf_resultaten(pad: &str, resultaten: &[Resultaat]) -> Result<(), Box<dyn Error>> {
+    let _slot = SCHRIJF_SLOT.lock().unwrap();
+    zoekindex::wis();
+    schrijf_resultaten(pad, resultaten)
+}
+
+pub fn wis_resultaten(pad: &str) -> Result<(), Box<dyn Error>> {
+    let _slot = SCHRIJF_SLOT.lock().unwrap();
+    zoekindex::wis();
+    fs::write(pad, "")?;
+    Ok(())
+}
+
+// Lezen, aanpassen en terugschrijven onder het slot. `aanpassing` geeft terug of er iets veranderd is
+fn pas_resultaten_aan(pad: &str, aanpassing: impl FnOnce(&mut Vec<Resultaat>) -> bool) -> Result<(), Box<dyn Error>> {
+    let _slot = SCHRIJF_SLOT.lock().unwrap();
+    let mut resultaten = lees_resultaten(pad)?;
+    if aanpassing(&mut resultaten) {
+        schrijf_resultaten(pad, &resultaten)?;
+    }
+    Ok(())
+}
+
+// Eerst naar een tijdelijk bestand, zodat een crash halverwege niet alles kwijtmaakt
+pub fn schrijf_atomisch(pad: &str, inhoud: &str) -> Result<(), Box<dyn Error>> {
+    let tijdelijk = format!("{}.tmp", pad);
+    {
+        let mut bestand = File::create(&tijdelijk)?;
+        bestand.write_all(inhoud.as_bytes())?;
+        bestand.sync_all()?;
+    }
+    fs::rename(tijdelijk, pad)?;
+    Ok(())
+}
+
+fn schrijf_resultaten(pad: &str, resultaten: &[Resultaat]) -> Result<(), Box<dyn Error>> {
+    let mut inhoud = String::new();
+    for resultaat in resultaten {
+        inhoud.push_str(&serde_json::to_string(resultaat)?);
+        inhoud.push('\n');
+    }
+    schrijf_atomisch(pad, &inhoud)
+}
+
+// Link -> zoekwoorden, alles van een ronde in één keer zodat het bestand niet per treffer herschreven wordt
+pub fn voeg_zoekwoorden_toe(pad: &str, per_link: &HashMap<String, Vec<String>>) -> Result<(), Box<dyn Error>> {
+    pas_resultaten_aan(pad, |resultaten| {
+        let mut aangepast = false;
+        for resultaat in resultaten.iter_mut() {
+            for zoekwoord in per_link.get(&resultaat.link).into_iter().flatten() {
+                if !resultaat.zoekwoorden.contains(zoekwoord) {
+                    resultaat.zoekwoorden.push(zoekwoord.clone());
+                    aangepast = true;
+                }
+            }
+        }
+        aangepast
+    })
+}
+
+pub fn voeg_herplaatsing_toe(pad: &str, origineel_item_id: &str, link: &str) -> Result<(), Box<dyn Error>> {
+    pas_resultaten_aan(pad, |resultaten| {
+        match resultaten.iter_mut().find(|r| r.advertentie.item_id == origineel_item_id) {
+            Some(origineel) => {
+                origineel.herplaatsingen.push(link.to_string());
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+// Wat de gebruiker met een advertentie gedaan heeft, bijgehouden per item id.
+// Advertenties zonder status zijn nieuw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Staat {
+    #[default]
+    Nieuw,
+    Gezien,
+    Verborgen,
+    Gecontacteerd,
+    Onderhandelen,
+    Gekocht,
+    Afgezien,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArtikelStatus {
+    #[serde(default)]
+    pub staat: Staat,
+    #[serde(default)]
+    pub favoriet: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notitie: String,
+    #[serde(default)]
+    pub bijgewerkt: String,
+}
+
+pub fn lees_statussen(pad: &str) -> Result<HashMap<String, ArtikelStatus>, Box<dyn Error>> {
+    if !Path::new(pad).exists() {
+        return Ok(HashMap::new());
+    }
+    let inhoud = fs::read_to_string(pad)?;
+    if inhoud.trim().is_empty() {
+        return Ok(HashMap::new());
+    }
+    Ok(serde_json::from_str(&inhoud)?)
+}
+
+pub fn pas_statussen_aan(pad: &str, item_ids: &[String], aanpassing: impl Fn(&mut ArtikelStatus)) -> Result<(), Box<dyn Error>> {
+    let _slot = SCHRIJF_SLOT.lock().unwrap();
+    let mut statussen = lees_statussen(pad)?;
+    let nu = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    for item_id in item_ids {
+        let status = statussen.entry(item_id.clone()).or_default();
+        aanpassing(status);
+        status.bijgewerkt = nu.clone();
+    }
+
+    schrijf_atomisch(pad, &serde_json::to_string_pretty(&statussen)?)
+}
+
+pub fn wis_statussen(pad: &str) -> Result<(), Box<dyn Error>> {
+    let _slot = SCHRIJF_SLOT.lock().unwrap();
+    if Path::new(pad).exists() {
+        fs::remove_file(pad)?;
+    }
+    Ok(())
+}
+
+// Voor wie al een resultaten .txt had van voor de JSON opslag
+pub fn importeer_tekst_resultaten(tekst_pad: &str, opslag_pad: &str) -> Result<usize, Box<dyn Error>> {
+    if Path::new(opslag_pad).exists() || !Path::new(tekst_pad).exists() {
+        return Ok(0);
+    }
+
+    let resultaten = parseer_tekst_resultaten(tekst_pad)?;
+    if resultaten.is_empty() {
+        return Ok(0);
+    }
+    herschrijf_resultaten(opslag_pad, &resultaten)?;
+    Ok(resultaten.len())
+}
+
+fn parseer_tekst_resultaten(pad: &str) -> Result<Vec<Resultaat>, Box<dyn Error>> {
+    let lezer = BufReader::new(File::open(pad)?);
+    let mut resultaten = Vec::new();
+    let mut huidig: Option<Resultaat> = None;
+
+    for lijn in lezer.lines().map_while(Result::ok) {
+        if lijn.starts_with("[") && lijn.contains("] Gevonden:") {
+            if let Some(resultaat) = huidig.take() {
+                resultaten.push(resultaat);
+            }
+
+            let onderdelen: Vec<&str> = lijn.splitn(2, "] Gevonden: ").collect();
+            if onderdelen.len() == 2 {
+                let tijdstempel = onderdelen[0].trim_start_matches('[').to_string();
+                let zoekwoord_onderdelen: Vec<&str> = onderdelen[1].splitn(2, "' (max €").collect();
+                let zoekwoord = zoekwoord_onderdelen[0].trim_start_matches('\'').to_string();
+                let max_prijs = zoekwoord_onderdelen.get(1)
+                    .and_then(|m| m.trim_end_matches(')').parse::<i32>().ok())
+                    .unwrap_or(i32::MAX); // "onbeperkt"
+
+                huidig = Some(Resultaat {
+                    tijdstempel,
+                    zoekwoorden: vec![zoekwoord.clone()],
+                    zoekwoord,
+                    max_prijs,
+                    link: String::new(),
+                    advertentie: Advertentie {
+                        item_id: String::new(),
+                        titel: String::new(),
+                        beschrijving: None,
+                        prijs_info: PrijsInfo { prijs_centen: None, prijs_type: String::new() },
+                        locatie: Locatie { stad_naam: None, afstand_meters: None },
+                        vip_url: String::new(),
+                        afbeelding_urls: None,
+                        verkoper: None,
+                    },
+                    details: None,
+                    deal: None,
+                    herplaatsing_van: None,
+                    herplaatsingen: Vec::new(),
+                });
+            }
+            continue;
+        }
+
+        let Some(ref mut resultaat) = huidig else { continue };
+        let lijn = lijn.trim();
+        let advertentie = &mut resultaat.advertentie;
+
+        if let Some(titel) = lijn.strip_prefix("Titel: ") {
+            advertentie.titel = titel.to_string();
+        } else if let Some(prijs) = lijn.strip_prefix("Prijs: ") {
+            advertentie.prijs_info = parseer_prijs(prijs);
+        } else if let Some(locatie) = lijn.strip_prefix("Locatie: ") {
+            if let Some(pos) = locatie.rfind(" (") {
+                advertentie.locatie.stad_naam = Some(locatie[..pos].to_string()).filter(|s| s != "Onbekend");
+                advertentie.locatie.afstand_meters = locatie[pos + 2..]
+                    .trim_end_matches(')')
+                    .trim_end_matches(" km")
+                    .parse::<f64>()
+                    .ok()
+                    .map(|km| (km * 1000.0).round() as i32);
+            } else {
+                advertentie.locatie.stad_naam = Some(locatie.to_string());
+            }
+        } else if let Some(link) = lijn.strip_prefix("Link: ") {
+            resultaat.link = link.to_string();
+            if let Some(pos) = link.find("/v/") {
+                advertentie.vip_url = link[pos..].to_string();
+            }
+            // Links zien eruit als .../m2100000001-titel-van-advertentie
+            advertentie.item_id = link.rsplit('/').next()
+                .and_then(|s| s.split('-').next())
+                .unwrap_or_default()
+                .to_string();
+        } else if let Some(afbeelding) = lijn.strip_prefix("Afbeelding: ") {
+            if afbeelding != "Geen afbeelding" && !afbeelding.is_empty() {
+                advertentie.afbeelding_urls = Some(vec![afbeelding.to_string()]);
+            }
+        } else if let Some(origineel) = lijn.strip_prefix("Herplaatsing van: ") {
+            resultaat.herplaatsing_van = Some(origineel.to_string());
+        } else if let Some(beschrijving) = lijn.strip_prefix("Beschrijving: ")
+            && beschrijving != "Geen beschrijving" {
+            advertentie.beschrijving = Some(beschrijving.to_string());
+        }
+    }
+
+    if let Some(resultaat) = huidig {
+        resultaten.push(resultaat);
+    }
+    Ok(resultaten)
+}
+
+// Omgekeerde van Advertentie::prijs_weergave + het prijs type label
+fn parseer_prijs(prijs: &str) -> PrijsInfo {
+    let (bedrag, label) = match prijs.split_once(" [") {
+        Some((bedrag, label)) => (bedrag, label.trim_end_matches(']')),
+        None => (prijs, ""),
+    };
+
+    let prijs_type = match (label, bedrag) {
+        ("BIEDEN", _) | (_, "Bieden") => "BID",
+        ("GRATIS", _) | (_, "Gratis") => "FREE",
+        ("GERESERVEERD", _) | (_, "Gereserveerd") => "RESERVED",
+        ("NOTK", _) | (_, "Nader overeen te komen") => "NOTK",
+        ("MIN. BOD", _) | (_, "Minimumbod") => "MIN_BID",
+        ("RUILEN", _) | (_, "Ruilen") => "SWAP",
+        (_, b) if b.starts_with('€') => "FIXED",
+        _ => "SEE_DESCRIPTION",
+    };
+
+    let prijs_centen = if bedrag == "Gratis" {
+        Some(0)
+    } else {
+        bedrag.strip_prefix('€')
+            .and_then(|b| b.parse::<f64>().ok())
+            .map(|euro| (euro * 100.0).round() as i32)
+    };
+
+    PrijsInfo { prijs_centen, prijs_type: prijs_type.to_string() }
+}
@@ -0,0 +1,296 @@
+// Wanneer welk zoekwoord aan de beurt is. Elk zoekwoord kan een eigen interval hebben en een venster waarin
+// het gecheckt mag worden (bijv. "ma-vr 08-23"). De check lus vraagt de planner wat er nu moet gebeuren
+// en slaapt tot het eerstvolgende zoekwoord, i.p.v. steeds de hele wenslijst met een vaste pauze.
+
+use chrono::{DateTime, Datelike, Duration, Local, Timelike};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+const DAGEN: [&str; 7] = ["ma", "di", "wo", "do", "vr", "za", "zo"];
+
+// Langer heeft geen zin, en zo blijft het rekenen met tijdstippen binnen de grenzen van chrono
+pub const MAX_INTERVAL_SECONDEN: u64 = 30 * 24 * 3600;
+
+// "300", "300s", "5m" of "2u", in seconden
+pub fn parseer_interval(tekst: &str) -> Result<u64, String> {
+    let tekst = tekst.trim().to_lowercase();
+    let (getal, factor) = match tekst.char_indices().last() {
+        Some((i, 's')) => (&tekst[..i], 1),
+        Some((i, 'm')) => (&tekst[..i], 60),
+        Some((i, 'u')) | Some((i, 'h')) => (&tekst[..i], 3600),
+        _ => (tekst.as_str(), 1),
+    };
+    match getal.trim().parse::<u64>().ok().and_then(|n| n.checked_mul(factor)) {
+        Some(seconden) if seconden > MAX_INTERVAL_SECONDEN => Err(format!("Interval '{}' is langer dan 30 dagen", tekst)),
+        Some(seconden) if seconden > 0 => Ok(seconden),
+        _ => Err(format!("Ongeldig interval '{}', gebruik bijv. 300, 5m of 2u", tekst)),
+    }
+}
+
+// `tijdstip` plus een aantal seconden, zonder paniek bij een te groot interval (bijv. uit de config)
+pub fn na(tijdstip: DateTime<Local>, seconden: u64) -> DateTime<Local> {
+    Duration::try_seconds(seconden.min(MAX_INTERVAL_SECONDEN) as i64)
+        .and_then(|duur| tijdstip.checked_add_signed(duur))
+        .unwrap_or(tijdstip)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Venster {
+    dagen: [bool; 7], // Maandag eerst
+    van: u32,         // Minuten na middernacht
+    tot: u32,         // Niet meer inbegrepen. Kleiner dan `van` loopt door na middernacht
+}
+
+fn parseer_dag(tekst: &str) -> Result<usize, String> {
+    DAGEN.iter().position(|dag| *dag == tekst.trim())
+        .ok_or_else(|| format!("Onbekende dag '{}', gebruik {}", tekst.trim(), DAGEN.join(", ")))
+}
+
+fn parseer_tijd(tekst: &str) -> Result<u32, String> {
+    let (uur, minuut) = tekst.trim().split_once(':').unwrap_or((tekst.trim(), "0"));
+    match (uur.parse::<u32>(), minuut.parse::<u32>()) {
+        (Ok(uur), Ok(minuut)) if minuut < 60 && uur * 60 + minuut <= 24 * 60 => Ok(uur * 60 + minuut),
+        _ => Err(format!("Ongeldige tijd '{}'", tekst.trim())),
+    }
+}
+
+impl Venster {
+    // Dagen en/of uren: "ma-vr 08-23", "za,zo", "22-06", "ma,wo,vr 18:30-23"
+    pub fn parseer(tekst: &str) -> Result<Venster, String> {
+        let mut venster = Venster { dagen: [true; 7], van: 0, tot: 24 * 60 };
+        let mut heeft_dagen = false;
+        let mut heeft_uren = false;
+
+        for deel in tekst.split_whitespace() {
+            if deel.chars().any(|c| c.is_ascii_digit()) {
+                if heeft_uren {
+                    return Err(format!("Meer dan een tijdvak in '{}'", tekst));
+                }
+                let (van, tot) = deel.split_once('-').ok_or_else(|| format!("Tijdvak '{}' moet van-tot zijn, bijv. 08-23", deel))?;
+                venster.van = parseer_tijd(van)?;
+                venster.tot = parseer_tijd(tot)?;
+                if venster.van == venster.tot {
+                    return Err(format!("Begin en einde van '{}' zijn gelijk", deel));
+                }
+                heeft_uren = true;
+            } else {
+                if heeft_dagen {
+                    return Err(format!("Dagen staan meer dan een keer in '{}'", tekst));
+                }
+                venster.dagen = [false; 7];
+                for stuk in deel.to_lowercase().split(',').filter(|s| !s.is_empty()) {
+                    match stuk.split_once('-') {
+                        Some((begin, einde)) => {
+                            let (begin, einde) = (parseer_dag(begin)?, parseer_dag(einde)?);
+                            // "vr-ma" loopt door het weekend
+                            let mut dag = begin;
+                            loop {
+                                venster.dagen[dag] = true;
+                                if dag == einde {
+                                    break;
+                                }
+                                dag = (dag + 1) % 7;
+                            }
+                        }
+                        None => venster.dagen[parseer_dag(stuk)?] = true,
+                    }
+                }
+                heeft_dagen = true;
+            }
+        }
+
+        if !heeft_dagen && !heeft_uren {
+            return Err("Leeg venster".to_string());
+        }
+        Ok(venster)
+    }
+
+    pub fn bevat(&self, tijdstip: DateTime<Local>) -> bool {
+        let minuut = tijdstip.hour() * 60 + tijdstip.minute();
+        let dag = tijdstip.weekday().num_days_from_monday() as usize;
+        if self.van < self.tot {
+            return self.dagen[dag] && minuut >= self.van && minuut < self.tot;
+        }
+        // Over middernacht telt het deel na middernacht bij de dag ervoor
+        (self.dagen[dag] && minuut >= self.van) || (self.dagen[(dag + 6) % 7] && minuut < self.tot)
+    }
+
+    // Het eerste moment vanaf `tijdstip` dat binnen het venster valt
+    pub fn volgende_begin(&self, tijdstip: DateTime<Local>) -> DateTime<Local> {
+        if self.bevat(tijdstip) {
+            return tijdstip;
+        }
+        let hele_minuut = tijdstip.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(tijdstip);
+        // Per minuut vooruit, binnen een week zit altijd een begin
+        (1..=7 * 24 * 60 + 1)
+            .map(|minuten| hele_minuut + Duration::minutes(minuten))
+            .find(|t| self.bevat(*t))
+            .unwrap_or(tijdstip + Duration::days(1))
+    }
+}
+
+// Adaptief plannen: het interval schuift tussen de grenzen uit de config, zodat ongeveer de helft van de checks
+// iets nieuws oplevert. Zoekwoorden zonder nieuwe advertenties worden steeds minder vaak gecheckt.
+const DOEL_TREFFERS_PER_CHECK: f64 = 0.5;
+const GEWICHT_LAATSTE_CHECK: f64 = 0.5; // Voor het lopend gemiddelde
+const MIN_FACTOR: f64 = 0.5;            // Hoe snel het interval per check korter of langer wordt
+const MAX_FACTOR: f64 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grenzen {
+    pub min_seconden: u64,
+    pub max_seconden: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Instelling {
+    pub zoekwoord: String,
+    pub interval_seconden: u64,
+    pub venster: Option<Venster>,
+    pub adaptief: Option<Grenzen>, // None is een vast interval
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Aanpassing {
+    interval_seconden: u64,
+    treffers_per_check: f64, // Lopend gemiddelde van nieuwe advertenties per check
+}
+
+// Wat er voor een zoekwoord gepland staat, voor /status en de wenslijst tab
+#[derive(Debug, Clone)]
+pub struct Gepland {
+    pub zoekwoord: String,
+    pub wanneer: DateTime<Local>,
+    pub interval_seconden: u64,
+    pub treffers_per_check: Option<f64>, // Alleen bij adaptief plannen, na de eerste check
+}
+
+// Volgorde in de wachtrij: eerst wie het eerst aan de beurt is, bij gelijke tijd het kortste interval,
+// daarna zoals op de wenslijst
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Afspraak {
+    wanneer: DateTime<Local>,
+    interval_seconden: u64,
+    plek: usize,
+    zoekwoord: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Planner {
+    wachtrij: BinaryHeap<Reverse<Afspraak>>,
+    instellingen: HashMap<String, (usize, Instelling)>, // Met de plek op de wenslijst
+    aanpassingen: HashMap<String, Aanpassing>,
+}
+
+impl Planner {
+    pub fn nieuw() -> Self {
+        Planner::default()
+    }
+
+    // Het interval dat nu geldt, bij adaptief plannen het berekende
+    fn interval(&self, instelling: &Instelling) -> u64 {
+        let Some(grenzen) = instelling.adaptief else {
+            return instelling.interval_seconden;
+        };
+        self.aanpassingen.get(&instelling.zoekwoord)
+            .map_or(instelling.interval_seconden, |aanpassing| aanpassing.interval_seconden)
+            .clamp(grenzen.min_seconden, grenzen.max_seconden)
+    }
+
+    fn afspraak(&self, plek: usize, instelling: &Instelling, wanneer: DateTime<Local>) -> Reverse<Afspraak> {
+        let wanneer = match &instelling.venster {
+            Some(venster) => venster.volgende_begin(wanneer),
+            None => wanneer,
+        };
+        Reverse(Afspraak { wanneer, interval_seconden: self.interval(instelling), plek, zoekwoord: instelling.zoekwoord.clone() })
+    }
+
+    fn pas_aan(&mut self, instelling: &Instelling, grenzen: Grenzen, nieuw: usize) {
+        let treffers_per_check = match self.aanpassingen.get(&instelling.zoekwoord) {
+            Some(vorige) => GEWICHT_LAATSTE_CHECK * nieuw as f64 + (1.0 - GEWICHT_LAATSTE_CHECK) * vorige.treffers_per_check,
+            None => nieuw as f64,
+        };
+        let factor = if treffers_per_check > 0.0 {
+            (DOEL_TREFFERS_PER_CHECK / treffers_per_check).clamp(MIN_FACTOR, MAX_FACTOR)
+        } else {
+            MAX_FACTOR
+        };
+        let interval_seconden = ((self.interval(instelling) as f64 * factor).round() as u64)
+            .clamp(grenzen.min_seconden, grenzen.max_seconden);
+        self.aanpassingen.insert(instelling.zoekwoord.clone(), Aanpassing { interval_seconden, treffers_per_check });
+    }
+
+    // Na het (opnieuw) lezen van de wenslijst. Nieuwe zoekwoorden zijn meteen aan de beurt, verdwenen zoekwoorden
+    // gaan uit de wachtrij en een korter interval of ander venster gaat meteen in.
+    pub fn werk_bij(&mut self, instellingen: Vec<Instelling>, nu: DateTime<Local>) {
+        let mut gepland: HashMap<String, DateTime<Local>> = self.wachtrij.drain()
+            .map(|Reverse(afspraak)| (afspraak.zoekwoord, afspraak.wanneer))
+            .collect();
+
+        self.instellingen.clear();
+        for (plek, instelling) in instellingen.into_iter().enumerate() {
+            // Hetzelfde zoekwoord twee keer op de wenslijst: het kortste interval telt
+            if let Some((_, bestaand)) = self.instellingen.get(&instelling.zoekwoord)
+                && bestaand.interval_seconden <= instelling.interval_seconden {
+                continue;
+            }
+            self.instellingen.insert(instelling.zoekwoord.clone(), (plek, instelling));
+        }
+        // Wat niet (meer) adaptief gepland wordt begint later weer opnieuw
+        self.aanpassingen.retain(|zoekwoord, _| {
+            self.instellingen.get(zoekwoord).is_some_and(|(_, instelling)| instelling.adaptief.is_some())
+        });
+
+        let afspraken: Vec<_> = self.instellingen.values()
+            .map(|(plek, instelling)| {
+                let uiterlijk = na(nu, self.interval(instelling));
+                let wanneer = gepland.remove(&instelling.zoekwoord).map_or(nu, |wanneer| wanneer.min(uiterlijk));
+                self.afspraak(*plek, instelling, wanneer)
+            })
+            .collect();
+        self.wachtrij.extend(afspraken);
+    }
+
+    pub fn eerstvolgende(&self) -> Option<DateTime<Local>> {
+        self.wachtrij.peek().map(|Reverse(afspraak)| afspraak.wanneer)
+    }
+
+    // Haalt alles wat aan de beurt is uit de wachtrij, belangrijkste eerst
+    pub fn aan_de_beurt(&mut self, nu: DateTime<Local>) -> Vec<String> {
+        let mut beurt = Vec::new();
+        while let Some(Reverse(afspraak)) = self.wachtrij.peek()
+            && afspraak.wanneer <= nu {
+            beurt.push(self.wachtrij.pop().unwrap().0.zoekwoord);
+        }
+        beurt
+    }
+
+    // Na het checken opnieuw inplannen, met het aantal nieuwe advertenties per gelukte zoekopdracht. Alles van
+    // dezelfde beurt telt vanaf hetzelfde moment, zodat zoekwoorden met hetzelfde interval samen aan de beurt blijven.
+    pub fn gecheckt(&mut self, zoekwoorden: &[String], nieuw: &HashMap<String, usize>, nu: DateTime<Local>) {
+        for zoekwoord in zoekwoorden {
+            let Some((plek, instelling)) = self.instellingen.get(zoekwoord).cloned() else {
+                continue;
+            };
+            // Een mislukte zoekopdracht zegt niks over hoe vaak er iets nieuws is
+            if let Some(grenzen) = instelling.adaptief
+                && let Some(aantal) = nieuw.get(zoekwoord) {
+                self.pas_aan(&instelling, grenzen, *aantal);
+            }
+            let wanneer = na(nu, self.interval(&instelling));
+            let afspraak = self.afspraak(plek, &instelling, wanneer);
+            self.wachtrij.push(afspraak);
+        }
+    }
+
+    pub fn overzicht(&self) -> Vec<Gepland> {
+        self.wachtrij.iter()
+            .map(|Reverse(afspraak)| Gepland {
+                zoekwoord: afspraak.zoekwoord.clone(),
+                wanneer: afspraak.wanneer,
+                interval_seconden: afspraak.interval_seconden,
+                treffers_per_check: self.aanpassingen.get(&afspraak.zoekwoord).map(|aanpassing| aanpassing.treffers_per_check),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,160 @@
+// Prijzen die per wenslijst zoekwoord voorbij zijn gekomen, om te kunnen zeggen of een advertentie een goede deal is.
+// Elke advertentie telt één keer (op item id) en alleen vaste prijzen tellen mee, bieden of "zie beschrijving" zegt niks.
+
+use crate::{Advertentie, opslag};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+const MIN_PRIJZEN: usize = 5;   // Met minder prijzen is een mediaan nog niks waard
+const MAX_PRIJZEN: usize = 500; // Per zoekwoord, de oudste gaan eruit zodat oude prijzen niet blijven hangen
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Waarneming {
+    pub item_id: String,
+    pub centen: i64,
+    pub datum: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Historie {
+    zoekwoorden: HashMap<String, Vec<Waarneming>>,
+}
+
+// Hoe een advertentie zich verhoudt tot de eerder geziene prijzen, wordt bij het resultaat bewaard
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Deal {
+    pub mediaan_centen: i64,
+    pub percentiel: f64,       // Deel van de geziene prijzen dat lager was, 0 is de goedkoopste ooit
+    pub verschil_procent: f64, // Ten opzichte van de mediaan, negatief is goedkoper
+    pub aantal_prijzen: usize,
+}
+
+impl Deal {
+    pub fn omschrijving(&self) -> String {
+        let mediaan = format!("€{:.2}", self.mediaan_centen as f64 / 100.0);
+        if self.verschil_procent < 0.0 {
+            format!("{:.0}% onder de mediaan ({})", -self.verschil_procent, mediaan)
+        } else if self.verschil_procent > 0.0 {
+            format!("{:.0}% boven de mediaan ({})", self.verschil_procent, mediaan)
+        } else {
+            format!("Precies de mediaan ({})", mediaan)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Verdeling {
+    pub aantal: usize,
+    pub min_centen: i64,
+    pub p10_centen: i64,
+    pub p25_centen: i64,
+    pub mediaan_centen: i64,
+    pub p75_centen: i64,
+    pub p90_centen: i64,
+    pub max_centen: i64,
+    #[serde(skip)]
+    prijzen: Vec<i64>, // Gesorteerd
+}
+
+// Lineair tussen de twee dichtstbijzijnde prijzen, zoals een spreadsheet het doet
+fn percentiel_waarde(gesorteerd: &[i64], percentiel: f64) -> i64 {
+    let positie = (gesorteerd.len() - 1) as f64 * percentiel / 100.0;
+    let onder = positie.floor() as usize;
+    let boven = (onder + 1).min(gesorteerd.len() - 1);
+    let fractie = positie - onder as f64;
+    (gesorteerd[onder] as f64 + fractie * (gesorteerd[boven] - gesorteerd[onder]) as f64).round() as i64
+}
+
+pub fn mediaan(gesorteerd: &[i64]) -> i64 {
+    percentiel_waarde(gesorteerd, 50.0)
+}
+
+fn afgerond(getal: f64) -> f64 {
+    (getal * 10.0).round() / 10.0
+}
+
+impl Verdeling {
+    pub fn percentiel(&self, percentiel: f64) -> i64 {
+        percentiel_waarde(&self.prijzen, percentiel)
+    }
+
+    pub fn score(&self, centen: i64) -> Deal {
+        let lager = self.prijzen.iter().filter(|prijs| **prijs < centen).count();
+        Deal {
+            mediaan_centen: self.mediaan_centen,
+            percentiel: afgerond(lager as f64 * 100.0 / self.aantal as f64),
+            verschil_procent: afgerond((centen - self.mediaan_centen) as f64 * 100.0 / self.mediaan_centen as f64),
+            aantal_prijzen: self.aantal,
+        }
+    }
+}
+
+// Alleen een vaste prijs is te vergelijken
+pub fn vaste_prijs(advertentie: &Advertentie) -> Option<i64> {
+    match (advertentie.prijs_info.prijs_type.as_str(), advertentie.prijs_info.prijs_centen) {
+        ("FIXED", Some(centen)) if centen > 0 => Some(centen as i64),
+        _ => None,
+    }
+}
+
+impl Historie {
+    pub fn laad(pad: &str) -> Result<Historie, Box<dyn Error>> {
+        if !Path::new(pad).exists() {
+            return Ok(Historie::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(pad)?)?)
+    }
+
+    pub fn bewaar(&self, pad: &str) -> Result<(), Box<dyn Error>> {
+        opslag::schrijf_atomisch(pad, &serde_json::to_string(self)?)
+    }
+
+    // Geeft terug of er iets bij gekomen is
+    pub fn neem_op(&mut self, zoekwoord: &str, advertenties: &[Advertentie]) -> bool {
+        let waarnemingen = self.zoekwoorden.entry(zoekwoord.to_string()).or_default();
+        let datum = Local::now().format("%Y-%m-%d").to_string();
+        let mut nieuw = false;
+
+        for advertentie in advertenties {
+            let Some(centen) = vaste_prijs(advertentie) else {
+                continue;
+            };
+            if waarnemingen.iter().any(|w| w.item_id == advertentie.item_id) {
+                continue;
+            }
+            waarnemingen.push(Waarneming { item_id: advertentie.item_id.clone(), centen, datum: datum.clone() });
+            nieuw = true;
+        }
+
+        if waarnemingen.len() > MAX_PRIJZEN {
+            let teveel = waarnemingen.len() - MAX_PRIJZEN;
+            waarnemingen.drain(..teveel);
+        }
+        nieuw
+    }
+
+    // None zolang er te weinig prijzen zijn
+    pub fn verdeling(&self, zoekwoord: &str) -> Option<Verdeling> {
+        let mut prijzen: Vec<i64> = self.zoekwoorden.get(zoekwoord)?.iter().map(|w| w.centen).collect();
+        if prijzen.len() < MIN_PRIJZEN {
+            return None;
+        }
+        prijzen.sort_unstable();
+        Some(Verdeling {
+            aantal: prijzen.len(),
+            min_centen: prijzen[0],
+            p10_centen: percentiel_waarde(&prijzen, 10.0),
+            p25_centen: percentiel_waarde(&prijzen, 25.0),
+            mediaan_centen: mediaan(&prijzen),
+            p75_centen: percentiel_waarde(&prijzen, 75.0),
+            p90_centen: percentiel_waarde(&prijzen, 90.0),
+            max_centen: prijzen[prijzen.len() - 1],
+            prijzen,
+        })
+    }
+}
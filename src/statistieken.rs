@@ -0,0 +1,155 @@
+// Statistieken per wenslijst zoekwoord, berekend uit de opgeslagen resultaten (results.jsonl).
+// Alleen advertenties die de monitor bewaard heeft tellen mee, dus binnen de maximale prijs van het zoekwoord.
+
+use crate::opslag::Resultaat;
+use crate::prijzen;
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+const MAX_STAVEN: i64 = 10; // Ongeveer, met afgeronde breedtes kan het er een meer worden
+
+#[derive(Debug, Serialize)]
+pub struct Statistieken {
+    pub zoekwoord: String,
+    pub alleen_treffers: bool, // Altijd waar, wat de zoekopdracht verder teruggaf is niet bewaard en telt dus niet mee
+    pub aantal: usize,
+    pub mediaan_centen: Option<i64>, // Van de vaste prijzen
+    pub per_dag: Vec<DagTelling>,    // Van de eerste tot de laatste dag, ook dagen zonder advertenties
+    pub histogram: Vec<Staaf>,
+    pub mediaan_per_week: Vec<WeekMediaan>,
+    pub prijs_types: Vec<PrijsTypeAandeel>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DagTelling {
+    pub datum: String,
+    pub aantal: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Staaf {
+    pub van_centen: i64, // Tot en met van, tot niet
+    pub tot_centen: i64,
+    pub aantal: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeekMediaan {
+    pub week: String, // ISO week, bijv. "2025-W41"
+    pub mediaan_centen: i64,
+    pub aantal: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PrijsTypeAandeel {
+    pub prijs_type: String,
+    pub aantal: usize,
+    pub aandeel_procent: f64,
+}
+
+// Tijdstempels zijn "%Y-%m-%d %H:%M:%S"
+fn datum(resultaat: &Resultaat) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(resultaat.tijdstempel.get(..10)?, "%Y-%m-%d").ok()
+}
+
+fn per_dag(datums: &[NaiveDate]) -> Vec<DagTelling> {
+    let (Some(eerste), Some(laatste)) = (datums.iter().min(), datums.iter().max()) else {
+        return Vec::new();
+    };
+    let mut tellingen: HashMap<NaiveDate, usize> = HashMap::new();
+    for datum in datums {
+        *tellingen.entry(*datum).or_default() += 1;
+    }
+
+    let mut dagen = Vec::new();
+    let mut dag = *eerste;
+    while dag <= *laatste {
+        dagen.push(DagTelling { datum: dag.format("%Y-%m-%d").to_string(), aantal: tellingen.get(&dag).copied().unwrap_or(0) });
+        dag += Duration::days(1);
+    }
+    dagen
+}
+
+// Een ronde breedte (1, 2 of 5 keer een macht van 10 euro), zodat de staven bij hele bedragen beginnen
+fn staaf_breedte(bereik_centen: i64) -> i64 {
+    let mut macht = 100;
+    loop {
+        for factor in [1, 2, 5] {
+            if factor * macht * MAX_STAVEN >= bereik_centen {
+                return factor * macht;
+            }
+        }
+        macht *= 10;
+    }
+}
+
+fn histogram(gesorteerd: &[i64]) -> Vec<Staaf> {
+    let (Some(&min), Some(&max)) = (gesorteerd.first(), gesorteerd.last()) else {
+        return Vec::new();
+    };
+    let breedte = staaf_breedte(max - min);
+    let begin = min / breedte * breedte;
+    let mut staven: Vec<Staaf> = (0..=(max - begin) / breedte)
+        .map(|i| Staaf { van_centen: begin + i * breedte, tot_centen: begin + (i + 1) * breedte, aantal: 0 })
+        .collect();
+    for prijs in gesorteerd {
+        staven[((prijs - begin) / breedte) as usize].aantal += 1;
+    }
+    staven
+}
+
+fn mediaan_per_week(prijzen: &[(NaiveDate, i64)]) -> Vec<WeekMediaan> {
+    let mut weken: BTreeMap<(i32, u32), Vec<i64>> = BTreeMap::new();
+    for (datum, centen) in prijzen {
+        let week = datum.iso_week();
+        weken.entry((week.year(), week.week())).or_default().push(*centen);
+    }
+    weken.into_iter()
+        .map(|((jaar, week), mut prijzen)| {
+            prijzen.sort_unstable();
+            WeekMediaan { week: format!("{}-W{:02}", jaar, week), mediaan_centen: prijzen::mediaan(&prijzen), aantal: prijzen.len() }
+        })
+        .collect()
+}
+
+fn prijs_types(resultaten: &[&Resultaat]) -> Vec<PrijsTypeAandeel> {
+    let mut tellingen: BTreeMap<&str, usize> = BTreeMap::new();
+    for resultaat in resultaten {
+        *tellingen.entry(resultaat.advertentie.prijs_info.prijs_type.as_str()).or_default() += 1;
+    }
+    let mut aandelen: Vec<PrijsTypeAandeel> = tellingen.into_iter()
+        .map(|(prijs_type, aantal)| PrijsTypeAandeel {
+            prijs_type: prijs_type.to_string(),
+            aantal,
+            aandeel_procent: (aantal as f64 * 1000.0 / resultaten.len() as f64).round() / 10.0,
+        })
+        .collect();
+    aandelen.sort_by_key(|a| Reverse(a.aantal)); // Stabiel, bij gelijk aantal op naam
+    aandelen
+}
+
+pub fn bereken(resultaten: &[Resultaat], zoekwoord: &str) -> Statistieken {
+    let eigen: Vec<&Resultaat> = resultaten.iter()
+        .filter(|r| r.zoekwoorden.iter().any(|z| z == zoekwoord))
+        .collect();
+
+    let datums: Vec<NaiveDate> = eigen.iter().filter_map(|r| datum(r)).collect();
+    let vaste_prijzen: Vec<(NaiveDate, i64)> = eigen.iter()
+        .filter_map(|r| Some((datum(r)?, prijzen::vaste_prijs(&r.advertentie)?)))
+        .collect();
+    let mut gesorteerd: Vec<i64> = vaste_prijzen.iter().map(|(_, centen)| *centen).collect();
+    gesorteerd.sort_unstable();
+
+    Statistieken {
+        zoekwoord: zoekwoord.to_string(),
+        alleen_treffers: true,
+        aantal: eigen.len(),
+        mediaan_centen: (!gesorteerd.is_empty()).then(|| prijzen::mediaan(&gesorteerd)),
+        per_dag: per_dag(&datums),
+        histogram: histogram(&gesorteerd),
+        mediaan_per_week: mediaan_per_week(&vaste_prijzen),
+        prijs_types: prijs_types(&eigen),
+    }
+}
@@ -0,0 +1,51 @@
+// HTTPS voor de web interface. Zonder certificaat wordt er bij de eerste start een zelf ondertekend
+// certificaat gemaakt, browsers geven daar een waarschuwing bij maar het verkeer is wel versleuteld.
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+
+pub fn maak_certificaat_indien_nodig(certificaat_pad: &str, sleutel_pad: &str, adres: &str) -> Result<(), Box<dyn Error>> {
+    match (Path::new(certificaat_pad).exists(), Path::new(sleutel_pad).exists()) {
+        (true, true) => return Ok(()),
+        (false, false) => {}
+        _ => return Err(format!("Alleen een van {} en {} bestaat, maak beide of geen van beide", certificaat_pad, sleutel_pad).into()),
+    }
+
+    let mut namen = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+    if adres != "0.0.0.0" && adres != "::" && !namen.contains(&adres.to_string()) {
+        namen.push(adres.to_string());
+    }
+
+    let rcgen::CertifiedKey { cert, key_pair } = rcgen::generate_simple_self_signed(namen)?;
+    fs::write(certificaat_pad, cert.pem())?;
+    fs::write(sleutel_pad, key_pair.serialize_pem())?;
+
+    // De sleutel hoeft niemand anders te lezen
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(sleutel_pad, fs::Permissions::from_mode(0o600))?;
+    }
+
+    println!("Zelf ondertekend certificaat aangemaakt: {} en {}", certificaat_pad, sleutel_pad);
+    Ok(())
+}
+
+pub fn laad_tls_config(certificaat_pad: &str, sleutel_pad: &str) -> Result<Arc<ServerConfig>, Box<dyn Error>> {
+    let certificaten = CertificateDer::pem_file_iter(certificaat_pad)?.collect::<Result<Vec<_>, _>>()?;
+    let sleutel = PrivateKeyDer::from_pem_file(sleutel_pad)?;
+
+    let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certificaten, sleutel)?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(Arc::new(config))
+}
@@ -0,0 +1,208 @@
+// Wat de check lus aan het doen is, voor /status en /healthz. De lus houdt het hier bij, de web interface leest het.
+
+use crate::planning::Gepland;
+use chrono::{DateTime, Duration, Local};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{LazyLock, Mutex};
+
+// Zo lang mag de volgende check over tijd zijn voordat de lus als vastgelopen telt
+const SPEELRUIMTE_SECONDEN: i64 = 300;
+// En zo lang mag een ronde duren
+const MAX_RONDE_MINUTEN: i64 = 30;
+
+static TOESTAND: LazyLock<Mutex<Toestand>> = LazyLock::new(|| Mutex::new(Toestand::nieuw()));
+
+const TIJD_FORMAAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, Default, Clone)]
+struct ItemToestand {
+    laatste_check: Option<DateTime<Local>>,
+    laatst_gelukt: Option<DateTime<Local>>,
+    laatste_fout: Option<String>,
+    volgende_check: Option<DateTime<Local>>,
+    interval_seconden: Option<u64>,
+    treffers_per_check: Option<f64>,
+}
+
+#[derive(Debug)]
+struct Toestand {
+    gestart: DateTime<Local>,
+    ronde_begin: Option<DateTime<Local>>,
+    ronde_einde: Option<DateTime<Local>>,
+    ronde_fout: Option<String>,
+    volgende_check: Option<DateTime<Local>>,
+    items: BTreeMap<String, ItemToestand>,
+    wissen_gevraagd: bool, // De resultaten zijn gewist, de lus moet vergeten wat hij al gezien heeft
+}
+
+impl Toestand {
+    fn nieuw() -> Self {
+        Toestand {
+            gestart: Local::now(),
+            ronde_begin: None,
+            ronde_einde: None,
+            ronde_fout: None,
+            volgende_check: None,
+            items: BTreeMap::new(),
+            wissen_gevraagd: false,
+        }
+    }
+
+    fn ronde_bezig(&self) -> bool {
+        match (self.ronde_begin, self.ronde_einde) {
+            (Some(begin), Some(einde)) => begin > einde,
+            (Some(_), None) => true,
+            _ => false,
+        }
+    }
+
+    // None als alles in orde is, anders de reden
+    fn probleem(&self, nu: DateTime<Local>) -> Option<String> {
+        if self.ronde_bezig() {
+            let begin = self.ronde_begin?;
+            return (nu - begin > Duration::minutes(MAX_RONDE_MINUTEN))
+                .then(|| format!("Ronde loopt al sinds {}", begin.format(TIJD_FORMAAT)));
+        }
+        match self.volgende_check {
+            Some(volgende) if nu - volgende > Duration::seconds(SPEELRUIMTE_SECONDEN) => {
+                Some(format!("Check van {} is niet gedaan", volgende.format(TIJD_FORMAAT)))
+            }
+            // Net gestart en nog geen ronde begonnen
+            None if nu - self.gestart > Duration::seconds(SPEELRUIMTE_SECONDEN) => Some("De check lus is nooit begonnen".to_string()),
+            _ => None,
+        }
+    }
+}
+
+fn tijd(tijdstip: Option<DateTime<Local>>) -> Option<String> {
+    tijdstip.map(|t| t.format(TIJD_FORMAAT).to_string())
+}
+
+pub fn ronde_begonnen() {
+    let mut toestand = TOESTAND.lock().unwrap();
+    toestand.ronde_begin = Some(Local::now());
+    toestand.volgende_check = None;
+}
+
+// Zoekwoorden die niet meer op de wenslijst staan vergeten
+pub fn wenslijst_gelezen(zoekwoorden: &[String]) {
+    TOESTAND.lock().unwrap().items.retain(|zoekwoord, _| zoekwoorden.contains(zoekwoord));
+}
+
+pub fn ronde_klaar(fout: Option<String>, volgende_check: Option<DateTime<Local>>) {
+    let mut toestand = TOESTAND.lock().unwrap();
+    toestand.ronde_einde = Some(Local::now());
+    toestand.ronde_fout = fout;
+    toestand.volgende_check = volgende_check;
+}
+
+// Vanuit de web interface en de API, de lus pakt het op voor de volgende ronde
+pub fn vraag_om_wissen() {
+    TOESTAND.lock().unwrap().wissen_gevraagd = true;
+}
+
+pub fn wissen_gevraagd() -> bool {
+    std::mem::take(&mut TOESTAND.lock().unwrap().wissen_gevraagd)
+}
+
+pub fn item_gepland(gepland: Gepland) {
+    let mut toestand = TOESTAND.lock().unwrap();
+    let item = toestand.items.entry(gepland.zoekwoord).or_default();
+    item.volgende_check = Some(gepland.wanneer);
+    item.interval_seconden = Some(gepland.interval_seconden);
+    item.treffers_per_check = gepland.treffers_per_check;
+}
+
+pub fn item_gecheckt(zoekwoord: &str, fout: Option<String>) {
+    let mut toestand = TOESTAND.lock().unwrap();
+    let nu = Local::now();
+    let item = toestand.items.entry(zoekwoord.to_string()).or_default();
+    item.laatste_check = Some(nu);
+    if fout.is_none() {
+        item.laatst_gelukt = Some(nu);
+    }
+    item.laatste_fout = fout;
+}
+
+#[derive(Debug, Serialize)]
+pub struct RondeStatus {
+    pub bezig: bool,
+    pub laatste_begin: Option<String>,
+    pub laatste_einde: Option<String>,
+    pub laatste_duur_seconden: Option<f64>,
+    pub laatste_fout: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ItemStatus {
+    pub zoekwoord: String,
+    pub laatste_check: Option<String>,
+    pub laatst_gelukt: Option<String>,
+    pub laatste_fout: Option<String>,
+    pub volgende_check: Option<String>,
+    pub interval_seconden: Option<u64>,   // Het interval dat nu geldt, bij adaptief plannen het berekende
+    pub treffers_per_check: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Status {
+    pub versie: &'static str,
+    pub gezond: bool,
+    pub probleem: Option<String>,
+    pub gestart: String,
+    pub uptime_seconden: i64,
+    pub ronde: RondeStatus,
+    pub volgende_check: Option<String>,
+    pub treffers_vandaag: usize,
+    pub wenslijst: Vec<ItemStatus>,
+}
+
+// `treffers_vandaag` komt uit de opslag, dat weet de lus zelf niet na een herstart
+pub fn status(treffers_vandaag: usize) -> Status {
+    let toestand = TOESTAND.lock().unwrap();
+    let nu = Local::now();
+    let probleem = toestand.probleem(nu);
+    let bezig = toestand.ronde_bezig();
+
+    let laatste_duur_seconden = match (toestand.ronde_begin, toestand.ronde_einde) {
+        (Some(begin), Some(einde)) if !bezig => Some((einde - begin).num_milliseconds() as f64 / 1000.0),
+        _ => None,
+    };
+
+    Status {
+        versie: env!("CARGO_PKG_VERSION"),
+        gezond: probleem.is_none(),
+        probleem,
+        gestart: toestand.gestart.format(TIJD_FORMAAT).to_string(),
+        uptime_seconden: (nu - toestand.gestart).num_seconds(),
+        ronde: RondeStatus {
+            bezig,
+            laatste_begin: tijd(toestand.ronde_begin),
+            laatste_einde: tijd(toestand.ronde_einde),
+            laatste_duur_seconden,
+            laatste_fout: toestand.ronde_fout.clone(),
+        },
+        volgende_check: tijd(toestand.volgende_check),
+        treffers_vandaag,
+        wenslijst: toestand.items.iter().map(|(zoekwoord, item)| ItemStatus {
+            zoekwoord: zoekwoord.clone(),
+            laatste_check: tijd(item.laatste_check),
+            laatst_gelukt: tijd(item.laatst_gelukt),
+            laatste_fout: item.laatste_fout.clone(),
+            volgende_check: tijd(item.volgende_check),
+            interval_seconden: item.interval_seconden,
+            treffers_per_check: item.treffers_per_check,
+        }).collect(),
+    }
+}
+
+// Alleen de vraag of de lus nog draait, zonder iets uit de opslag te lezen
+pub fn probleem() -> Option<String> {
+    TOESTAND.lock().unwrap().probleem(Local::now())
+}
+
+// Om de teller bij het starten van het programma te laten beginnen en niet bij de eerste vraag
+pub fn start() {
+    LazyLock::force(&TOESTAND);
+}
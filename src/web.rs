@@ -1,22 +1,92 @@
-use crate::{Configuratie, Monitor, Advertentie};
+use crate::{Configuratie, Monitor, Advertentie, AdvertentieDetails, verkoper_op_lijst};
+use crate::opslag::{self, Resultaat, Staat};
+use crate::{api, export, feed, metrieken, tls, toestand, wenslijst, zoekindex};
+use crate::prijzen::Deal;
+use crate::auth::{self, LoginVerzoek, Sessies};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::cmp::Ordering;
 use std::sync::{Arc, Mutex};
-use std::fs;
-use std::io::{BufRead, BufReader};
+use warp::http::StatusCode;
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use http_body_util::combinators::BoxBody;
+use hyper::service::Service;
+use hyper_util::rt::TokioIo;
 use warp::{Filter, Reply};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
 
 #[derive(Debug, Serialize)]
-struct ResultaatArtikel {
+pub struct ResultaatArtikel {
+    item_id: String,
     tijdstempel: String,
     zoekwoord: String,
+    zoekwoorden: Vec<String>,
     titel: String,
     prijs: String,
+    prijs_centen: Option<i32>,
+    prijs_type: String,
     locatie: String,
     afstand: String,
+    afstand_meters: Option<i32>,
     link: String,
     beschrijving: String,
     afbeelding: Option<String>,
+    verkoper_id: Option<String>,
+    verkoper_naam: Option<String>,
+    vertrouwde_verkoper: bool,
+    herplaatsing_van: Option<String>,
+    herplaatsingen: Vec<String>,
+    staat: Staat,
+    favoriet: bool,
+    notitie: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    relevantie: Option<f64>, // Alleen bij zoeken met q
+    deal: Option<Deal>,
+    details: Option<AdvertentieDetails>,
+}
+
+// Niet elke zoekopdracht geeft de verkoper mee, dan komt hij van de detail pagina
+pub fn verkoper_id(resultaat: &Resultaat) -> Option<String> {
+    resultaat.advertentie.verkoper_id().or_else(|| resultaat.details.as_ref()?.verkoper_id.clone())
+}
+
+pub fn verkoper_naam(resultaat: &Resultaat) -> Option<String> {
+    resultaat.advertentie.verkoper_naam().map(String::from).or_else(|| resultaat.details.as_ref()?.verkoper_naam.clone())
+}
+
+impl From<Resultaat> for ResultaatArtikel {
+    fn from(resultaat: Resultaat) -> Self {
+        let advertentie = &resultaat.advertentie;
+        let (id, naam) = (verkoper_id(&resultaat), verkoper_naam(&resultaat));
+        ResultaatArtikel {
+            item_id: advertentie.item_id.clone(),
+            tijdstempel: resultaat.tijdstempel,
+            zoekwoord: resultaat.zoekwoord,
+            zoekwoorden: resultaat.zoekwoorden,
+            titel: advertentie.titel.clone(),
+            prijs: format!("{}{}", advertentie.prijs_weergave(), advertentie.prijs_type_label()),
+            prijs_centen: advertentie.prijs_info.prijs_centen,
+            prijs_type: advertentie.prijs_info.prijs_type.clone(),
+            locatie: advertentie.locatie.stad_naam.clone().unwrap_or_else(|| "Onbekend".to_string()),
+            afstand: advertentie.afstand_weergave(),
+            afstand_meters: advertentie.locatie.afstand_meters,
+            link: resultaat.link,
+            beschrijving: advertentie.korte_beschrijving(),
+            afbeelding: advertentie.afbeelding_urls.as_ref().and_then(|urls| urls.first()).cloned(),
+            verkoper_id: id,
+            verkoper_naam: naam,
+            vertrouwde_verkoper: false, // Hangt af van de config, zie haal_resultaten
+            herplaatsing_van: resultaat.herplaatsing_van,
+            herplaatsingen: resultaat.herplaatsingen,
+            staat: Staat::Nieuw, // Staat in het status bestand, zie haal_resultaten
+            favoriet: false,
+            notitie: String::new(),
+            relevantie: None,
+            deal: resultaat.deal,
+            details: resultaat.details,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -31,8 +101,62 @@ struct MarkeerGezienVerzoek {
 }
 
 #[derive(Debug, Deserialize)]
-struct ZoekQuery {
+struct StatusVerzoek {
+    item_ids: Vec<String>,
+    staat: Option<Staat>,
+    favoriet: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotitieVerzoek {
+    item_id: String,
+    notitie: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FeedQuery {
+    token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerkoperVerzoek {
+    verkoper: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ZoekQuery {
     q: Option<String>,
+    zoekwoord: Option<String>,
+    staat: Option<Staat>,
+    favoriet: Option<bool>,
+    prijs_min: Option<f64>, // In euro's
+    prijs_max: Option<f64>,
+    afstand_min: Option<f64>, // In km
+    afstand_max: Option<f64>,
+    van: Option<String>, // Datum als 2025-10-01, op de tijdstempel van het vinden
+    tot: Option<String>,
+    prijs_type: Option<String>, // Bijv. "FIXED,BID"
+    sorteer: Option<Sortering>,
+    richting: Option<Richting>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Sortering {
+    Relevantie,
+    Datum,
+    Prijs,
+    Afstand,
+    Deal, // Verschil met de mediaan, beste deal eerst
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Richting {
+    Op,
+    Af,
 }
 
 #[derive(Debug, Deserialize)]
@@ -40,32 +164,96 @@ struct ConfigUpdate {
     postcode: String,
     afstand_km: u32,
     check_interval_seconden: u64,
+    adaptief_interval: Option<bool>,
+    min_interval_seconden: Option<u64>,
+    max_interval_seconden: Option<u64>,
     max_advertenties_per_zoekopdracht: u32,
     toon_bieden: bool,
     toon_gratis: bool,
     toon_zie_beschrijving: bool,
+    haal_details_op: Option<bool>,
+    geblokkeerde_verkopers: Option<Vec<String>>,
+    vertrouwde_verkopers: Option<Vec<String>>,
+    alleen_vertrouwde_verkopers: Option<bool>,
+    onderdruk_herplaatsingen: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
 struct WenslijstUpdate {
     artikelen: Vec<WenslijstArtikelInput>,
+    #[serde(default)]
+    versie: Option<String>, // Uit de ETag van GET /wenslijst, om andermans wijzigingen niet te overschrijven
 }
 
 #[derive(Debug, Deserialize)]
 struct WenslijstArtikelInput {
     zoekwoord: String,
     max_prijs: String,
+    #[serde(default)]
+    interval_seconden: Option<u64>,
+    #[serde(default)]
+    venster: Option<String>,
 }
 
-pub async fn start_web_server(poort: u16, config: Arc<Mutex<Configuratie>>, monitor: Arc<Mutex<Monitor>>) {
+pub async fn start_web_server(poort: u16, config: Arc<Mutex<Configuratie>>) {
+    let configuratie = config.lock().unwrap().clone();
+
+    let ip: IpAddr = match configuratie.web_adres.parse() {
+        Ok(ip) => ip,
+        Err(_) => {
+            eprintln!("Web interface niet gestart: ongeldig web_adres '{}'", configuratie.web_adres);
+            return;
+        }
+    };
+    let adres = SocketAddr::new(ip, poort);
+
+    // Zonder login kan dan iedereen in het netwerk de config en wenslijst aanpassen
+    if !ip.is_loopback() && !auth::staat_aan(&configuratie) {
+        eprintln!("Web interface niet gestart: {} is bereikbaar vanaf andere apparaten, zet eerst web_token of web_wachtwoord in config.toml", ip);
+        return;
+    }
+    if ip.is_unspecified() && configuratie.publieke_url.is_empty() {
+        eprintln!("Let op: de links in de feeds gaan naar localhost, zet publieke_url in config.toml om ze vanaf andere apparaten te kunnen volgen");
+    }
+
+    let sessies: Sessies = Arc::new(Mutex::new(HashMap::new()));
+    let beveiligd = auth::vereis_toegang(config.clone(), sessies.clone());
+    let api_routes = api::routes(config.clone());
+    let sessies_filter = warp::any().map(move || sessies.clone());
     let config_filter = warp::any().map(move || config.clone());
-    let monitor_filter = warp::any().map(move || monitor.clone());
 
     let index = warp::get()
         .and(warp::path::end())
-        .map(|| {
-            let html = index_html();
-            warp::reply::html(html)
+        .and(warp::cookie::optional::<String>(auth::SESSIE_COOKIE))
+        .and(config_filter.clone())
+        .and(sessies_filter.clone())
+        .map(toon_index);
+
+    let login_get = warp::get()
+        .and(warp::path("login"))
+        .and(warp::path::end())
+        .and(config_filter.clone())
+        .map(|config: Arc<Mutex<Configuratie>>| warp::reply::html(login_html(&config.lock().unwrap())));
+
+    let login_post = warp::post()
+        .and(warp::path("login"))
+        .and(warp::path::end())
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .and(sessies_filter.clone())
+        .and_then(log_in);
+
+    let logout = warp::post()
+        .and(warp::path("logout"))
+        .and(warp::cookie::optional::<String>(auth::SESSIE_COOKIE))
+        .and(sessies_filter.clone())
+        .map(|cookie: Option<String>, sessies: Sessies| {
+            auth::beeindig_sessie(&sessies, cookie.as_deref());
+            let antwoord = warp::reply::json(&StatusBericht {
+                status: "ok".to_string(),
+                bericht: "Uitgelogd".to_string(),
+            });
+            warp::reply::with_header(antwoord, "Set-Cookie", auth::verlopen_cookie())
         });
 
     let resultaten = warp::get()
@@ -108,40 +296,360 @@ pub async fn start_web_server(poort: u16, config: Arc<Mutex<Configuratie>>, moni
         .and(config_filter.clone())
         .and_then(markeer_als_gezien);
 
+    let status_post = warp::post()
+        .and(warp::path("status"))
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .and_then(zet_status);
+
+    // De samenvatting van de check lus, POST /status hierboven zet de staat van advertenties
+    let status_get = warp::get()
+        .and(warp::path("status"))
+        .and(warp::path::end())
+        .and(config_filter.clone())
+        .map(api::haal_status);
+
+    let notitie_post = warp::post()
+        .and(warp::path("notitie"))
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .and_then(zet_notitie);
+
+    let metrics = warp::get()
+        .and(warp::path("metrics"))
+        .and(warp::path::end())
+        .map(|| warp::reply::with_header(metrieken::METRIEKEN.tekst(), "Content-Type", "text/plain; version=0.0.4"));
+
+    // Voor docker/systemd/uptime checks, zonder login en zonder iets uit de opslag te lezen
+    let healthz = warp::get()
+        .and(warp::path("healthz"))
+        .and(warp::path::end())
+        .map(|| match toestand::probleem() {
+            None => warp::reply::with_status(warp::reply::json(&serde_json::json!({ "status": "ok" })), StatusCode::OK),
+            Some(reden) => warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({ "status": "fout", "reden": reden })),
+                StatusCode::SERVICE_UNAVAILABLE,
+            ),
+        });
+
+    // Buiten de login, met een eigen token (zie auth::feed_toegestaan)
+    let feed_alles = warp::get()
+        .and(warp::path("feed"))
+        .and(warp::path::end())
+        .map(|| None);
+    let feed_zoekwoord = warp::get()
+        .and(warp::path("feed"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .map(|zoekwoord: String| Some(urlencoding::decode(&zoekwoord).map(|z| z.into_owned()).unwrap_or(zoekwoord)));
+    let feed = feed_alles.or(feed_zoekwoord).unify()
+        .and(warp::query::<FeedQuery>())
+        .and(config_filter.clone())
+        .map(toon_feed);
+
     let wis_resultaten = warp::post()
         .and(warp::path("wis_resultaten"))
         .and(config_filter.clone())
-        .and(monitor_filter.clone())
         .and_then(wis_alle_resultaten);
 
-    let routes = index
-        .or(resultaten)
+    let blokkeer_verkoper = warp::post()
+        .and(warp::path("blokkeer_verkoper"))
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .and_then(blokkeer_verkoper);
+
+    let vertrouw_verkoper = warp::post()
+        .and(warp::path("vertrouw_verkoper"))
+        .and(warp::body::json())
+        .and(config_filter.clone())
+        .and_then(vertrouw_verkoper);
+
+    // Alles behalve de pagina zelf en het inloggen zit achter de login (als die aan staat)
+    let beveiligde_routes = resultaten
         .or(config_get)
         .or(config_post)
         .or(zoek)
         .or(wenslijst_get)
         .or(wenslijst_post)
         .or(markeer_gezien)
-        .or(wis_resultaten);
+        .or(status_post)
+        .or(status_get)
+        .or(notitie_post)
+        .or(wis_resultaten)
+        .or(blokkeer_verkoper)
+        .or(vertrouw_verkoper)
+        .or(logout)
+        .or(metrics)
+        .or(api_routes);
+
+    let routes = index
+        .or(login_get)
+        .or(login_post)
+        .or(healthz)
+        .or(feed)
+        .or(api::openapi_route())
+        .or(beveiligd.and(beveiligde_routes))
+        .recover(verwerk_weigering);
+
+    let tls_config = if configuratie.web_tls {
+        match tls::maak_certificaat_indien_nodig(&configuratie.tls_certificaat, &configuratie.tls_sleutel, &configuratie.web_adres)
+            .and_then(|_| tls::laad_tls_config(&configuratie.tls_certificaat, &configuratie.tls_sleutel)) {
+            Ok(tls_config) => Some(tls_config),
+            Err(e) => {
+                eprintln!("Web interface niet gestart, probleem met het certificaat: {}", e);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+
+    let luisteraar = match tokio::net::TcpListener::bind(adres).await {
+        Ok(luisteraar) => luisteraar,
+        Err(e) => {
+            eprintln!("Web interface niet gestart, kon niet binden aan {}: {}", adres, e);
+            return;
+        }
+    };
+
+    // De verbindingen zelf aannemen en aan hyper geven: warp 0.4 heeft zelf (nog) geen TLS, en kan geen
+    // body streamen voor de export (zie export.rs)
+    println!("Web interface draait op {}://{}", if tls_config.is_some() { "https" } else { "http" }, adres);
+    let acceptor = tls_config.map(tokio_rustls::TlsAcceptor::from);
+    let warp_service = hyper_util::service::TowerToHyperService::new(warp::service(routes));
+    let service = hyper::service::service_fn(move |verzoek| {
+        let antwoord = warp_service.call(verzoek);
+        async move { antwoord.await.map(met_export) }
+    });
+    loop {
+        let (verbinding, _) = match luisteraar.accept().await {
+            Ok(verbinding) => verbinding,
+            Err(e) => {
+                eprintln!("Probleem met een inkomende verbinding: {}", e);
+                continue;
+            }
+        };
+
+        let acceptor = acceptor.clone();
+        let service = service.clone();
+        tokio::spawn(async move {
+            let verbinding_bouwer = hyper::server::conn::http1::Builder::new();
+            match acceptor {
+                Some(acceptor) => {
+                    // Mislukt bijv. als de browser het zelf ondertekende certificaat weigert
+                    let Ok(verbinding) = acceptor.accept(verbinding).await else { return };
+                    verbinding_bouwer.serve_connection(TokioIo::new(verbinding), service).await.ok();
+                }
+                None => {
+                    verbinding_bouwer.serve_connection(TokioIo::new(verbinding), service).await.ok();
+                }
+            }
+        });
+    }
+}
 
-    println!("Web interface draait op http://localhost:{}", poort);
-    warp::serve(routes).run(([127, 0, 0, 1], poort)).await;
+// Een export krijgt hier pas zijn body, de rest gaat ongewijzigd door
+fn met_export(mut antwoord: warp::reply::Response) -> warp::http::Response<BoxBody<Bytes, Box<dyn std::error::Error + Send + Sync>>> {
+    match antwoord.extensions_mut().remove::<export::Opdracht>() {
+        Some(opdracht) => antwoord.map(|_| export::stream(opdracht).map_err(Into::into).boxed()),
+        None => antwoord.map(|body| body.map_err(Into::into).boxed()),
+    }
 }
 
-async fn markeer_als_gezien(verzoek: MarkeerGezienVerzoek, config: Arc<Mutex<Configuratie>>) -> Result<impl Reply, warp::Rejection> {
-    let configuratie = config.lock().unwrap();
-    let gezien_bestand = "gezien.txt";
-    
-    if let Ok(mut bestand) = fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(gezien_bestand) {
-        
-        use std::io::Write;
-        for link in verzoek.links {
-            writeln!(bestand, "{}", link).ok();
+fn toon_feed(zoekwoord: Option<String>, query: FeedQuery, config: Arc<Mutex<Configuratie>>) -> warp::reply::Response {
+    let configuratie = config.lock().unwrap().clone();
+    if !auth::feed_toegestaan(&configuratie, query.token.as_deref()) {
+        let bericht = if configuratie.feed_token.is_empty() {
+            "De login staat aan, zet een feed_token in config.toml om de feeds te gebruiken"
+        } else {
+            "Ongeldig of ontbrekend feed token"
+        };
+        return api::fout(StatusCode::UNAUTHORIZED, "niet_ingelogd", bericht);
+    }
+
+    let mut resultaten = opslag::lees_resultaten(&configuratie.opslag_bestand).unwrap_or_default();
+    resultaten.retain(|resultaat| !verkoper_op_lijst(
+        &configuratie.geblokkeerde_verkopers,
+        verkoper_id(resultaat).as_deref(),
+        verkoper_naam(resultaat).as_deref(),
+    ));
+    let statussen = opslag::lees_statussen(&configuratie.status_bestand).unwrap_or_default();
+    let basis_url = feed_basis_url(&configuratie);
+    let pad = match &zoekwoord {
+        Some(zoekwoord) => format!("/feed/{}", urlencoding::encode(zoekwoord)),
+        None => "/feed".to_string(),
+    };
+
+    let atom = feed::atom(&resultaten, &statussen, zoekwoord.as_deref(), &basis_url, &pad);
+    warp::reply::with_header(atom, "Content-Type", "application/atom+xml; charset=utf-8").into_response()
+}
+
+// Niet uit de Host header, een feed reader of cache zou anders links naar een verzonnen domein bewaren.
+// Op 0.0.0.0 of :: luistert de monitor overal, daar kan een link niet heen, dan maar localhost
+fn feed_basis_url(configuratie: &Configuratie) -> String {
+    if !configuratie.publieke_url.is_empty() {
+        return configuratie.publieke_url.trim_end_matches('/').to_string();
+    }
+    let schema = if configuratie.web_tls { "https" } else { "http" };
+    let host = match configuratie.web_adres.parse::<IpAddr>() {
+        Ok(ip) if ip.is_unspecified() => "localhost".to_string(),
+        Ok(IpAddr::V6(ip)) => format!("[{}]", ip),
+        _ => configuratie.web_adres.clone(),
+    };
+    format!("{}://{}:{}", schema, host, configuratie.web_poort)
+}
+
+fn toon_index(cookie: Option<String>, config: Arc<Mutex<Configuratie>>, sessies: Sessies) -> warp::reply::Response {
+    let login_aan = auth::staat_aan(&config.lock().unwrap());
+    let csrf_token = match auth::zoek_sessie(&sessies, cookie.as_deref()) {
+        Some(sessie) => sessie.csrf_token,
+        None if login_aan => {
+            return warp::redirect::see_other(warp::http::Uri::from_static("/login")).into_response();
         }
+        None => String::new(),
+    };
+    warp::reply::html(index_html().replace("{{CSRF_TOKEN}}", &csrf_token)).into_response()
+}
+
+async fn log_in(verzoek: LoginVerzoek, config: Arc<Mutex<Configuratie>>, sessies: Sessies) -> Result<warp::reply::Response, warp::Rejection> {
+    let configuratie = config.lock().unwrap().clone();
+
+    if !auth::staat_aan(&configuratie) || !auth::controleer_login(&configuratie, &verzoek) {
+        // Raden wat langzamer maken
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        let antwoord = warp::reply::json(&StatusBericht {
+            status: "error".to_string(),
+            bericht: "Onjuiste inloggegevens".to_string(),
+        });
+        return Ok(warp::reply::with_status(antwoord, StatusCode::UNAUTHORIZED).into_response());
+    }
+
+    let sessie_id = auth::nieuwe_sessie(&sessies);
+    let antwoord = warp::reply::json(&StatusBericht {
+        status: "ok".to_string(),
+        bericht: "Ingelogd".to_string(),
+    });
+    Ok(warp::reply::with_header(antwoord, "Set-Cookie", auth::sessie_cookie(&sessie_id, configuratie.web_tls)).into_response())
+}
+
+async fn verwerk_weigering(weigering: warp::Rejection) -> Result<warp::reply::Response, warp::Rejection> {
+    if weigering.find::<auth::NietIngelogd>().is_some() {
+        return Ok(api::fout(StatusCode::UNAUTHORIZED, "niet_ingelogd", "Niet ingelogd"));
+    }
+    if weigering.find::<auth::OngeldigCsrf>().is_some() {
+        return Ok(api::fout(StatusCode::FORBIDDEN, "ongeldig_csrf", "Ongeldig of ontbrekend CSRF token"));
     }
+    api::fout_voor_weigering(&weigering).ok_or(weigering)
+}
+
+pub fn bewaar_config(configuratie: &Configuratie) {
+    if let Err(e) = configuratie.bewaar() {
+        eprintln!("Kon config niet opslaan in {}: {}", configuratie.config_bestand, e);
+    }
+}
+
+async fn blokkeer_verkoper(verzoek: VerkoperVerzoek, config: Arc<Mutex<Configuratie>>) -> Result<impl Reply, warp::Rejection> {
+    let mut configuratie = config.lock().unwrap();
+    let verkoper = verzoek.verkoper.trim().to_string();
+
+    if !verkoper.is_empty() {
+        let aantal = configuratie.vertrouwde_verkopers.len();
+        configuratie.vertrouwde_verkopers.retain(|v| !verkoper_op_lijst(std::slice::from_ref(v), Some(&verkoper), Some(&verkoper)));
+        let nieuw = !verkoper_op_lijst(&configuratie.geblokkeerde_verkopers, Some(&verkoper), Some(&verkoper));
+        if nieuw {
+            configuratie.geblokkeerde_verkopers.push(verkoper.clone());
+        }
+        if nieuw || configuratie.vertrouwde_verkopers.len() != aantal {
+            bewaar_config(&configuratie);
+        }
+    }
+
+    Ok(warp::reply::json(&StatusBericht {
+        status: "ok".to_string(),
+        bericht: format!("Verkoper {} geblokkeerd", verkoper),
+    }))
+}
+
+async fn vertrouw_verkoper(verzoek: VerkoperVerzoek, config: Arc<Mutex<Configuratie>>) -> Result<impl Reply, warp::Rejection> {
+    let mut configuratie = config.lock().unwrap();
+    let verkoper = verzoek.verkoper.trim().to_string();
+
+    if !verkoper.is_empty() {
+        let aantal = configuratie.geblokkeerde_verkopers.len();
+        configuratie.geblokkeerde_verkopers.retain(|v| !verkoper_op_lijst(std::slice::from_ref(v), Some(&verkoper), Some(&verkoper)));
+        let nieuw = !verkoper_op_lijst(&configuratie.vertrouwde_verkopers, Some(&verkoper), Some(&verkoper));
+        if nieuw {
+            configuratie.vertrouwde_verkopers.push(verkoper.clone());
+        }
+        if nieuw || configuratie.geblokkeerde_verkopers.len() != aantal {
+            bewaar_config(&configuratie);
+        }
+    }
+
+    Ok(warp::reply::json(&StatusBericht {
+        status: "ok".to_string(),
+        bericht: format!("Verkoper {} vertrouwd", verkoper),
+    }))
+}
+
+async fn zet_status(verzoek: StatusVerzoek, config: Arc<Mutex<Configuratie>>) -> Result<impl Reply, warp::Rejection> {
+    let status_bestand = config.lock().unwrap().status_bestand.clone();
+
+    let resultaat = opslag::pas_statussen_aan(&status_bestand, &verzoek.item_ids, |status| {
+        if let Some(staat) = verzoek.staat {
+            status.staat = staat;
+        }
+        if let Some(favoriet) = verzoek.favoriet {
+            status.favoriet = favoriet;
+        }
+    });
+
+    if let Err(e) = resultaat {
+        return Ok(warp::reply::json(&StatusBericht {
+            status: "error".to_string(),
+            bericht: format!("Kon status niet opslaan: {}", e),
+        }));
+    }
+
+    Ok(warp::reply::json(&StatusBericht {
+        status: "ok".to_string(),
+        bericht: format!("Status van {} artikel(en) bijgewerkt", verzoek.item_ids.len()),
+    }))
+}
+
+async fn zet_notitie(verzoek: NotitieVerzoek, config: Arc<Mutex<Configuratie>>) -> Result<impl Reply, warp::Rejection> {
+    let status_bestand = config.lock().unwrap().status_bestand.clone();
+    let notitie = verzoek.notitie.trim().to_string();
+
+    if let Err(e) = opslag::pas_statussen_aan(&status_bestand, &[verzoek.item_id], |status| status.notitie = notitie.clone()) {
+        return Ok(warp::reply::json(&StatusBericht {
+            status: "error".to_string(),
+            bericht: format!("Kon notitie niet opslaan: {}", e),
+        }));
+    }
+
+    Ok(warp::reply::json(&StatusBericht {
+        status: "ok".to_string(),
+        bericht: "Notitie opgeslagen".to_string(),
+    }))
+}
+
+// Oudere manier, met links i.p.v. item ids
+async fn markeer_als_gezien(verzoek: MarkeerGezienVerzoek, config: Arc<Mutex<Configuratie>>) -> Result<impl Reply, warp::Rejection> {
+    let configuratie = config.lock().unwrap().clone();
+
+    let item_ids: Vec<String> = opslag::lees_resultaten(&configuratie.opslag_bestand)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|r| verzoek.links.contains(&r.link))
+        .map(|r| r.advertentie.item_id)
+        .collect();
+
+    opslag::pas_statussen_aan(&configuratie.status_bestand, &item_ids, |status| {
+        if status.staat == Staat::Nieuw {
+            status.staat = Staat::Gezien;
+        }
+    }).ok();
     
     Ok(warp::reply::json(&StatusBericht {
         status: "ok".to_string(),
@@ -149,25 +657,16 @@ async fn markeer_als_gezien(verzoek: MarkeerGezienVerzoek, config: Arc<Mutex<Con
     }))
 }
 
-async fn wis_alle_resultaten(config: Arc<Mutex<Configuratie>>, monitor: Arc<Mutex<Monitor>>) -> Result<impl Reply, warp::Rejection> {
-    let configuratie = config.lock().unwrap();
-    let bestand_pad = &configuratie.resultaten_bestand;
+async fn wis_alle_resultaten(config: Arc<Mutex<Configuratie>>) -> Result<impl Reply, warp::Rejection> {
+    let configuratie = config.lock().unwrap().clone();
     
-    if let Err(_) = fs::write(bestand_pad, "") {
+    if api::wis_alles(&configuratie).is_err() {
         return Ok(warp::reply::json(&StatusBericht {
             status: "error".to_string(),
             bericht: "Kon resultaten niet wissen".to_string(),
         }));
     }
     
-    let gezien_bestand = "gezien.txt";
-    if Path::new(gezien_bestand).exists() {
-        fs::remove_file(gezien_bestand).ok();
-    }
-    
-    let mut monitor_lock = monitor.lock().unwrap();
-    monitor_lock.gezien_advertenties.clear();
-    
     Ok(warp::reply::json(&StatusBericht {
         status: "ok".to_string(),
         bericht: "Alle resultaten en gezien artikelen permanent gewist".to_string(),
@@ -175,120 +674,216 @@ async fn wis_alle_resultaten(config: Arc<Mutex<Configuratie>>, monitor: Arc<Mute
 }
 
 async fn haal_resultaten(query: ZoekQuery, config: Arc<Mutex<Configuratie>>) -> Result<impl Reply, warp::Rejection> {
-    let configuratie = config.lock().unwrap();
-    let bestand_pad = &configuratie.resultaten_bestand;
-    
-    let mut resultaten = Vec::new();
-    
-    if let Ok(bestand) = fs::File::open(bestand_pad) {
-        let lezer = BufReader::new(bestand);
-        let mut huidig_artikel: Option<ResultaatArtikel> = None;
-        
-        for lijn in lezer.lines() {
-            if let Ok(lijn) = lijn {
-                if lijn.starts_with("[") && lijn.contains("] Gevonden:") {
-                    if let Some(artikel) = huidig_artikel.take() {
-                        resultaten.push(artikel);
-                    }
-                    
-                    let onderdelen: Vec<&str> = lijn.splitn(2, "] Gevonden: ").collect();
-                    if onderdelen.len() == 2 {
-                        let tijdstempel = onderdelen[0].trim_start_matches('[').to_string();
-                        let rest = onderdelen[1];
-                        let zoekwoord_onderdelen: Vec<&str> = rest.splitn(2, "' (max €").collect();
-                        let zoekwoord = zoekwoord_onderdelen[0].trim_start_matches('\'').to_string();
-                        
-                        huidig_artikel = Some(ResultaatArtikel {
-                            tijdstempel,
-                            zoekwoord,
-                            titel: String::new(),
-                            prijs: String::new(),
-                            locatie: String::new(),
-                            afstand: String::new(),
-                            link: String::new(),
-                            beschrijving: String::new(),
-                            afbeelding: None,
-                        });
-                    }
-                } else if lijn.contains("Titel: ") {
-                    if let Some(ref mut artikel) = huidig_artikel {
-                        artikel.titel = lijn.trim().trim_start_matches("Titel: ").to_string();
-                    }
-                } else if lijn.contains("Prijs: ") {
-                    if let Some(ref mut artikel) = huidig_artikel {
-                        artikel.prijs = lijn.trim().trim_start_matches("Prijs: ").to_string();
-                    }
-                } else if lijn.contains("Locatie: ") {
-                    if let Some(ref mut artikel) = huidig_artikel {
-                        let locatie_str = lijn.trim().trim_start_matches("Locatie: ");
-                        if let Some(pos) = locatie_str.rfind(" (") {
-                            artikel.locatie = locatie_str[..pos].to_string();
-                            artikel.afstand = locatie_str[pos+2..].trim_end_matches(')').to_string();
-                        } else {
-                            artikel.locatie = locatie_str.to_string();
-                        }
-                    }
-                } else if lijn.contains("Link: ") {
-                    if let Some(ref mut artikel) = huidig_artikel {
-                        artikel.link = lijn.trim().trim_start_matches("Link: ").to_string();
-                    }
-                } else if lijn.contains("Afbeelding: ") {
-                    if let Some(ref mut artikel) = huidig_artikel {
-                        let afb = lijn.trim().trim_start_matches("Afbeelding: ").to_string();
-                        if afb != "Geen afbeelding" && !afb.is_empty() {
-                            artikel.afbeelding = Some(afb);
-                        }
-                    }
-                } else if lijn.contains("Beschrijving: ") {
-                    if let Some(ref mut artikel) = huidig_artikel {
-                        artikel.beschrijving = lijn.trim().trim_start_matches("Beschrijving: ").to_string();
-                    }
-                }
-            }
-        }
-        
-        if let Some(artikel) = huidig_artikel {
-            resultaten.push(artikel);
+    let configuratie = config.lock().unwrap().clone();
+    let (pagina, totaal) = zoek_resultaten(&configuratie, query);
+    Ok(warp::reply::with_header(warp::reply::json(&pagina), "X-Totaal", totaal.to_string()))
+}
+
+// Alle opgeslagen resultaten met hun status, zonder die van geblokkeerde verkopers
+fn lees_artikelen(configuratie: &Configuratie) -> Vec<ResultaatArtikel> {
+    let mut resultaten: Vec<ResultaatArtikel> = opslag::lees_resultaten(&configuratie.opslag_bestand)
+        .unwrap_or_default()
+        .into_iter()
+        .map(ResultaatArtikel::from)
+        .collect();
+
+    // Wat al bewaard was voordat de verkoper geblokkeerd werd ook verbergen
+    resultaten.retain(|r| !verkoper_op_lijst(
+        &configuratie.geblokkeerde_verkopers,
+        r.verkoper_id.as_deref(),
+        r.verkoper_naam.as_deref(),
+    ));
+
+    let statussen = opslag::lees_statussen(&configuratie.status_bestand).unwrap_or_default();
+
+    for resultaat in resultaten.iter_mut() {
+        resultaat.vertrouwde_verkoper = verkoper_op_lijst(
+            &configuratie.vertrouwde_verkopers,
+            resultaat.verkoper_id.as_deref(),
+            resultaat.verkoper_naam.as_deref(),
+        );
+        if let Some(status) = statussen.get(&resultaat.item_id) {
+            resultaat.staat = status.staat;
+            resultaat.favoriet = status.favoriet;
+            resultaat.notitie = status.notitie.clone();
         }
     }
+
+    resultaten
+}
+
+pub fn haal_resultaat(configuratie: &Configuratie, item_id: &str) -> Option<ResultaatArtikel> {
+    lees_artikelen(configuratie).into_iter().find(|r| r.item_id == item_id)
+}
+
+// Filteren, sorteren en een pagina eruit halen. Geeft ook het totaal aantal voor de filters terug
+pub fn zoek_resultaten(configuratie: &Configuratie, query: ZoekQuery) -> (Vec<ResultaatArtikel>, usize) {
+    let mut resultaten = lees_artikelen(configuratie);
+
+    // Verborgen artikelen alleen als er specifiek om gevraagd wordt
+    match query.staat {
+        Some(staat) => resultaten.retain(|r| r.staat == staat),
+        None => resultaten.retain(|r| r.staat != Staat::Verborgen),
+    }
+
+    if let Some(favoriet) = query.favoriet {
+        resultaten.retain(|r| r.favoriet == favoriet);
+    }
     
-    if let Some(zoekterm) = query.q {
-        let zoekterm_lower = zoekterm.to_lowercase();
-        resultaten.retain(|r| {
-            r.titel.to_lowercase().contains(&zoekterm_lower) ||
-            r.beschrijving.to_lowercase().contains(&zoekterm_lower) ||
-            r.zoekwoord.to_lowercase().contains(&zoekterm_lower)
+    let zoekterm = query.q.filter(|q| !q.trim().is_empty());
+    if let Some(zoekterm) = &zoekterm {
+        let scores = zoekindex::zoek(&configuratie.opslag_bestand, zoekterm);
+        resultaten.retain_mut(|r| {
+            r.relevantie = scores.get(&r.item_id).copied();
+            r.relevantie.is_some()
         });
     }
+
+    if let Some(zoekwoord) = query.zoekwoord {
+        resultaten.retain(|r| r.zoekwoorden.contains(&zoekwoord));
+    }
+
+    // Advertenties zonder prijs (bieden etc.) vallen af zodra er op prijs gefilterd wordt, net zo voor afstand
+    if query.prijs_min.is_some() || query.prijs_max.is_some() {
+        let min = (query.prijs_min.unwrap_or(0.0) * 100.0).round() as i32;
+        let max = query.prijs_max.map(|p| (p * 100.0).round() as i32).unwrap_or(i32::MAX);
+        resultaten.retain(|r| r.prijs_centen.is_some_and(|c| c >= min && c <= max));
+    }
+
+    if query.afstand_min.is_some() || query.afstand_max.is_some() {
+        let min = (query.afstand_min.unwrap_or(0.0) * 1000.0).round() as i32;
+        let max = query.afstand_max.map(|km| (km * 1000.0).round() as i32).unwrap_or(i32::MAX);
+        resultaten.retain(|r| r.afstand_meters.is_some_and(|m| m >= min && m <= max));
+    }
+
+    // Tijdstempels zijn "%Y-%m-%d %H:%M:%S", dus de datum vergelijken als tekst werkt
+    if let Some(van) = query.van.filter(|v| !v.is_empty()) {
+        resultaten.retain(|r| r.tijdstempel.get(..10).unwrap_or(&r.tijdstempel) >= van.as_str());
+    }
+    if let Some(tot) = query.tot.filter(|t| !t.is_empty()) {
+        resultaten.retain(|r| r.tijdstempel.get(..10).unwrap_or(&r.tijdstempel) <= tot.as_str());
+    }
+
+    if let Some(prijs_types) = query.prijs_type.filter(|p| !p.is_empty()) {
+        let prijs_types: Vec<String> = prijs_types.split(',').map(|p| p.trim().to_uppercase()).collect();
+        resultaten.retain(|r| prijs_types.contains(&r.prijs_type));
+    }
     
+    // Nieuwste eerst, het bestand staat op volgorde van vinden
     resultaten.reverse();
-    
-    Ok(warp::reply::json(&resultaten))
+
+    let sortering = query.sorteer.unwrap_or(match zoekterm {
+        Some(_) => Sortering::Relevantie,
+        None => Sortering::Datum,
+    });
+    let richting = query.richting.unwrap_or(match sortering {
+        Sortering::Relevantie | Sortering::Datum => Richting::Af,
+        _ => Richting::Op,
+    });
+    sorteer_resultaten(&mut resultaten, sortering, richting);
+
+    let totaal = resultaten.len();
+    let pagina: Vec<ResultaatArtikel> = resultaten.into_iter()
+        .skip(query.offset.unwrap_or(0))
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect();
+
+    (pagina, totaal)
+}
+
+// Zonder prijs, afstand of deal komt altijd achteraan, welke richting ook
+fn sorteer_resultaten(resultaten: &mut [ResultaatArtikel], sortering: Sortering, richting: Richting) {
+    fn vergelijk<T: PartialOrd>(a: Option<T>, b: Option<T>, richting: Richting) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => match richting {
+                Richting::Op => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                Richting::Af => b.partial_cmp(&a).unwrap_or(Ordering::Equal),
+            },
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    // Stabiel sorteren, zodat gelijke waardes nieuwste eerst blijven
+    match sortering {
+        // Het bestand staat al op volgorde van vinden, nieuwste eerst
+        Sortering::Datum => if let Richting::Op = richting {
+            resultaten.reverse();
+        },
+        Sortering::Relevantie => resultaten.sort_by(|a, b| vergelijk(a.relevantie, b.relevantie, richting)),
+        Sortering::Prijs => resultaten.sort_by(|a, b| vergelijk(a.prijs_centen, b.prijs_centen, richting)),
+        Sortering::Afstand => resultaten.sort_by(|a, b| vergelijk(a.afstand_meters, b.afstand_meters, richting)),
+        Sortering::Deal => resultaten.sort_by(|a, b| {
+            vergelijk(a.deal.as_ref().map(|d| d.verschil_procent), b.deal.as_ref().map(|d| d.verschil_procent), richting)
+        }),
+    }
+}
+
+// Geheimen blijven op de server
+pub fn zonder_geheimen(configuratie: &Configuratie) -> Configuratie {
+    let mut configuratie = configuratie.clone();
+    configuratie.web_token.clear();
+    configuratie.web_wachtwoord.clear();
+    configuratie.web_wachtwoord_hash.clear();
+    configuratie.api_tokens.clear();
+    configuratie.feed_token.clear();
+    configuratie
 }
 
 async fn haal_config(config: Arc<Mutex<Configuratie>>) -> Result<impl Reply, warp::Rejection> {
-    let configuratie = config.lock().unwrap();
-    Ok(warp::reply::json(&*configuratie))
+    let configuratie = zonder_geheimen(&config.lock().unwrap());
+    Ok(warp::reply::json(&configuratie))
 }
 
 async fn update_config(update: ConfigUpdate, config: Arc<Mutex<Configuratie>>) -> Result<impl Reply, warp::Rejection> {
-    let mut configuratie = config.lock().unwrap();
+    let mut gedeeld = config.lock().unwrap();
+    let mut configuratie = gedeeld.clone();
     
     configuratie.postcode = update.postcode;
     configuratie.afstand_km = update.afstand_km;
     configuratie.check_interval_seconden = update.check_interval_seconden;
+    if let Some(adaptief) = update.adaptief_interval {
+        configuratie.adaptief_interval = adaptief;
+    }
+    if let Some(min) = update.min_interval_seconden {
+        configuratie.min_interval_seconden = min;
+    }
+    if let Some(max) = update.max_interval_seconden {
+        configuratie.max_interval_seconden = max;
+    }
     configuratie.max_advertenties_per_zoekopdracht = update.max_advertenties_per_zoekopdracht;
     configuratie.toon_bieden = update.toon_bieden;
     configuratie.toon_gratis = update.toon_gratis;
     configuratie.toon_zie_beschrijving = update.toon_zie_beschrijving;
+    if let Some(haal_details_op) = update.haal_details_op {
+        configuratie.haal_details_op = haal_details_op;
+    }
+    if let Some(geblokkeerd) = update.geblokkeerde_verkopers {
+        configuratie.geblokkeerde_verkopers = geblokkeerd.into_iter().filter(|v| !v.trim().is_empty()).collect();
+    }
+    if let Some(vertrouwd) = update.vertrouwde_verkopers {
+        configuratie.vertrouwde_verkopers = vertrouwd.into_iter().filter(|v| !v.trim().is_empty()).collect();
+    }
+    if let Some(alleen_vertrouwd) = update.alleen_vertrouwde_verkopers {
+        configuratie.alleen_vertrouwde_verkopers = alleen_vertrouwd;
+    }
+    if let Some(onderdruk) = update.onderdruk_herplaatsingen {
+        configuratie.onderdruk_herplaatsingen = onderdruk;
+    }
+
+    // Net zo streng als PATCH /api/v1/config
+    if let Err(bericht) = configuratie.controleer() {
+        let antwoord = warp::reply::json(&StatusBericht { status: "error".to_string(), bericht });
+        return Ok(warp::reply::with_status(antwoord, StatusCode::BAD_REQUEST));
+    }
+    *gedeeld = configuratie;
+    bewaar_config(&gedeeld);
     
-    let toml_string = toml::to_string_pretty(&*configuratie).unwrap();
-    fs::write("config.toml", toml_string).ok();
-    
-    Ok(warp::reply::json(&StatusBericht {
+    Ok(warp::reply::with_status(warp::reply::json(&StatusBericht {
         status: "ok".to_string(),
         bericht: "Configuratie opgeslagen".to_string(),
-    }))
+    }), StatusCode::OK))
 }
 
 #[derive(Debug, Deserialize)]
@@ -316,61 +911,73 @@ async fn zoek_nu(verzoek: ZoekVerzoek, config: Arc<Mutex<Configuratie>>) -> Resu
 }
 
 async fn haal_wenslijst(config: Arc<Mutex<Configuratie>>) -> Result<impl Reply, warp::Rejection> {
-    let configuratie = config.lock().unwrap();
-    let bestand_pad = &configuratie.wenslijst_bestand;
-    
-    let mut artikelen = Vec::new();
-    
-    if let Ok(bestand) = fs::File::open(bestand_pad) {
-        let lezer = BufReader::new(bestand);
-        
-        for lijn in lezer.lines() {
-            if let Ok(lijn) = lijn {
-                let lijn = lijn.trim();
-                
-                if lijn.is_empty() || lijn.starts_with("#") {
-                    continue;
-                }
-                
-                if lijn.contains(";") {
-                    let onderdelen: Vec<&str> = lijn.splitn(2, ";").collect();
-                    if onderdelen.len() == 2 {
-                        artikelen.push(serde_json::json!({
-                            "zoekwoord": onderdelen[0].trim(),
-                            "max_prijs": onderdelen[1].trim()
-                        }));
-                    }
+    let bestand_pad = config.lock().unwrap().wenslijst_bestand.clone();
+    let (regels, versie) = wenslijst::lees(&bestand_pad).unwrap_or_default();
+
+    let artikelen: Vec<serde_json::Value> = regels.iter()
+        .map(|regel| {
+            let mut artikel = serde_json::json!({
+                "zoekwoord": regel.zoekopdracht.zoekwoord,
+                "max_prijs": match regel.zoekopdracht.max_percentiel {
+                    Some(percentiel) => format!("p{}", percentiel),
+                    None => regel.zoekopdracht.max_prijs.to_string(),
                 }
+            });
+            if let Some(interval) = regel.zoekopdracht.interval_seconden {
+                artikel["interval_seconden"] = interval.into();
             }
-        }
-    }
-    
-    Ok(warp::reply::json(&artikelen))
+            if let Some(venster) = &regel.zoekopdracht.venster {
+                artikel["venster"] = venster.clone().into();
+            }
+            artikel
+        })
+        .collect();
+
+    Ok(warp::reply::with_header(warp::reply::json(&artikelen), "ETag", format!("\"{}\"", versie)))
 }
 
+// Vervangt alle zoekwoorden in een keer, de web interface past ze nu los aan via /api/v1/wenslijst
 async fn update_wenslijst(update: WenslijstUpdate, config: Arc<Mutex<Configuratie>>) -> Result<impl Reply, warp::Rejection> {
-    let configuratie = config.lock().unwrap();
-    let bestand_pad = &configuratie.wenslijst_bestand;
-    
-    let mut inhoud = String::from("# Marktplaats Wensenlijst\n");
-    inhoud.push_str("# Formaat: zoekwoord;maximaleprijs\n");
-    inhoud.push_str("# Om te commenteren gebruikt u #\n");
-    inhoud.push_str("# Als u geen maximale prijs wilt, stelt u de prijs in als -1\n");
-    inhoud.push_str("# Wilt u gratis producten, doe 0 als de prijs\n\n");
-    
+    let bestand_pad = config.lock().unwrap().wenslijst_bestand.clone();
+
+    let mut regels = Vec::new();
     for artikel in update.artikelen {
-        inhoud.push_str(&format!("{};{}\n", artikel.zoekwoord, artikel.max_prijs));
+        // "p25" is een percentiel in plaats van een prijs
+        let prijs = artikel.max_prijs.trim();
+        let geparseerd = match prijs.strip_prefix('p') {
+            Some(percentiel) => percentiel.parse::<u8>().map(|p| (-1, Some(p))).ok(),
+            None => prijs.parse::<i32>().map(|p| (p, None)).ok(),
+        };
+        let zoekopdracht = geparseerd.ok_or_else(|| format!("Ongeldige prijs '{}'", artikel.max_prijs))
+            .map(|(max_prijs, max_percentiel)| wenslijst::Zoekopdracht {
+                zoekwoord: artikel.zoekwoord,
+                max_prijs,
+                max_percentiel,
+                interval_seconden: artikel.interval_seconden,
+                venster: artikel.venster,
+            })
+            .and_then(|zoekopdracht| wenslijst::controleer(&zoekopdracht).map(|_| zoekopdracht));
+        match zoekopdracht {
+            Ok(zoekopdracht) => regels.push(zoekopdracht),
+            Err(bericht) => {
+                let antwoord = warp::reply::json(&StatusBericht { status: "error".to_string(), bericht });
+                return Ok(warp::reply::with_status(antwoord, StatusCode::BAD_REQUEST));
+            }
+        }
     }
-    
-    fs::write(bestand_pad, inhoud).ok();
-    
-    Ok(warp::reply::json(&StatusBericht {
-        status: "ok".to_string(),
-        bericht: "Wenslijst opgeslagen".to_string(),
-    }))
-}
 
+    let (status, bericht) = match wenslijst::vervang_alles(&bestand_pad, &regels, update.versie.as_deref()) {
+        Ok(_) => (StatusCode::OK, "Wenslijst opgeslagen".to_string()),
+        Err(e) if e.is::<wenslijst::Conflict>() => (StatusCode::CONFLICT, e.to_string()),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Kon wenslijst niet opslaan: {}", e)),
+    };
 
+    let antwoord = warp::reply::json(&StatusBericht {
+        status: if status == StatusCode::OK { "ok" } else { "error" }.to_string(),
+        bericht,
+    });
+    Ok(warp::reply::with_status(antwoord, status))
+}
 
 fn index_html() -> String {
     r#"<!DOCTYPE html>
@@ -378,14 +985,28 @@ fn index_html() -> String {
 <head>
     <meta charset="utf-8">
     <title>Marktplaats Monitor</title>
+    <link rel="alternate" type="application/atom+xml" title="Nieuwe advertenties" href="/feed">
     <style>
         body { font-family: Arial; margin: 20px; background: #f5f5f5; min-height: 100vh; display: flex; flex-direction: column; }
         .content { flex: 1; }
         h1 { color: #333; }
+        .wenslijst-artikel .planning { font-size: 12px; color: #666; margin-top: 3px; }
+        .statistiek { border: 1px solid #ddd; border-radius: 5px; padding: 10px 15px; margin-bottom: 15px; }
+        .statistiek h3 { margin: 0 0 5px 0; }
+        .grafieken { display: flex; flex-wrap: wrap; gap: 20px; }
+        .grafiek { flex: 1 1 300px; }
+        .grafiek h4 { margin: 10px 0 5px 0; font-size: 14px; color: #666; }
+        .staven { display: flex; align-items: flex-end; gap: 2px; height: 120px; border-bottom: 1px solid #ccc; }
+        .staaf { flex: 1; background: rgb(255, 143, 68); min-height: 1px; }
+        .staaf-labels { display: flex; justify-content: space-between; font-size: 11px; color: #666; }
+        .status-paneel { background: #f8f9fa; border: 1px solid #ddd; border-radius: 5px; padding: 8px 12px; margin: 10px 0; font-size: 14px; }
+        .status-paneel.probleem { background: #f8d7da; border-color: #f5c6cb; }
+        .status-paneel .fout { color: #721c24; }
         .status-bericht { padding: 10px; margin: 10px 0; border-radius: 5px; display: none; }
         .status-bericht.success { background: #d4edda; color: #155724; border: 1px solid #c3e6cb; }
         .status-bericht.error { background: #f8d7da; color: #721c24; border: 1px solid #f5c6cb; }
         .zoekbalk { margin: 20px 0; }
+        .filters input[type=number] { width: 80px; }
         input[type="text"] { padding: 8px; width: 300px; }
         button { padding: 8px 16px; background: rgb(255, 143, 68); color: white; border: none; cursor: pointer; margin-right: 5px; }
         button:hover { background: #0056b3; }
@@ -414,6 +1035,22 @@ fn index_html() -> String {
         .wenslijst-artikel input { flex: 1; padding: 8px; }
         .wenslijst-artikel button { padding: 5px 10px; background: #dc3545; }
         .wenslijst-artikel button:hover { background: #c82333; }
+        .wenslijst-artikel button.opslaan { background: #28a745; }
+        .wenslijst-artikel button.opslaan:hover { background: #218838; }
+        .details-btn { padding: 4px 10px; font-size: 12px; background: #6c757d; }
+        .details { display: none; clear: both; margin-top: 10px; padding: 10px; background: #f9f9f9; border: 1px solid #eee; border-radius: 5px; }
+        .details.open { display: block; }
+        .details img { max-width: 120px; max-height: 120px; margin: 0 5px 5px 0; float: none; }
+        .details pre { white-space: pre-wrap; font-family: inherit; }
+        .verkoper-btn { padding: 4px 10px; font-size: 12px; background: #6c757d; }
+        .vertrouwd-stempel { background: #007bff; color: white; padding: 2px 5px; border-radius: 3px; font-size: 12px; }
+        .notitie { width: 100%; box-sizing: border-box; min-height: 40px; margin-top: 5px; }
+        .config-form textarea { width: 100%; box-sizing: border-box; min-height: 60px; padding: 8px; }
+        .deal-stempel { background: #28a745; color: white; padding: 2px 5px; border-radius: 3px; font-size: 12px; margin-left: 5px; }
+        .deal-stempel.duur { background: #6c757d; }
+        .herplaatst-stempel { background: #ffc107; color: black; padding: 2px 5px; border-radius: 3px; font-size: 12px; }
+        .zoekwoord-tag { display: inline-block; background: #e9ecef; color: #333; padding: 1px 6px; margin-right: 3px; border-radius: 10px; font-size: 12px; cursor: pointer; }
+        .zoekwoord-tag:hover { background: rgb(255, 143, 68); color: white; }
         footer { margin-top: 40px; padding: 20px; text-align: center; color: black; border-radius: 5px; }
         footer a { color: #4db8ff; text-decoration: none; }
         footer a:hover { text-decoration: underline; }
@@ -421,15 +1058,19 @@ fn index_html() -> String {
 </head>
 <body>
     <div class="content">
-        <h1>Marktplaats Monitor</h1>
+        <h1>Marktplaats Monitor <button id="uitloggen" style="display: none; float: right;" onclick="logUit()">Uitloggen</button></h1>
+        
+        <div id="status-paneel" class="status-paneel">Status laden...</div>
         
         <div id="status-bericht" class="status-bericht"></div>
         
         <div class="tabs">
             <div class="tab active" onclick="toonTab('nieuwe-artikelen')">Nieuwe Artikelen</div>
             <div class="tab" onclick="toonTab('resultaten')">Alle Resultaten</div>
+            <div class="tab" onclick="toonTab('favorieten')">Favorieten</div>
             <div class="tab" onclick="toonTab('config')">Configuratie</div>
             <div class="tab" onclick="toonTab('wenslijst')">Wenslijst</div>
+            <div class="tab" onclick="toonTab('statistieken')">Statistieken</div>
         </div>
         
         <div id="nieuwe-artikelen-tab" class="tab-content active">
@@ -441,14 +1082,60 @@ fn index_html() -> String {
         
         <div id="resultaten-tab" class="tab-content">
             <div class="zoekbalk">
-                <input type="text" id="zoekterm" placeholder="Zoek in resultaten...">
+                <input type="text" id="zoekterm" placeholder="Zoek in resultaten..." onkeydown="if (event.key === 'Enter') zoekResultaten()">
                 <button onclick="zoekResultaten()">Zoeken</button>
-                <button onclick="laadResultaten()">Alles tonen</button>
+                <button onclick="allesTonen()">Alles tonen</button>
+                <button onclick="laadVerborgen()">Verborgen</button>
+            </div>
+            <div class="zoekbalk filters">
+                Prijs: <input type="number" id="filter-prijs-min" placeholder="min €" step="0.01">
+                - <input type="number" id="filter-prijs-max" placeholder="max €" step="0.01">
+                Afstand: <input type="number" id="filter-afstand-max" placeholder="max km">
+                Gevonden: <input type="date" id="filter-van"> - <input type="date" id="filter-tot">
+                <select id="filter-prijs-type">
+                    <option value="">Alle prijs types</option>
+                    <option value="FIXED">Vaste prijs</option>
+                    <option value="BID">Bieden</option>
+                    <option value="FREE">Gratis</option>
+                    <option value="SEE_DESCRIPTION">Zie beschrijving</option>
+                </select>
+                <select id="filter-sortering" onchange="zoekResultaten()">
+                    <option value="">Beste match / nieuwste eerst</option>
+                    <option value="datum:af">Nieuwste eerst</option>
+                    <option value="datum:op">Oudste eerst</option>
+                    <option value="prijs:op">Prijs oplopend</option>
+                    <option value="prijs:af">Prijs aflopend</option>
+                    <option value="afstand:op">Dichtstbij</option>
+                    <option value="deal:op">Beste deal</option>
+                </select>
+                <select id="exporteren" onchange="exporteer(this)">
+                    <option value="">Exporteren...</option>
+                    <option value="csv">CSV</option>
+                    <option value="json">JSON</option>
+                    <option value="ndjson">NDJSON</option>
+                </select>
+                <div id="resultaten-aantal" class="info"></div>
              <!--   <button class="danger" onclick="wisAlleResultaten()">Alle Artikelen wissen</button>  -->
             </div>
             <div id="resultaten"></div>
         </div>
         
+        <div id="favorieten-tab" class="tab-content">
+            <div class="zoekbalk">
+                <label for="favorieten-staat">Status:</label>
+                <select id="favorieten-staat" onchange="laadFavorieten()">
+                    <option value="">Alle</option>
+                    <option value="nieuw">Nieuw</option>
+                    <option value="gezien">Gezien</option>
+                    <option value="gecontacteerd">Gecontacteerd</option>
+                    <option value="onderhandelen">Onderhandelen</option>
+                    <option value="gekocht">Gekocht</option>
+                    <option value="afgezien">Afgezien</option>
+                </select>
+            </div>
+            <div id="favorieten"></div>
+        </div>
+        
         <div id="config-tab" class="tab-content">
             <div class="config-form">
                 <h2>Configuratie</h2>
@@ -461,12 +1148,26 @@ fn index_html() -> String {
                 <label>Check interval (seconden):</label>
                 <input type="number" id="check_interval">
                 
+                <label><input type="checkbox" id="adaptief_interval"> Interval per zoekwoord aanpassen aan hoe vaak er iets nieuws is</label>
+                <label>Kortste en langste adaptief interval (seconden):</label>
+                <input type="number" id="min_interval" min="1">
+                <input type="number" id="max_interval" min="1">
+                
                 <label>Max advertenties per zoekopdracht:</label>
                 <input type="number" id="max_advertenties">
                 
                 <label><input type="checkbox" id="toon_bieden"> Toon bieden</label>
                 <label><input type="checkbox" id="toon_gratis"> Toon gratis</label>
                 <label><input type="checkbox" id="toon_zie_beschrijving"> Toon zie beschrijving</label>
+                <label><input type="checkbox" id="haal_details_op"> Volledige advertentie details ophalen</label>
+                
+                <label>Geblokkeerde verkopers (id of naam, één per regel):</label>
+                <textarea id="geblokkeerde_verkopers"></textarea>
+                
+                <label>Vertrouwde verkopers (id of naam, één per regel):</label>
+                <textarea id="vertrouwde_verkopers"></textarea>
+                <label><input type="checkbox" id="alleen_vertrouwde_verkopers"> Alleen vertrouwde verkopers tonen</label>
+                <label><input type="checkbox" id="onderdruk_herplaatsingen"> Herplaatste advertenties niet als nieuw melden</label>
                 
                 <br><br>
                 <button onclick="bewaarConfig()">Opslaan</button>
@@ -480,9 +1181,14 @@ fn index_html() -> String {
                 <div id="wenslijst-artikelen"></div>
                 <br>
                 <button onclick="voegWenslijstArtikelToe()">+ Nieuw artikel toevoegen</button>
-                <button onclick="bewaarWenslijst()">Opslaan</button>
             </div>
         </div>
+        
+        <div id="statistieken-tab" class="tab-content">
+            <h2>Statistieken per zoekwoord (alleen treffers)</h2>
+            <div class="info">Alleen de treffers die de monitor bewaard heeft, dus binnen de maximale prijs en de filters. Wat de zoekopdracht verder teruggaf telt niet mee.</div>
+            <div id="statistieken"></div>
+        </div>
     </div>
     
     <footer>
@@ -490,8 +1196,34 @@ fn index_html() -> String {
     </footer>
     
     <script>
+        // Leeg als de login uit staat
+        const CSRF_TOKEN = '{{CSRF_TOKEN}}';
+        
+        // Alles behalve GET krijgt het CSRF token mee, en bij een verlopen sessie terug naar de login
+        const origineleFetch = window.fetch;
+        window.fetch = (url, opties = {}) => {
+            if (opties.method && opties.method !== 'GET') {
+                opties.headers = Object.assign({}, opties.headers, { 'X-CSRF-Token': CSRF_TOKEN });
+            }
+            return origineleFetch(url, opties).then(r => {
+                if (r.status === 401) {
+                    window.location = '/login';
+                }
+                return r;
+            });
+        };
+        
+        function logUit() {
+            fetch('/logout', { method: 'POST' }).then(() => window.location = '/login');
+        }
+        
+        if (CSRF_TOKEN) {
+            document.getElementById('uitloggen').style.display = 'inline-block';
+        }
+        
         let wenslijstArtikelen = [];
-        let gezienArtikelen = new Set();
+        let wenslijstVersie = null;
+        let wenslijstPlanning = {}; // Zoekwoord -> planning uit /status
         
         function toonStatusBericht(bericht, isSuccess) {
             const element = document.getElementById('status-bericht');
@@ -504,17 +1236,6 @@ fn index_html() -> String {
             }, 3000);
         }
         
-        function laad_gezien_artikelen() {
-            const opgeslagen = localStorage.getItem('gezien_artikelen');
-            if (opgeslagen) {
-                gezienArtikelen = new Set(JSON.parse(opgeslagen));
-            }
-        }
-        
-        function bewaar_gezien_artikelen() {
-            localStorage.setItem('gezien_artikelen', JSON.stringify([...gezienArtikelen]));
-        }
-        
         function toonTab(tab) {
             document.querySelectorAll('.tab').forEach(t => t.classList.remove('active'));
             document.querySelectorAll('.tab-content').forEach(c => c.classList.remove('active'));
@@ -528,20 +1249,117 @@ fn index_html() -> String {
                 laadWenslijst();
             } else if (tab === 'nieuwe-artikelen') {
                 laadNieuweArtikelen();
+            } else if (tab === 'favorieten') {
+                laadFavorieten();
+            } else if (tab === 'statistieken') {
+                laadStatistieken();
             } else {
                 laadResultaten();
             }
         }
         
+        // Tekst van Marktplaats, de wenslijst of de API altijd zo in innerHTML zetten
+        function esc(tekst) {
+            return String(tekst ?? '').replace(/[&<>"']/g, c => ({ '&': '&amp;', '<': '&lt;', '>': '&gt;', '"': '&quot;', "'": '&#39;' })[c]);
+        }
+        
+        function zoekwoordTags(artikel) {
+            return artikel.zoekwoorden
+                .map(z => `<span class="zoekwoord-tag" data-zoekwoord="${esc(z)}" onclick="filterOpZoekwoord(this.dataset.zoekwoord)">${esc(z)}</span>`)
+                .join('');
+        }
+        
+        function filterOpZoekwoord(zoekwoord) {
+            document.querySelectorAll('.tab').forEach(t => t.classList.remove('active'));
+            document.querySelectorAll('.tab-content').forEach(c => c.classList.remove('active'));
+            document.querySelectorAll('.tab')[1].classList.add('active');
+            document.getElementById('resultaten-tab').classList.add('active');
+            
+            laadResultaten({ zoekwoord: zoekwoord });
+        }
+        
+        function dealHtml(artikel) {
+            if (!artikel.deal) return '';
+            const deal = artikel.deal;
+            const mediaan = '€' + (deal.mediaan_centen / 100).toFixed(2);
+            const titel = `Goedkoper dan ${(100 - deal.percentiel).toFixed(0)}% van ${deal.aantal_prijzen} geziene prijzen, mediaan ${mediaan}`;
+            if (deal.verschil_procent < 0) {
+                return `<span class="deal-stempel" title="${titel}">${Math.round(-deal.verschil_procent)}% onder mediaan</span>`;
+            }
+            return `<span class="deal-stempel duur" title="${titel}">${Math.round(deal.verschil_procent)}% boven mediaan</span>`;
+        }
+
+        function herplaatsingHtml(artikel) {
+            if (artikel.herplaatsing_van) {
+                return `<span class="herplaatst-stempel" title="Eerder gezien als ${artikel.herplaatsing_van}">Herplaatst</span>`;
+            }
+            if (artikel.herplaatsingen.length > 0) {
+                return `<span class="herplaatst-stempel" title="${artikel.herplaatsingen.join('\n')}">${artikel.herplaatsingen.length}x herplaatst</span>`;
+            }
+            return '';
+        }
+        
+        function verkoperHtml(artikel) {
+            const verkoper = artikel.verkoper_id || artikel.verkoper_naam;
+            if (!verkoper) {
+                return '';
+            }
+            
+            // Via data-verkoper, zodat een ' of code in de naam niet in het onclick script terechtkomt
+            const naam = artikel.verkoper_naam || artikel.verkoper_id;
+            const stempel = artikel.vertrouwde_verkoper ? ' <span class="vertrouwd-stempel">Vertrouwd</span>' : '';
+            return `Verkoper: ${esc(naam)}${stempel}
+                <button class="verkoper-btn" data-verkoper="${esc(verkoper)}" onclick="verkoperActie('blokkeer_verkoper', this.dataset.verkoper)">Blokkeer verkoper</button>
+                <button class="verkoper-btn" data-verkoper="${esc(verkoper)}" onclick="verkoperActie('vertrouw_verkoper', this.dataset.verkoper)">Vertrouw</button><br>`;
+        }
+        
+        function verkoperActie(actie, verkoper) {
+            fetch('/' + actie, {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ verkoper: verkoper })
+            })
+            .then(r => r.json())
+            .then(data => {
+                toonStatusBericht(data.bericht, data.status === 'ok');
+                laadNieuweArtikelen();
+                laadResultaten();
+                laadFavorieten();
+            });
+        }
+        
+        function detailsHtml(artikel) {
+            const d = artikel.details;
+            if (!d) {
+                return '';
+            }
+            
+            const afbeeldingen = d.afbeelding_urls.map(url => `<img src="${esc(url)}">`).join('');
+            const kenmerken = d.kenmerken.map(k => `${esc(k.naam)}: ${esc(k.waarde)}`).join('<br>');
+            
+            return `
+                <button class="details-btn" onclick="this.nextElementSibling.classList.toggle('open')">Details</button>
+                <div class="details">
+                    ${afbeeldingen}
+                    <pre>${esc(d.beschrijving)}</pre>
+                    <div class="info">
+                        Verkoper: ${esc(d.verkoper_naam || 'Onbekend')}${d.verkoper_id ? ' (' + esc(d.verkoper_id) + ')' : ''}<br>
+                        ${d.geplaatst_op ? 'Geplaatst: ' + esc(d.geplaatst_op) + '<br>' : ''}
+                        ${d.bezorging ? 'Bezorging: ' + esc(d.bezorging) + '<br>' : ''}
+                        ${d.aantal_bekeken !== null ? 'Bekeken: ' + esc(d.aantal_bekeken) + ' keer<br>' : ''}
+                        ${kenmerken}
+                    </div>
+                </div>
+            `;
+        }
+        
         function laadNieuweArtikelen() {
-            fetch('/resultaten')
+            fetch('/resultaten?staat=nieuw')
                 .then(r => r.json())
-                .then(data => {
+                .then(nieuweArtikelen => {
                     const container = document.getElementById('nieuwe-artikelen');
                     container.innerHTML = '';
                     
-                    const nieuweArtikelen = data.filter(artikel => !gezienArtikelen.has(artikel.link));
-                    
                     if (nieuweArtikelen.length === 0) {
                         container.innerHTML = '<p>Geen nieuwe artikelen.</p>';
                         return;
@@ -550,7 +1368,6 @@ fn index_html() -> String {
                     nieuweArtikelen.forEach(artikel => {
                         const div = document.createElement('div');
                         div.className = 'resultaat nieuw';
-                        div.dataset.link = artikel.link;
                         
                         let afbeelding = '';
                         if (artikel.afbeelding) {
@@ -559,16 +1376,19 @@ fn index_html() -> String {
                         
                         div.innerHTML = `
                             <span class="nieuw-stempel">NIEUW</span>
-                            <button class="markeer-gezien-btn" onclick="markeerAlsGezien('${artikel.link}')">Gezien</button>
+                            <button class="markeer-gezien-btn" onclick="markeerAlsGezien('${artikel.item_id}')">Gezien</button>
                             ${afbeelding}
-                            <h3><a href="${artikel.link}" target="_blank">${artikel.titel}</a></h3>
-                            <div class="prijs">${artikel.prijs}</div>
+                            <h3><a href="${artikel.link}" target="_blank">${artikel.titel}</a> ${herplaatsingHtml(artikel)}</h3>
+                            <div class="prijs">${artikel.prijs} ${dealHtml(artikel)}</div>
                             <div class="info">
                                 Locatie: ${artikel.locatie} (${artikel.afstand})<br>
-                                Zoekwoord: ${artikel.zoekwoord}<br>
+                                ${verkoperHtml(artikel)}
+                                Zoekwoorden: ${zoekwoordTags(artikel)}<br>
                                 ${artikel.tijdstempel}
                             </div>
                             <p>${artikel.beschrijving}</p>
+                            ${detailsHtml(artikel)}
+                            ${statusKnoppen(artikel)}
                             <div style="clear: both;"></div>
                         `;
                         
@@ -577,42 +1397,73 @@ fn index_html() -> String {
                 });
         }
         
-        function markeerAlsGezien(link) {
-            gezienArtikelen.add(link);
-            bewaar_gezien_artikelen();
-            
-            fetch('/markeer_gezien', {
+        function zetStatus(itemIds, wijziging) {
+            return fetch('/status', {
                 method: 'POST',
                 headers: { 'Content-Type': 'application/json' },
-                body: JSON.stringify({ links: [link] })
+                body: JSON.stringify(Object.assign({ item_ids: itemIds }, wijziging))
+            })
+            .then(r => r.json());
+        }
+        
+        function markeerAlsGezien(itemId) {
+            zetStatus([itemId], { staat: 'gezien' }).then(() => laadNieuweArtikelen());
+        }
+        
+        function statusActie(itemId, wijziging) {
+            zetStatus([itemId], wijziging).then(data => {
+                toonStatusBericht(data.bericht, data.status === 'ok');
+                laadNieuweArtikelen();
+                laadResultaten();
+                laadFavorieten();
             });
-            
-            laadNieuweArtikelen();
+        }
+        
+        function bewaarNotitie(itemId) {
+            const notitie = document.getElementById('notitie-' + itemId).value;
+            fetch('/notitie', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ item_id: itemId, notitie: notitie })
+            })
+            .then(r => r.json())
+            .then(data => toonStatusBericht(data.bericht, data.status === 'ok'));
+        }
+        
+        const STATEN = ['nieuw', 'gezien', 'gecontacteerd', 'onderhandelen', 'gekocht', 'afgezien', 'verborgen'];
+        
+        function statusKnoppen(artikel) {
+            const ster = artikel.favoriet ? '★' : '☆';
+            const verbergen = artikel.staat === 'verborgen'
+                ? `<button class="verkoper-btn" onclick="statusActie('${artikel.item_id}', { staat: 'gezien' })">Tonen</button>`
+                : `<button class="verkoper-btn" onclick="statusActie('${artikel.item_id}', { staat: 'verborgen' })">Verbergen</button>`;
+            return `
+                <button class="verkoper-btn" title="Favoriet" onclick="statusActie('${artikel.item_id}', { favoriet: ${!artikel.favoriet} })">${ster}</button>
+                ${verbergen}
+                <select onchange="statusActie('${artikel.item_id}', { staat: this.value })">
+                    ${STATEN.map(s => `<option value="${s}" ${s === artikel.staat ? 'selected' : ''}>${s}</option>`).join('')}
+                </select>
+                ${artikel.favoriet ? `
+                    <textarea class="notitie" id="notitie-${artikel.item_id}" placeholder="Notitie...">${artikel.notitie}</textarea>
+                    <button class="verkoper-btn" onclick="bewaarNotitie('${artikel.item_id}')">Notitie opslaan</button>
+                ` : ''}
+            `;
         }
         
         function markeerAlleNieuweAlsGezien() {
-            fetch('/resultaten')
+            fetch('/resultaten?staat=nieuw')
                 .then(r => r.json())
                 .then(data => {
-                    const nieuweLinks = data
-                        .filter(artikel => !gezienArtikelen.has(artikel.link))
-                        .map(artikel => artikel.link);
+                    const itemIds = data.map(artikel => artikel.item_id);
                     
-                    if (nieuweLinks.length === 0) {
+                    if (itemIds.length === 0) {
                         toonStatusBericht('Geen nieuwe artikelen om te markeren', false);
                         return;
                     }
                     
-                    nieuweLinks.forEach(link => gezienArtikelen.add(link));
-                    bewaar_gezien_artikelen();
-                    
-                    fetch('/markeer_gezien', {
-                        method: 'POST',
-                        headers: { 'Content-Type': 'application/json' },
-                        body: JSON.stringify({ links: nieuweLinks })
-                    })
+                    zetStatus(itemIds, { staat: 'gezien' })
                     .then(() => {
-                        toonStatusBericht(`${nieuweLinks.length} artikelen gemarkeerd als gezien`, true);
+                        toonStatusBericht(`${itemIds.length} artikelen gemarkeerd als gezien`, true);
                         laadNieuweArtikelen();
                     });
                 });
@@ -626,9 +1477,6 @@ fn index_html() -> String {
             .then(r => r.json())
             .then(data => {
                 if (data.status === 'ok') {
-                    localStorage.removeItem('gezien_artikelen');
-                    gezienArtikelen.clear();
-                    
                     toonStatusBericht(data.bericht, true);
                     laadNieuweArtikelen();
                     laadResultaten();
@@ -641,83 +1489,149 @@ fn index_html() -> String {
             });
         }
         
-        function laadResultaten() {
-            fetch('/resultaten')
-                .then(r => r.json())
+        // Resultaten worden per pagina opgehaald, de volgende pagina zodra je onderaan bent
+        const PAGINA_GROOTTE = 50;
+        let resultatenFilters = {};
+        let resultatenOffset = 0;
+        let resultatenTotaal = 0;
+        let resultatenBezig = false;
+        
+        function laadResultaten(filters = resultatenFilters) {
+            resultatenFilters = filters;
+            resultatenOffset = 0;
+            resultatenTotaal = 0;
+            laadVolgendePagina();
+        }
+        
+        function laadVolgendePagina() {
+            if (resultatenBezig || (resultatenOffset > 0 && resultatenOffset >= resultatenTotaal)) {
+                return;
+            }
+            resultatenBezig = true;
+            
+            const params = new URLSearchParams(resultatenFilters);
+            params.set('limit', PAGINA_GROOTTE);
+            params.set('offset', resultatenOffset);
+            
+            fetch('/resultaten?' + params)
+                .then(r => {
+                    resultatenTotaal = parseInt(r.headers.get('X-Totaal') || '0');
+                    return r.json();
+                })
                 .then(data => {
-                    const container = document.getElementById('resultaten');
-                    container.innerHTML = '';
-                    
-                    if (data.length === 0) {
-                        container.innerHTML = '<p>Geen resultaten gevonden.</p>';
-                        return;
-                    }
-                    
-                    data.forEach(artikel => {
-                        const div = document.createElement('div');
-                        div.className = 'resultaat';
-                        
-                        let afbeelding = '';
-                        if (artikel.afbeelding) {
-                            afbeelding = `<img src="${artikel.afbeelding}" alt="${artikel.titel}">`;
-                        }
-                        
-                        div.innerHTML = `
-                            ${afbeelding}
-                            <h3><a href="${artikel.link}" target="_blank">${artikel.titel}</a></h3>
-                            <div class="prijs">${artikel.prijs}</div>
-                            <div class="info">
-                                Locatie: ${artikel.locatie} (${artikel.afstand})<br>
-                                Zoekwoord: ${artikel.zoekwoord}<br>
-                                ${artikel.tijdstempel}
-                                </div>
-                            <p>${artikel.beschrijving}</p>
-                            <div style="clear: both;"></div>
-                        `;
-                        
-                        container.appendChild(div);
-                    });
+                    toonResultaten(data, 'resultaten', resultatenOffset === 0);
+                    resultatenOffset += data.length;
+                    document.getElementById('resultaten-aantal').textContent = `${resultatenOffset} van ${resultatenTotaal} resultaten`;
+                })
+                .finally(() => {
+                    resultatenBezig = false;
                 });
         }
         
+        window.addEventListener('scroll', () => {
+            if (!document.getElementById('resultaten-tab').classList.contains('active')) {
+                return;
+            }
+            if (window.innerHeight + window.scrollY >= document.body.offsetHeight - 800) {
+                laadVolgendePagina();
+            }
+        });
+        
+        function filtersUitFormulier() {
+            const [sorteer, richting] = document.getElementById('filter-sortering').value.split(':');
+            const filters = {
+                q: document.getElementById('zoekterm').value,
+                prijs_min: document.getElementById('filter-prijs-min').value,
+                prijs_max: document.getElementById('filter-prijs-max').value,
+                afstand_max: document.getElementById('filter-afstand-max').value,
+                van: document.getElementById('filter-van').value,
+                tot: document.getElementById('filter-tot').value,
+                prijs_type: document.getElementById('filter-prijs-type').value,
+                sorteer: sorteer,
+                richting: richting
+            };
+            Object.keys(filters).forEach(k => {
+                if (!filters[k]) {
+                    delete filters[k];
+                }
+            });
+            return filters;
+        }
+        
+        // Met de filters van het formulier die de export ook kent, de browser downloadt het bestand
+        function exporteer(select) {
+            const filters = filtersUitFormulier();
+            const parameters = new URLSearchParams({ formaat: select.value });
+            ['prijs_min', 'prijs_max', 'van', 'tot', 'prijs_type'].forEach(k => {
+                if (filters[k]) parameters.set(k, filters[k]);
+            });
+            select.selectedIndex = 0;
+            window.location = '/api/v1/export?' + parameters;
+        }
+        
+        function allesTonen() {
+            document.querySelectorAll('#resultaten-tab input').forEach(i => i.value = '');
+            document.querySelectorAll('#resultaten-tab select').forEach(s => s.selectedIndex = 0);
+            laadResultaten({});
+        }
+        
+        function laadVerborgen() {
+            laadResultaten(Object.assign(filtersUitFormulier(), { staat: 'verborgen' }));
+        }
+        
         function zoekResultaten() {
-            const zoekterm = document.getElementById('zoekterm').value;
-            fetch('/resultaten?q=' + encodeURIComponent(zoekterm))
+            laadResultaten(filtersUitFormulier());
+        }
+        
+        function laadFavorieten() {
+            const staat = document.getElementById('favorieten-staat').value;
+            let url = '/resultaten?favoriet=true';
+            if (staat) {
+                url += '&staat=' + staat;
+            }
+            fetch(url)
                 .then(r => r.json())
-                .then(data => {
-                    const container = document.getElementById('resultaten');
-                    container.innerHTML = '';
-                    
-                    if (data.length === 0) {
-                        container.innerHTML = '<p>Geen resultaten gevonden.</p>';
-                        return;
-                    }
-                    
-                    data.forEach(artikel => {
-                        const div = document.createElement('div');
-                        div.className = 'resultaat';
-                        
-                        let afbeelding = '';
-                        if (artikel.afbeelding) {
-                            afbeelding = `<img src="${artikel.afbeelding}" alt="${artikel.titel}">`;
-                        }
-                        
-                        div.innerHTML = `
-                            ${afbeelding}
-                            <h3><a href="${artikel.link}" target="_blank">${artikel.titel}</a></h3>
-                            <div class="prijs">${artikel.prijs}</div>
-                            <div class="info">
-                                Locatie: ${artikel.locatie} (${artikel.afstand})<br>
-                                Zoekwoord: ${artikel.zoekwoord}<br>
-                                ${artikel.tijdstempel}
-                            </div>
-                            <p>${artikel.beschrijving}</p>
-                            <div style="clear: both;"></div>
-                        `;
-                        
-                        container.appendChild(div);
-                    });
-                });
+                .then(data => toonResultaten(data, 'favorieten'));
+        }
+        
+        function toonResultaten(data, containerId = 'resultaten', wissen = true) {
+            const container = document.getElementById(containerId);
+            if (wissen) {
+                container.innerHTML = '';
+            }
+            
+            if (data.length === 0 && wissen) {
+                container.innerHTML = '<p>Geen resultaten gevonden.</p>';
+                return;
+            }
+            
+            data.forEach(artikel => {
+                const div = document.createElement('div');
+                div.className = 'resultaat';
+                
+                let afbeelding = '';
+                if (artikel.afbeelding) {
+                    afbeelding = `<img src="${artikel.afbeelding}" alt="${artikel.titel}">`;
+                }
+                
+                div.innerHTML = `
+                    ${afbeelding}
+                    <h3><a href="${artikel.link}" target="_blank">${artikel.titel}</a> ${herplaatsingHtml(artikel)}</h3>
+                    <div class="prijs">${artikel.prijs} ${dealHtml(artikel)}</div>
+                    <div class="info">
+                        Locatie: ${artikel.locatie} (${artikel.afstand})<br>
+                        ${verkoperHtml(artikel)}
+                        Zoekwoorden: ${zoekwoordTags(artikel)}<br>
+                        ${artikel.tijdstempel}
+                    </div>
+                    <p>${artikel.beschrijving}</p>
+                    ${detailsHtml(artikel)}
+                    ${statusKnoppen(artikel)}
+                    <div style="clear: both;"></div>
+                `;
+                
+                container.appendChild(div);
+            });
         }
         
         function laadConfig() {
@@ -727,10 +1641,18 @@ fn index_html() -> String {
                     document.getElementById('postcode').value = data.postcode;
                     document.getElementById('afstand_km').value = data.afstand_km;
                     document.getElementById('check_interval').value = data.check_interval_seconden;
+                    document.getElementById('adaptief_interval').checked = data.adaptief_interval;
+                    document.getElementById('min_interval').value = data.min_interval_seconden;
+                    document.getElementById('max_interval').value = data.max_interval_seconden;
                     document.getElementById('max_advertenties').value = data.max_advertenties_per_zoekopdracht;
                     document.getElementById('toon_bieden').checked = data.toon_bieden;
                     document.getElementById('toon_gratis').checked = data.toon_gratis;
                     document.getElementById('toon_zie_beschrijving').checked = data.toon_zie_beschrijving;
+                    document.getElementById('haal_details_op').checked = data.haal_details_op;
+                    document.getElementById('geblokkeerde_verkopers').value = data.geblokkeerde_verkopers.join('\n');
+                    document.getElementById('vertrouwde_verkopers').value = data.vertrouwde_verkopers.join('\n');
+                    document.getElementById('alleen_vertrouwde_verkopers').checked = data.alleen_vertrouwde_verkopers;
+                    document.getElementById('onderdruk_herplaatsingen').checked = data.onderdruk_herplaatsingen;
                 });
         }
         
@@ -739,10 +1661,18 @@ fn index_html() -> String {
                 postcode: document.getElementById('postcode').value,
                 afstand_km: parseInt(document.getElementById('afstand_km').value),
                 check_interval_seconden: parseInt(document.getElementById('check_interval').value),
+                adaptief_interval: document.getElementById('adaptief_interval').checked,
+                min_interval_seconden: parseInt(document.getElementById('min_interval').value),
+                max_interval_seconden: parseInt(document.getElementById('max_interval').value),
                 max_advertenties_per_zoekopdracht: parseInt(document.getElementById('max_advertenties').value),
                 toon_bieden: document.getElementById('toon_bieden').checked,
                 toon_gratis: document.getElementById('toon_gratis').checked,
-                toon_zie_beschrijving: document.getElementById('toon_zie_beschrijving').checked
+                toon_zie_beschrijving: document.getElementById('toon_zie_beschrijving').checked,
+                haal_details_op: document.getElementById('haal_details_op').checked,
+                geblokkeerde_verkopers: document.getElementById('geblokkeerde_verkopers').value.split('\n'),
+                vertrouwde_verkopers: document.getElementById('vertrouwde_verkopers').value.split('\n'),
+                alleen_vertrouwde_verkopers: document.getElementById('alleen_vertrouwde_verkopers').checked,
+                onderdruk_herplaatsingen: document.getElementById('onderdruk_herplaatsingen').checked
             };
             
             fetch('/config', {
@@ -756,13 +1686,86 @@ fn index_html() -> String {
             });
         }
         
-        function laadWenslijst() {
-            fetch('/wenslijst')
+        // Elk zoekwoord wordt los opgeslagen. De versie (ETag) gaat mee, zodat een wijziging van iemand
+        // anders in de tussentijd niet overschreven wordt
+        function euro(centen) {
+            return '€' + (centen / 100).toFixed(0);
+        }
+        
+        // Simpele staafdiagram zonder library, de titel van een staaf laat het precieze getal zien
+        function staafDiagram(staven, links, rechts) {
+            if (staven.length === 0) return '<div class="info">Nog geen gegevens</div>';
+            const hoogste = Math.max(...staven.map(s => s.waarde), 1);
+            const html = staven
+                .map(s => `<div class="staaf" title="${s.label}: ${s.titel ?? s.waarde}" style="height: ${s.waarde / hoogste * 100}%"></div>`)
+                .join('');
+            return `<div class="staven">${html}</div><div class="staaf-labels"><span>${links}</span><span>${rechts}</span></div>`;
+        }
+        
+        function toonStatistiek(stat) {
+            const dagen = stat.per_dag.map(d => ({ label: d.datum, waarde: d.aantal }));
+            const histogram = stat.histogram.map(s => ({ label: `${euro(s.van_centen)} - ${euro(s.tot_centen)}`, waarde: s.aantal }));
+            const weken = stat.mediaan_per_week.map(w => ({ label: w.week, waarde: w.mediaan_centen, titel: `${euro(w.mediaan_centen)} (${w.aantal} prijzen)` }));
+            const types = stat.prijs_types.map(t => `${t.prijs_type}: ${t.aantal} (${t.aandeel_procent}%)`).join(' | ');
+            const eerste = lijst => lijst.length ? lijst[0].label : '';
+            const laatste = lijst => lijst.length ? lijst[lijst.length - 1].label : '';
+            
+            return `
+                <div class="statistiek">
+                    <h3>${stat.zoekwoord}</h3>
+                    <div class="info">${stat.aantal} treffers${stat.mediaan_centen !== null ? `, mediaan ${euro(stat.mediaan_centen)}` : ''}</div>
+                    <div class="info">${types || 'Geen treffers'}</div>
+                    <div class="grafieken">
+                        <div class="grafiek"><h4>Treffers per dag</h4>${staafDiagram(dagen, eerste(dagen), laatste(dagen))}</div>
+                        <div class="grafiek"><h4>Prijsverdeling van de treffers</h4>${staafDiagram(histogram, eerste(histogram), laatste(histogram))}</div>
+                        <div class="grafiek"><h4>Mediaan per week</h4>${staafDiagram(weken, eerste(weken), laatste(weken))}</div>
+                    </div>
+                </div>
+            `;
+        }
+        
+        function laadStatistieken() {
+            fetch('/api/v1/statistieken')
                 .then(r => r.json())
                 .then(data => {
-                    wenslijstArtikelen = data;
-                    toonWenslijstArtikelen();
+                    const container = document.getElementById('statistieken');
+                    container.innerHTML = data.length ? data.map(toonStatistiek).join('') : '<div class="info">De wenslijst is leeg</div>';
+                });
+        }
+        
+        function laadWenslijst() {
+            const wenslijst = fetch('/api/v1/wenslijst')
+                .then(r => {
+                    wenslijstVersie = r.headers.get('ETag');
+                    return r.json();
                 });
+            const status = fetch('/status').then(r => r.json()).catch(() => ({ wenslijst: [] }));
+            Promise.all([wenslijst, status]).then(([data, status]) => {
+                wenslijstArtikelen = data;
+                wenslijstPlanning = {};
+                status.wenslijst.forEach(item => wenslijstPlanning[item.zoekwoord] = item);
+                toonWenslijstArtikelen();
+            });
+        }
+        
+        function formatteerDuur(seconden) {
+            if (seconden >= 3600) return `${Math.floor(seconden / 3600)}u ${Math.round((seconden % 3600) / 60)}m`;
+            if (seconden >= 60) return `${Math.floor(seconden / 60)}m ${seconden % 60}s`;
+            return `${seconden}s`;
+        }
+        
+        // Het interval dat de monitor nu gebruikt, bij adaptief plannen het berekende
+        function planningTekst(artikel) {
+            const item = wenslijstPlanning[artikel.zoekwoord];
+            if (!item || item.interval_seconden === null) return '';
+            let tekst = `Elke ${formatteerDuur(item.interval_seconden)}`;
+            if (item.treffers_per_check !== null) {
+                tekst += ` (adaptief, ${item.treffers_per_check.toFixed(2)} nieuw per check)`;
+            }
+            if (item.volgende_check) {
+                tekst += `, volgende check ${item.volgende_check}`;
+            }
+            return tekst;
         }
         
         function toonWenslijstArtikelen() {
@@ -774,42 +1777,173 @@ fn index_html() -> String {
                 div.className = 'wenslijst-artikel';
                 div.innerHTML = `
                     <input type="text" value="${artikel.zoekwoord}" onchange="updateWenslijstArtikel(${index}, 'zoekwoord', this.value)" placeholder="Zoekwoord">
-                    <input type="text" value="${artikel.max_prijs}" onchange="updateWenslijstArtikel(${index}, 'max_prijs', this.value)" placeholder="Max prijs (-1 = onbeperkt, 0 = gratis)">
+                    <input type="text" value="${artikel.max_percentiel ? 'p' + artikel.max_percentiel : artikel.max_prijs}" onchange="updateWenslijstArtikel(${index}, 'max_prijs', this.value)" placeholder="Max prijs (-1 = onbeperkt, 0 = gratis, p25 = 25e percentiel)">
+                    <input type="number" min="1" value="${artikel.interval_seconden ?? ''}" onchange="updateWenslijstArtikel(${index}, 'interval_seconden', this.value)" placeholder="Interval (s), leeg = standaard">
+                    <input type="text" value="${artikel.venster ?? ''}" onchange="updateWenslijstArtikel(${index}, 'venster', this.value)" placeholder="Wanneer, bijv. ma-vr 08-23">
+                    <button class="opslaan" onclick="bewaarWenslijstArtikel(${index})">Opslaan</button>
                     <button onclick="verwijderWenslijstArtikel(${index})">Verwijderen</button>
+                    <div class="planning">${planningTekst(artikel)} ${artikel.id !== null ? `<a href="/feed/${encodeURIComponent(artikel.zoekwoord)}" target="_blank">Atom feed</a>` : ''}</div>
                 `;
                 container.appendChild(div);
             });
         }
         
         function updateWenslijstArtikel(index, veld, waarde) {
+            // "p25" is geen prijs maar een percentiel van de eerder geziene prijzen
+            if (veld === 'max_prijs' && waarde.trim().startsWith('p')) {
+                wenslijstArtikelen[index].max_prijs = -1;
+                wenslijstArtikelen[index].max_percentiel = parseInt(waarde.trim().slice(1), 10);
+                return;
+            }
+            if (veld === 'max_prijs') {
+                wenslijstArtikelen[index].max_percentiel = null;
+            }
             wenslijstArtikelen[index][veld] = waarde;
         }
         
         function voegWenslijstArtikelToe() {
-            wenslijstArtikelen.push({ zoekwoord: '', max_prijs: '-1' });
+            wenslijstArtikelen.push({ id: null, zoekwoord: '', max_prijs: '-1' });
             toonWenslijstArtikelen();
         }
         
+        // Na elke wijziging opnieuw laden, de ids schuiven op als er iets verwijderd is
+        function verwerkWenslijstAntwoord(r, bericht) {
+            if (r.ok) {
+                toonStatusBericht(bericht, true);
+                laadWenslijst();
+                return;
+            }
+            r.json().then(data => {
+                toonStatusBericht(data.bericht, false);
+                if (r.status === 412) {
+                    laadWenslijst();
+                }
+            });
+        }
+        
+        function bewaarWenslijstArtikel(index) {
+            const artikel = wenslijstArtikelen[index];
+            const nieuw = artikel.id === null;
+            fetch(nieuw ? '/api/v1/wenslijst' : `/api/v1/wenslijst/${artikel.id}`, {
+                method: nieuw ? 'POST' : 'PUT',
+                headers: { 'Content-Type': 'application/json', 'If-Match': wenslijstVersie },
+                body: JSON.stringify({
+                    zoekwoord: artikel.zoekwoord,
+                    max_prijs: parseInt(artikel.max_prijs, 10),
+                    max_percentiel: artikel.max_percentiel ?? null,
+                    interval_seconden: artikel.interval_seconden ? parseInt(artikel.interval_seconden, 10) : null,
+                    venster: artikel.venster ? artikel.venster : null
+                })
+            })
+            .then(r => verwerkWenslijstAntwoord(r, nieuw ? 'Zoekwoord toegevoegd' : 'Zoekwoord opgeslagen'));
+        }
+        
         function verwijderWenslijstArtikel(index) {
-            wenslijstArtikelen.splice(index, 1);
-            toonWenslijstArtikelen();
+            const artikel = wenslijstArtikelen[index];
+            if (artikel.id === null) {
+                wenslijstArtikelen.splice(index, 1);
+                toonWenslijstArtikelen();
+                return;
+            }
+            fetch(`/api/v1/wenslijst/${artikel.id}`, {
+                method: 'DELETE',
+                headers: { 'If-Match': wenslijstVersie }
+            })
+            .then(r => verwerkWenslijstAntwoord(r, 'Zoekwoord verwijderd'));
         }
         
-        function bewaarWenslijst() {
-            fetch('/wenslijst', {
+        // Wat de check lus aan het doen is, elke 30 seconden opnieuw
+        function laadStatus() {
+            fetch('/status')
+                .then(r => r.json())
+                .then(data => {
+                    const paneel = document.getElementById('status-paneel');
+                    paneel.className = data.gezond ? 'status-paneel' : 'status-paneel probleem';
+                    const ronde = data.ronde.bezig
+                        ? `Bezig met checken sinds ${data.ronde.laatste_begin}`
+                        : data.ronde.laatste_einde
+                            ? `Laatste check: ${data.ronde.laatste_einde} (${data.ronde.laatste_duur_seconden.toFixed(1)}s)`
+                            : 'Nog niet gecheckt';
+                    const fouten = data.wenslijst
+                        .filter(item => item.laatste_fout)
+                        .map(item => `<div class="fout">${item.zoekwoord}: ${item.laatste_fout}</div>`)
+                        .join('');
+                    paneel.innerHTML = `
+                        ${ronde}
+                        ${data.volgende_check ? ` | Volgende check: ${data.volgende_check}` : ''}
+                        | Treffers vandaag: ${data.treffers_vandaag}
+                        | Versie ${data.versie}
+                        ${data.probleem ? `<div class="fout">${data.probleem}</div>` : ''}
+                        ${data.ronde.laatste_fout ? `<div class="fout">${data.ronde.laatste_fout}</div>` : ''}
+                        ${fouten}
+                    `;
+                })
+                .catch(() => {});
+        }
+        
+        laadStatus();
+        setInterval(laadStatus, 30000);
+        laadNieuweArtikelen();
+    </script>
+</body>
+</html>"#.to_string()
+}
+
+fn login_html(configuratie: &Configuratie) -> String {
+    let mut velden = String::new();
+    if !configuratie.web_token.is_empty() {
+        velden.push_str(r#"<label for="token">Token:</label><input type="password" id="token">"#);
+    }
+    if !configuratie.web_wachtwoord_hash.is_empty() {
+        velden.push_str(r#"<label for="gebruiker">Gebruiker:</label><input type="text" id="gebruiker">
+            <label for="wachtwoord">Wachtwoord:</label><input type="password" id="wachtwoord">"#);
+    }
+
+    r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>Marktplaats Monitor - Inloggen</title>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <style>
+        body { font-family: Arial; margin: 20px; background: #f5f5f5; }
+        form { background: white; padding: 20px; border: 1px solid #ddd; max-width: 320px; }
+        label { display: block; margin-top: 10px; }
+        input { padding: 8px; width: 100%; box-sizing: border-box; }
+        button { margin-top: 15px; padding: 8px 16px; background: rgb(255, 143, 68); color: white; border: none; cursor: pointer; }
+        .fout { color: #721c24; margin-top: 10px; }
+    </style>
+</head>
+<body>
+    <h1>Marktplaats Monitor</h1>
+    <form onsubmit="logIn(event)">
+        {{VELDEN}}
+        <button type="submit">Inloggen</button>
+        <div id="fout" class="fout"></div>
+    </form>
+    <script>
+        function waarde(id) {
+            const veld = document.getElementById(id);
+            return veld ? veld.value : '';
+        }
+        
+        function logIn(event) {
+            event.preventDefault();
+            fetch('/login', {
                 method: 'POST',
                 headers: { 'Content-Type': 'application/json' },
-                body: JSON.stringify({ artikelen: wenslijstArtikelen })
+                body: JSON.stringify({ token: waarde('token'), gebruiker: waarde('gebruiker'), wachtwoord: waarde('wachtwoord') })
             })
             .then(r => r.json())
             .then(data => {
-                toonStatusBericht(data.bericht, data.status === 'ok');
+                if (data.status === 'ok') {
+                    window.location = '/';
+                } else {
+                    document.getElementById('fout').textContent = data.bericht;
+                }
             });
         }
-        
-        laad_gezien_artikelen();
-        laadNieuweArtikelen();
     </script>
 </body>
-</html>"#.to_string()
-}
\ No newline at end of file
+</html>"#.replace("{{VELDEN}}", &velden)
+}
@@ -0,0 +1,291 @@
+// Losse regels van de wenslijst lezen en aanpassen. Commentaar, lege regels en de volgorde in het bestand blijven staan,
+// alleen de regel die aangepast wordt verandert. Het id van een regel is de plek in de lijst (1 = eerste zoekwoord).
+//
+// Elke versie van het bestand heeft een versie (hash van de inhoud). Wie een versie meegeeft bij het aanpassen krijgt een
+// Conflict als iemand anders het bestand intussen heeft aangepast, zodat twee tabbladen elkaars werk niet overschrijven.
+
+use crate::opslag;
+use crate::planning::{self, Venster};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Mutex;
+
+pub const KOPTEKST: &str = "# Marktplaats Wensenlijst
+# Formaat: zoekwoord;maximaleprijs
+# Om te commenteren gebruikt u #
+# Als u geen maximale prijs wilt, stelt u de prijs in als -1
+# Wilt u gratis producten, doe 0 als de prijs
+# Met p25 als prijs krijgt u alleen advertenties onder het 25e percentiel van eerder geziene prijzen
+# Optioneel een eigen interval en wanneer er gecheckt mag worden: zoekwoord;maximaleprijs;5m;ma-vr 08-23
+
+";
+
+// De web interface en de API kunnen tegelijk aanpassen
+static SCHRIJF_SLOT: Mutex<()> = Mutex::new(());
+
+// Wat er op een regel van de wenslijst staat
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Zoekopdracht {
+    pub zoekwoord: String,
+    pub max_prijs: i32, // -1 is geen maximum, 0 is alleen gratis
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_percentiel: Option<u8>,     // "p25" in het bestand, dan is max_prijs -1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval_seconden: Option<u64>, // Leeg is check_interval_seconden uit de config
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub venster: Option<String>,        // Bijv. "ma-vr 08-23", leeg is altijd
+}
+
+impl Zoekopdracht {
+    fn als_regel(&self) -> String {
+        let mut regel = match self.max_percentiel {
+            Some(percentiel) => format!("{};p{}", self.zoekwoord.trim(), percentiel),
+            None => format!("{};{}", self.zoekwoord.trim(), self.max_prijs),
+        };
+        if self.interval_seconden.is_some() || self.venster.is_some() {
+            regel.push(';');
+            if let Some(interval) = self.interval_seconden {
+                regel.push_str(&interval.to_string());
+            }
+        }
+        if let Some(venster) = &self.venster {
+            regel.push(';');
+            regel.push_str(venster.trim());
+        }
+        regel
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WenslijstRegel {
+    pub id: usize,
+    #[serde(flatten)]
+    pub zoekopdracht: Zoekopdracht,
+}
+
+#[derive(Debug)]
+pub struct Conflict;
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "De wenslijst is intussen door iemand anders aangepast, laad hem opnieuw")
+    }
+}
+
+impl Error for Conflict {}
+
+// Ok(None) voor commentaar en lege regels, Err met de reden voor een ongeldige regel
+fn ontleed_regel(lijn: &str) -> Result<Option<Zoekopdracht>, String> {
+    let lijn = lijn.trim();
+    if lijn.is_empty() || lijn.starts_with('#') {
+        return Ok(None);
+    }
+    let mut velden = lijn.split(';').map(str::trim);
+    let zoekwoord = velden.next().unwrap_or_default().to_string();
+    let Some(prijs) = velden.next() else {
+        return Err("geen puntkomma (;) gevonden. Als u geen maximum prijs wilt stellen kunt u -1 schrijven".to_string());
+    };
+    let (max_prijs, max_percentiel) = match prijs.strip_prefix('p') {
+        Some(percentiel) => match percentiel.parse::<u8>() {
+            Ok(percentiel) if (1..=99).contains(&percentiel) => (-1, Some(percentiel)),
+            _ => return Err(format!("het percentiel ({}) moet tussen p1 en p99 liggen", prijs)),
+        },
+        None => match prijs.parse::<i32>() {
+            Ok(max_prijs) if max_prijs >= -1 => (max_prijs, None),
+            _ => return Err(format!("de prijs ({}) is ongeldig", prijs)),
+        },
+    };
+    let interval_seconden = match velden.next() {
+        None | Some("") => None,
+        Some(interval) => Some(planning::parseer_interval(interval)?),
+    };
+    let venster = match velden.next() {
+        None | Some("") => None,
+        Some(venster) => {
+            Venster::parseer(venster)?;
+            Some(venster.to_string())
+        }
+    };
+    if velden.next().is_some() {
+        return Err("te veel velden, gebruik zoekwoord;prijs;interval;venster".to_string());
+    }
+    let zoekopdracht = Zoekopdracht { zoekwoord, max_prijs, max_percentiel, interval_seconden, venster };
+    controleer(&zoekopdracht)?;
+    Ok(Some(zoekopdracht))
+}
+
+// Ongeldige regels tellen niet mee, ook niet voor de id's
+fn parseer_regel(lijn: &str) -> Option<Zoekopdracht> {
+    ontleed_regel(lijn).ok().flatten()
+}
+
+pub fn controleer(zoekopdracht: &Zoekopdracht) -> Result<(), String> {
+    let zoekwoord = zoekopdracht.zoekwoord.trim();
+    if zoekwoord.is_empty() {
+        return Err("Zoekwoord mag niet leeg zijn".to_string());
+    }
+    if zoekwoord.contains(';') || zoekwoord.contains('\n') || zoekwoord.starts_with('#') {
+        return Err("Zoekwoord mag geen ; of nieuwe regel bevatten en niet met # beginnen".to_string());
+    }
+    if zoekopdracht.max_prijs < -1 {
+        return Err("Maximale prijs moet -1 (geen maximum), 0 (gratis) of hoger zijn".to_string());
+    }
+    if let Some(percentiel) = zoekopdracht.max_percentiel {
+        if !(1..=99).contains(&percentiel) {
+            return Err("Percentiel moet tussen 1 en 99 liggen".to_string());
+        }
+        if zoekopdracht.max_prijs != -1 {
+            return Err("Een percentiel gaat niet samen met een maximale prijs, laat max_prijs op -1".to_string());
+        }
+    }
+    if zoekopdracht.interval_seconden == Some(0) {
+        return Err("Interval moet minstens 1 seconde zijn".to_string());
+    }
+    if zoekopdracht.interval_seconden.is_some_and(|interval| interval > planning::MAX_INTERVAL_SECONDEN) {
+        return Err("Interval mag niet langer dan 30 dagen zijn".to_string());
+    }
+    if let Some(venster) = &zoekopdracht.venster {
+        if venster.contains(';') || venster.contains('\n') {
+            return Err("Venster mag geen ; of nieuwe regel bevatten".to_string());
+        }
+        Venster::parseer(venster)?;
+    }
+    Ok(())
+}
+
+fn lees_inhoud(pad: &str) -> Result<String, Box<dyn Error>> {
+    if !Path::new(pad).exists() {
+        return Ok(String::new());
+    }
+    Ok(fs::read_to_string(pad)?)
+}
+
+fn bereken_versie(inhoud: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    inhoud.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+// Index in de lijnen van het bestand voor elk geldig zoekwoord, op volgorde
+fn regel_posities(lijnen: &[String]) -> Vec<usize> {
+    lijnen.iter().enumerate()
+        .filter(|(_, lijn)| parseer_regel(lijn).is_some())
+        .map(|(positie, _)| positie)
+        .collect()
+}
+
+fn regels(inhoud: &str) -> Vec<WenslijstRegel> {
+    inhoud.lines()
+        .filter_map(parseer_regel)
+        .enumerate()
+        .map(|(index, zoekopdracht)| WenslijstRegel { id: index + 1, zoekopdracht })
+        .collect()
+}
+
+// De regels met de versie waar ze bij horen
+pub fn lees(pad: &str) -> Result<(Vec<WenslijstRegel>, String), Box<dyn Error>> {
+    let inhoud = lees_inhoud(pad)?;
+    Ok((regels(&inhoud), bereken_versie(&inhoud)))
+}
+
+// Voor de check lus: de regels en wat er mis is met de ongeldige, met het lijnnummer erbij
+pub fn lees_met_problemen(pad: &str) -> Result<(Vec<WenslijstRegel>, Vec<String>), Box<dyn Error>> {
+    let inhoud = lees_inhoud(pad)?;
+    let problemen = inhoud.lines().enumerate()
+        .filter_map(|(lijn_num, lijn)| ontleed_regel(lijn).err().map(|e| format!("Probleem op lijn {}: {}", lijn_num + 1, e)))
+        .collect();
+    Ok((regels(&inhoud), problemen))
+}
+
+// Lezen, versie controleren, aanpassen en terugschrijven onder het slot. Als `aanpassing` None geeft
+// (bijv. een onbekend id) wordt er niets geschreven. Geeft de uitkomst en de nieuwe versie terug.
+fn pas_lijnen_aan<T>(pad: &str, verwachte_versie: Option<&str>, aanpassing: impl FnOnce(&mut Vec<String>) -> Option<T>) -> Result<Option<(T, String)>, Box<dyn Error>> {
+    let _slot = SCHRIJF_SLOT.lock().unwrap();
+    let inhoud = lees_inhoud(pad)?;
+    if verwachte_versie.is_some_and(|v| v != bereken_versie(&inhoud)) {
+        return Err(Box::new(Conflict));
+    }
+
+    let mut lijnen: Vec<String> = inhoud.lines().map(String::from).collect();
+    let Some(uitkomst) = aanpassing(&mut lijnen) else {
+        return Ok(None);
+    };
+
+    let mut nieuwe_inhoud = lijnen.join("\n");
+    nieuwe_inhoud.push('\n');
+    opslag::schrijf_atomisch(pad, &nieuwe_inhoud)?;
+    Ok(Some((uitkomst, bereken_versie(&nieuwe_inhoud))))
+}
+
+fn positie_van(lijnen: &[String], id: usize) -> Option<usize> {
+    regel_posities(lijnen).get(id.checked_sub(1)?).copied()
+}
+
+// Zoals het in het bestand komt te staan
+fn opgeschoond(zoekopdracht: &Zoekopdracht) -> Zoekopdracht {
+    Zoekopdracht {
+        zoekwoord: zoekopdracht.zoekwoord.trim().to_string(),
+        venster: zoekopdracht.venster.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(String::from),
+        ..zoekopdracht.clone()
+    }
+}
+
+pub fn voeg_toe(pad: &str, zoekopdracht: &Zoekopdracht, verwachte_versie: Option<&str>) -> Result<(WenslijstRegel, String), Box<dyn Error>> {
+    let zoekopdracht = opgeschoond(zoekopdracht);
+    let uitkomst = pas_lijnen_aan(pad, verwachte_versie, |lijnen| {
+        if lijnen.is_empty() {
+            *lijnen = KOPTEKST.lines().map(String::from).collect();
+        }
+        lijnen.push(zoekopdracht.als_regel());
+        Some(WenslijstRegel { id: regel_posities(lijnen).len(), zoekopdracht })
+    })?;
+    Ok(uitkomst.expect("toevoegen lukt altijd"))
+}
+
+// None als er geen regel met dat id is
+pub fn pas_aan(pad: &str, id: usize, zoekopdracht: &Zoekopdracht, verwachte_versie: Option<&str>) -> Result<Option<(WenslijstRegel, String)>, Box<dyn Error>> {
+    let zoekopdracht = opgeschoond(zoekopdracht);
+    pas_lijnen_aan(pad, verwachte_versie, |lijnen| {
+        let positie = positie_van(lijnen, id)?;
+        lijnen[positie] = zoekopdracht.als_regel();
+        Some(WenslijstRegel { id, zoekopdracht })
+    })
+}
+
+// Geeft de nieuwe versie terug, of None als er geen regel met dat id is
+pub fn verwijder(pad: &str, id: usize, verwachte_versie: Option<&str>) -> Result<Option<String>, Box<dyn Error>> {
+    let uitkomst = pas_lijnen_aan(pad, verwachte_versie, |lijnen| {
+        let positie = positie_van(lijnen, id)?;
+        lijnen.remove(positie);
+        Some(())
+    })?;
+    Ok(uitkomst.map(|(_, versie)| versie))
+}
+
+// Alle zoekwoorden vervangen. Commentaar en lege regels blijven op hun plek, de zoekwoorden komen op
+// de plekken van de oude, wat over is komt achteraan.
+pub fn vervang_alles(pad: &str, nieuwe_regels: &[Zoekopdracht], verwachte_versie: Option<&str>) -> Result<String, Box<dyn Error>> {
+    let uitkomst = pas_lijnen_aan(pad, verwachte_versie, |lijnen| {
+        if lijnen.is_empty() {
+            *lijnen = KOPTEKST.lines().map(String::from).collect();
+        }
+
+        let mut nieuw = nieuwe_regels.iter().map(|zoekopdracht| opgeschoond(zoekopdracht).als_regel());
+        let mut resultaat = Vec::with_capacity(lijnen.len());
+        for lijn in lijnen.drain(..) {
+            match parseer_regel(&lijn) {
+                None => resultaat.push(lijn),
+                Some(_) => resultaat.extend(nieuw.next()),
+            }
+        }
+        resultaat.extend(nieuw);
+        *lijnen = resultaat;
+        Some(())
+    })?;
+    Ok(uitkomst.expect("vervangen lukt altijd").1)
+}
@@ -0,0 +1,203 @@
+// Omgekeerde index over de titels en beschrijvingen van gevonden advertenties, voor de zoekbalk.
+// Wordt bij de eerste zoekopdracht opgebouwd uit de opslag, daarna komt elk nieuw resultaat er direct bij.
+
+use crate::opslag::{self, Resultaat};
+use crate::verwijder_html;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+
+// Een woord in de titel zegt meer dan een woord ergens in de beschrijving
+const TITEL_GEWICHT: u32 = 3;
+
+// Standaard BM25 waardes
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+// Termen die alleen als begin van een woord gevonden worden tellen half
+const PREFIX_GEWICHT: f64 = 0.5;
+
+const STOPWOORDEN: &[&str] = &[
+    "de", "het", "een", "en", "van", "in", "op", "met", "voor", "te", "is", "of", "aan", "bij", "als",
+    "om", "uit", "die", "dat", "er", "zijn", "ook", "maar", "niet", "nog", "wel", "tot", "naar", "door",
+    "over", "je", "ik", "we", "zo", "per", "wordt", "heb", "heeft",
+];
+
+static INDEX: Mutex<Option<Zoekindex>> = Mutex::new(None);
+
+#[derive(Default)]
+struct Zoekindex {
+    pad: String,
+    termen: BTreeMap<String, HashMap<String, u32>>, // Term -> item id -> gewogen aantal keer
+    lengtes: HashMap<String, u32>,
+}
+
+impl Zoekindex {
+    fn bouw(pad: &str) -> Self {
+        let mut index = Zoekindex { pad: pad.to_string(), ..Default::default() };
+        for resultaat in opslag::lees_resultaten(pad).unwrap_or_default() {
+            index.voeg_toe(&resultaat);
+        }
+        index
+    }
+
+    fn voeg_toe(&mut self, resultaat: &Resultaat) {
+        let item_id = &resultaat.advertentie.item_id;
+        if self.lengtes.contains_key(item_id) {
+            return;
+        }
+
+        // De volledige beschrijving als die opgehaald is, anders die uit de zoekresultaten
+        let beschrijving = resultaat.details.as_ref()
+            .and_then(|d| d.beschrijving.as_deref())
+            .or(resultaat.advertentie.beschrijving.as_deref())
+            .unwrap_or("");
+
+        let mut tellingen: HashMap<String, u32> = HashMap::new();
+        for term in termen(&resultaat.advertentie.titel) {
+            *tellingen.entry(term).or_default() += TITEL_GEWICHT;
+        }
+        for term in termen(beschrijving) {
+            *tellingen.entry(term).or_default() += 1;
+        }
+
+        self.lengtes.insert(item_id.clone(), tellingen.values().sum());
+        for (term, aantal) in tellingen {
+            self.termen.entry(term).or_default().insert(item_id.clone(), aantal);
+        }
+    }
+
+    // Alle zoektermen moeten voorkomen, de score is de som van BM25 per term
+    fn zoek(&self, zoekterm: &str) -> HashMap<String, f64> {
+        let mut zoektermen = termen(zoekterm);
+        let mut gezien = HashSet::new();
+        zoektermen.retain(|t| gezien.insert(t.clone()));
+
+        if zoektermen.is_empty() || self.lengtes.is_empty() {
+            return HashMap::new();
+        }
+
+        let aantal = self.lengtes.len() as f64;
+        let gemiddelde_lengte = self.lengtes.values().sum::<u32>() as f64 / aantal;
+
+        let mut scores: Option<HashMap<String, f64>> = None;
+        for zoekterm in zoektermen {
+            let mut term_scores: HashMap<String, f64> = HashMap::new();
+
+            // Korte termen alleen exact, anders vindt "rx" ook "rxt" en zo
+            let met_prefix = zoekterm.chars().count() >= 3;
+            let kandidaten = self.termen.range(zoekterm.clone()..)
+                .take_while(|(term, _)| term.starts_with(&zoekterm))
+                .filter(|(term, _)| met_prefix || **term == zoekterm);
+
+            for (term, documenten) in kandidaten {
+                let gewicht = if *term == zoekterm { 1.0 } else { PREFIX_GEWICHT };
+                let df = documenten.len() as f64;
+                let idf = ((aantal - df + 0.5) / (df + 0.5) + 1.0).ln();
+
+                for (item_id, &tf) in documenten {
+                    let tf = tf as f64;
+                    let lengte = self.lengtes[item_id] as f64;
+                    let score = gewicht * idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * lengte / gemiddelde_lengte));
+                    let huidig = term_scores.entry(item_id.clone()).or_insert(0.0);
+                    *huidig = huidig.max(score);
+                }
+            }
+
+            scores = Some(match scores {
+                None => term_scores,
+                Some(mut vorige) => {
+                    vorige.retain(|item_id, _| term_scores.contains_key(item_id));
+                    for (item_id, score) in vorige.iter_mut() {
+                        *score += term_scores[item_id];
+                    }
+                    vorige
+                }
+            });
+        }
+
+        scores.unwrap_or_default()
+    }
+}
+
+// Item id -> score, alleen advertenties die aan alle zoektermen voldoen
+pub fn zoek(pad: &str, zoekterm: &str) -> HashMap<String, f64> {
+    let mut index = INDEX.lock().unwrap();
+    if index.as_ref().is_none_or(|i| i.pad != pad) {
+        *index = Some(Zoekindex::bouw(pad));
+    }
+    index.as_ref().map(|i| i.zoek(zoekterm)).unwrap_or_default()
+}
+
+// Nog niet opgebouwd is prima, dan komt het resultaat er bij het opbouwen vanzelf in
+pub fn voeg_toe(pad: &str, resultaat: &Resultaat) {
+    if let Some(index) = INDEX.lock().unwrap().as_mut()
+        && index.pad == pad {
+        index.voeg_toe(resultaat);
+    }
+}
+
+// Na het herschrijven of wissen van de opslag wordt de index opnieuw opgebouwd
+pub fn wis() {
+    *INDEX.lock().unwrap() = None;
+}
+
+pub fn termen(tekst: &str) -> Vec<String> {
+    verwijder_html(tekst)
+        .to_lowercase()
+        .chars()
+        .map(vouw_accenten)
+        .collect::<String>()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|woord| woord.chars().count() > 1 && !STOPWOORDEN.contains(woord))
+        .map(stam)
+        .collect()
+}
+
+fn vouw_accenten(c: char) -> char {
+    match c {
+        'á' | 'à' | 'â' | 'ä' | 'ã' | 'å' => 'a',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'ó' | 'ò' | 'ô' | 'ö' | 'õ' => 'o',
+        'ú' | 'ù' | 'û' | 'ü' => 'u',
+        'ç' => 'c',
+        'ñ' => 'n',
+        'ý' | 'ÿ' => 'y',
+        c => c,
+    }
+}
+
+// Heel simpele Nederlandse stemmer: verkleinwoorden, meervouden en de buigings -e eraf.
+// "stoeltjes", "stoelen" en "stoel" worden allemaal "stoel", "zwarte" wordt "zwart".
+fn stam(woord: &str) -> String {
+    if woord.chars().any(|c| !c.is_alphabetic()) {
+        return woord.to_string(); // "6600", "512gb" etc. laten we met rust
+    }
+
+    let mut stam = woord.to_string();
+    let mag_weg = |stam: &str, achtervoegsel: &str| stam.ends_with(achtervoegsel) && stam.len() - achtervoegsel.len() >= 4;
+
+    if let Some(verkleinwoord) = ["etjes", "tjes", "jes", "etje", "tje", "je"].iter().find(|a| mag_weg(&stam, a)) {
+        stam.truncate(stam.len() - verkleinwoord.len());
+        return stam;
+    }
+
+    // "tafels", "computers", "keukens"
+    if ["els", "ers", "ems", "ens"].iter().any(|a| stam.ends_with(a)) && stam.len() >= 5 {
+        stam.pop();
+    }
+
+    if mag_weg(&stam, "en") {
+        stam.truncate(stam.len() - 2);
+        // "bedden" -> "bedd" -> "bed"
+        let mut einde = stam.chars().rev();
+        if let (Some(a), Some(b)) = (einde.next(), einde.next())
+            && a == b && !"aeiou".contains(a) {
+            stam.pop();
+        }
+    } else if mag_weg(&stam, "e") {
+        stam.pop();
+    }
+
+    stam
+}
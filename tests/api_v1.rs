@@ -0,0 +1,350 @@
+// Tests voor de versie 1 JSON API onder /api/v1: status codes, foutmeldingen en de OpenAPI beschrijving.
+
+mod common;
+
+use common::Omgeving;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+
+const WENSLIJST: &str = "# Test wenslijst\nrx 6600;150\nstoel;0\n\n# Handhelds\nsteam deck;-1\n";
+
+async fn verstuur(verzoek: reqwest::RequestBuilder) -> (StatusCode, Value) {
+    let antwoord = verzoek.send().await.unwrap();
+    let status = antwoord.status();
+    let tekst = antwoord.text().await.unwrap();
+    (status, serde_json::from_str(&tekst).unwrap_or(Value::Null))
+}
+
+// De ETag die bij aanpassen en verwijderen mee moet
+async fn wenslijst_versie(omgeving: &Omgeving) -> String {
+    let antwoord = reqwest::get(omgeving.url("/api/v1/wenslijst")).await.unwrap();
+    antwoord.headers()["etag"].to_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn resultaten_lezen_en_aanpassen() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "");
+    let client = reqwest::Client::new();
+
+    let (status, pagina) = verstuur(client.get(omgeving.url("/api/v1/resultaten?sorteer=prijs&limit=2"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(pagina["totaal"], 5);
+    assert_eq!(pagina["offset"], 0);
+    assert_eq!(pagina["limit"], 2);
+    assert_eq!(pagina["resultaten"].as_array().unwrap().len(), 2);
+    assert_eq!(pagina["resultaten"][0]["titel"], "Houten stoel gratis af te halen");
+
+    let (status, resultaat) = verstuur(client.get(omgeving.url("/api/v1/resultaten/m2100000001"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(resultaat["titel"], "AMD Radeon RX 6600 8GB");
+    assert_eq!(resultaat["staat"], "nieuw");
+
+    let (status, resultaat) = verstuur(client.patch(omgeving.url("/api/v1/resultaten/m2100000001"))
+        .json(&json!({ "staat": "gecontacteerd", "favoriet": true, "notitie": "  Morgen ophalen " }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(resultaat["staat"], "gecontacteerd");
+    assert_eq!(resultaat["favoriet"], true);
+    assert_eq!(resultaat["notitie"], "Morgen ophalen");
+
+    let (status, fout) = verstuur(client.get(omgeving.url("/api/v1/resultaten/m999"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(fout["fout"], "niet_gevonden");
+
+    // Een status voor een onbekend id wordt niet aangemaakt
+    let (status, _) = verstuur(client.patch(omgeving.url("/api/v1/resultaten/m999")).json(&json!({ "favoriet": true }))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(!omgeving.lees("status.json").contains("m999"));
+
+    let (status, fout) = verstuur(client.patch(omgeving.url("/api/v1/resultaten/m2100000001")).json(&json!({ "staat": "verkocht" }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(fout["fout"], "ongeldige_body");
+
+    let (status, fout) = verstuur(client.get(omgeving.url("/api/v1/resultaten?sorteer=willekeurig"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(fout["fout"], "ongeldige_query");
+
+    let (status, fout) = verstuur(client.get(omgeving.url("/api/v1/bestaat_niet"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(fout["fout"], "niet_gevonden");
+
+    let (status, _) = verstuur(client.delete(omgeving.url("/api/v1/resultaten"))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, pagina) = verstuur(client.get(omgeving.url("/api/v1/resultaten"))).await;
+    assert_eq!(pagina["totaal"], 0);
+}
+
+#[tokio::test]
+async fn wenslijst_per_regel() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "");
+    let client = reqwest::Client::new();
+
+    let (status, regels) = verstuur(client.get(omgeving.url("/api/v1/wenslijst"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(regels, json!([
+        { "id": 1, "zoekwoord": "rx 6600", "max_prijs": 150 },
+        { "id": 2, "zoekwoord": "stoel", "max_prijs": 0 },
+        { "id": 3, "zoekwoord": "steam deck", "max_prijs": -1 },
+    ]));
+
+    let antwoord = client.post(omgeving.url("/api/v1/wenslijst")).json(&json!({ "zoekwoord": "iphone 13", "max_prijs": 300 })).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::CREATED);
+    assert_eq!(antwoord.headers()["location"], "/api/v1/wenslijst/4");
+
+    // Zonder versie geen aanpassing, de id's kunnen intussen verschoven zijn
+    for verzoek in [client.put(omgeving.url("/api/v1/wenslijst/2")).json(&json!({ "zoekwoord": "bureaustoel", "max_prijs": 25 })), client.delete(omgeving.url("/api/v1/wenslijst/1"))] {
+        let (status, fout) = verstuur(verzoek).await;
+        assert_eq!(status, StatusCode::PRECONDITION_REQUIRED);
+        assert_eq!(fout["fout"], "versie_vereist");
+    }
+    assert!(omgeving.lees("wishlist.txt").contains("\nstoel;0\n"));
+
+    let (status, regel) = verstuur(client.put(omgeving.url("/api/v1/wenslijst/2")).header("If-Match", wenslijst_versie(&omgeving).await).json(&json!({ "zoekwoord": "bureaustoel", "max_prijs": 25 }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(regel, json!({ "id": 2, "zoekwoord": "bureaustoel", "max_prijs": 25 }));
+
+    let (status, _) = verstuur(client.delete(omgeving.url("/api/v1/wenslijst/1")).header("If-Match", wenslijst_versie(&omgeving).await)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // Commentaar en lege regels blijven staan
+    assert_eq!(omgeving.lees("wishlist.txt"), "# Test wenslijst\nbureaustoel;25\n\n# Handhelds\nsteam deck;-1\niphone 13;300\n");
+
+    let (status, fout) = verstuur(client.get(omgeving.url("/api/v1/wenslijst/9"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(fout["fout"], "niet_gevonden");
+    let (status, _) = verstuur(client.delete(omgeving.url("/api/v1/wenslijst/9")).header("If-Match", wenslijst_versie(&omgeving).await)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    for ongeldig in [json!({ "zoekwoord": "", "max_prijs": 10 }), json!({ "zoekwoord": "a;b", "max_prijs": 10 }), json!({ "zoekwoord": "tv", "max_prijs": -5 })] {
+        let (status, fout) = verstuur(client.post(omgeving.url("/api/v1/wenslijst")).json(&ongeldig)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", ongeldig);
+        assert_eq!(fout["fout"], "ongeldige_invoer");
+    }
+    let (status, fout) = verstuur(client.post(omgeving.url("/api/v1/wenslijst")).json(&json!({ "zoekwoord": "tv" }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(fout["fout"], "ongeldige_body");
+
+    // Eigen interval en venster per zoekwoord
+    let (status, regel) = verstuur(client.put(omgeving.url("/api/v1/wenslijst/2")).header("If-Match", wenslijst_versie(&omgeving).await)
+        .json(&json!({ "zoekwoord": "steam deck", "max_prijs": -1, "interval_seconden": 300, "venster": "ma-vr 08-23" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(regel, json!({ "id": 2, "zoekwoord": "steam deck", "max_prijs": -1, "interval_seconden": 300, "venster": "ma-vr 08-23" }));
+    assert!(omgeving.lees("wishlist.txt").contains("\nsteam deck;-1;300;ma-vr 08-23\n"));
+    let (_, regel) = verstuur(client.put(omgeving.url("/api/v1/wenslijst/3")).header("If-Match", wenslijst_versie(&omgeving).await).json(&json!({ "zoekwoord": "iphone 13", "max_prijs": 300, "venster": "za,zo" }))).await;
+    assert_eq!(regel["venster"], "za,zo");
+    assert!(omgeving.lees("wishlist.txt").ends_with("\niphone 13;300;;za,zo\n"));
+    let (_, regels) = verstuur(client.get(omgeving.url("/api/v1/wenslijst"))).await;
+    assert_eq!(regels[2], json!({ "id": 3, "zoekwoord": "iphone 13", "max_prijs": 300, "venster": "za,zo" }));
+
+    // Percentiel in plaats van een prijs
+    let (status, regel) = verstuur(client.put(omgeving.url("/api/v1/wenslijst/3")).header("If-Match", wenslijst_versie(&omgeving).await).json(&json!({ "zoekwoord": "iphone 13", "max_prijs": -1, "max_percentiel": 20 }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(regel, json!({ "id": 3, "zoekwoord": "iphone 13", "max_prijs": -1, "max_percentiel": 20 }));
+    assert!(omgeving.lees("wishlist.txt").ends_with("\niphone 13;p20\n"));
+
+    for ongeldig in [json!({ "interval_seconden": 0 }), json!({ "interval_seconden": 5999999999999999940u64 }), json!({ "venster": "maandag" }), json!({ "venster": "08-08" }), json!({ "venster": "ma 25-26" }), json!({ "max_percentiel": 25 }), json!({ "max_prijs": -1, "max_percentiel": 100 })] {
+        let mut body = json!({ "zoekwoord": "tv", "max_prijs": 10 });
+        body.as_object_mut().unwrap().extend(ongeldig.as_object().unwrap().clone());
+        let (status, fout) = verstuur(client.post(omgeving.url("/api/v1/wenslijst")).json(&body)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        assert_eq!(fout["fout"], "ongeldige_invoer");
+    }
+}
+
+#[tokio::test]
+async fn config_gedeeltelijk_aanpassen() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "web_token = \"geheim\"\napi_tokens = [\"script-token\"]\n");
+    let client = reqwest::Client::new();
+
+    let (status, config) = verstuur(client.patch(omgeving.url("/api/v1/config")).bearer_auth("script-token")
+        .json(&json!({ "afstand_km": 25, "toon_bieden": false }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(config["afstand_km"], 25);
+    assert_eq!(config["postcode"], "3032SG");
+    assert_eq!(config["web_token"], "");
+
+    let opgeslagen: toml::Table = toml::from_str(&omgeving.lees("config.toml")).unwrap();
+    assert_eq!(opgeslagen["afstand_km"].as_integer(), Some(25));
+    assert_eq!(opgeslagen["toon_bieden"].as_bool(), Some(false));
+    assert_eq!(opgeslagen["web_token"].as_str(), Some("geheim"));
+
+    for ongeldig in [json!({ "afstand": 5 }), json!({ "afstand_km": "ver" }), json!({ "api_tokens": [] }), json!({ "check_interval_seconden": 0 }), json!({ "min_interval_seconden": 5000 })] {
+        let (status, fout) = verstuur(client.patch(omgeving.url("/api/v1/config")).bearer_auth("script-token").json(&ongeldig)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", ongeldig);
+        assert_eq!(fout["fout"], "ongeldige_invoer");
+    }
+
+    for vast in [json!({ "opslag_bestand": "/tmp/elders.jsonl" }), json!({ "tls_sleutel": "sleutel.pem" }), json!({ "web_adres": "0.0.0.0" }), json!({ "api_basis_url": "http://elders" })] {
+        let (status, fout) = verstuur(client.patch(omgeving.url("/api/v1/config")).bearer_auth("script-token").json(&vast)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", vast);
+        assert_eq!(fout["fout"], "ongeldige_invoer");
+    }
+    let opgeslagen: toml::Table = toml::from_str(&omgeving.lees("config.toml")).unwrap();
+    assert_ne!(opgeslagen.get("opslag_bestand").and_then(|waarde| waarde.as_str()), Some("/tmp/elders.jsonl"));
+
+    let (status, fout) = verstuur(client.get(omgeving.url("/api/v1/config"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(fout["fout"], "niet_ingelogd");
+}
+
+#[tokio::test]
+async fn zoeken_op_marktplaats() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "");
+    let client = reqwest::Client::new();
+
+    let (status, treffers) = verstuur(client.get(omgeving.url("/api/v1/zoek?q=rx%206600&max_prijs=145"))).await;
+    assert_eq!(status, StatusCode::OK);
+    let treffers = treffers.as_array().unwrap();
+    assert!(!treffers.is_empty());
+    assert!(treffers.iter().all(|t| t["prijs_centen"].as_i64().is_none_or(|c| c <= 14500)));
+    assert!(treffers.iter().all(|t| t["link"].as_str().unwrap().starts_with("https://www.marktplaats.nl/v/")));
+    // Geen ruwe Marktplaats velden
+    assert!(treffers[0].get("priceInfo").is_none());
+
+    let (status, fout) = verstuur(client.get(omgeving.url("/api/v1/zoek?q=%20"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(fout["fout"], "ongeldige_query");
+}
+
+#[tokio::test]
+async fn openapi_beschrijving() {
+    // Ook zonder login te lezen
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "web_token = \"geheim\"\n");
+
+    let (status, document) = verstuur(reqwest::Client::new().get(omgeving.url("/api/v1/openapi.json"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(document["openapi"], "3.0.3");
+    for pad in ["/resultaten", "/resultaten/{item_id}", "/zoek", "/wenslijst", "/wenslijst/{id}", "/status", "/config"] {
+        assert!(document["paths"][pad].is_object(), "{} ontbreekt", pad);
+    }
+    assert!(document["paths"]["/wenslijst/{id}"]["delete"]["responses"]["404"].is_object());
+
+    // Elke verwijzing moet naar een bestaand schema wijzen
+    let tekst = document.to_string();
+    for verwijzing in tekst.split("#/components/schemas/").skip(1) {
+        let naam = verwijzing.split('"').next().unwrap();
+        assert!(document["components"]["schemas"][naam].is_object(), "schema {} ontbreekt", naam);
+    }
+}
+
+#[tokio::test]
+async fn wenslijst_met_versie() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "");
+    let client = reqwest::Client::new();
+
+    let antwoord = client.get(omgeving.url("/api/v1/wenslijst")).send().await.unwrap();
+    let versie = antwoord.headers()["etag"].to_str().unwrap().to_string();
+    assert!(versie.starts_with('"'));
+
+    // Iemand anders past de wenslijst aan met dezelfde versie, daarna is die versie verlopen
+    let antwoord = client.put(omgeving.url("/api/v1/wenslijst/1")).header("If-Match", &versie)
+        .json(&json!({ "zoekwoord": "rx 6700", "max_prijs": 200 })).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::OK);
+    let nieuwe_versie = antwoord.headers()["etag"].to_str().unwrap().to_string();
+    assert_ne!(nieuwe_versie, versie);
+
+    let (status, fout) = verstuur(client.delete(omgeving.url("/api/v1/wenslijst/2")).header("If-Match", &versie)).await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(fout["fout"], "versie_conflict");
+    let (status, _) = verstuur(client.post(omgeving.url("/api/v1/wenslijst")).header("If-Match", &versie)
+        .json(&json!({ "zoekwoord": "tv", "max_prijs": 50 }))).await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(omgeving.lees("wishlist.txt"), "# Test wenslijst\nrx 6700;200\nstoel;0\n\n# Handhelds\nsteam deck;-1\n");
+
+    // Met de nieuwe versie lukt het wel, en "*" slaat de controle over
+    let antwoord = client.delete(omgeving.url("/api/v1/wenslijst/2")).header("If-Match", &nieuwe_versie).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::NO_CONTENT);
+    let (status, regel) = verstuur(client.put(omgeving.url("/api/v1/wenslijst/2")).header("If-Match", "*")
+        .json(&json!({ "zoekwoord": "steam deck oled", "max_prijs": -1 }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(regel["id"], 2);
+    assert_eq!(omgeving.lees("wishlist.txt"), "# Test wenslijst\nrx 6700;200\n\n# Handhelds\nsteam deck oled;-1\n");
+
+    // De oude route houdt zich ook aan de versie en laat het commentaar staan
+    let antwoord = client.get(omgeving.url("/wenslijst")).send().await.unwrap();
+    let versie = antwoord.headers()["etag"].to_str().unwrap().trim_matches('"').to_string();
+    let antwoord = client.post(omgeving.url("/wenslijst"))
+        .json(&json!({ "artikelen": [{ "zoekwoord": "rx 6600", "max_prijs": "150" }], "versie": "verouderd" }))
+        .send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::CONFLICT);
+    let antwoord = client.post(omgeving.url("/wenslijst"))
+        .json(&json!({ "artikelen": [{ "zoekwoord": "rx 6600", "max_prijs": "150" }], "versie": versie }))
+        .send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::OK);
+    assert_eq!(omgeving.lees("wishlist.txt"), "# Test wenslijst\nrx 6600;150\n\n# Handhelds\n");
+}
+
+#[tokio::test]
+async fn resultaten_exporteren() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "");
+
+    let antwoord = reqwest::get(omgeving.url("/api/v1/export?formaat=csv")).await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::OK);
+    assert_eq!(antwoord.headers()["content-type"], "text/csv; charset=utf-8");
+    assert!(antwoord.headers()["content-disposition"].to_str().unwrap().ends_with(".csv\""));
+    let csv = antwoord.text().await.unwrap();
+    let regels: Vec<&str> = csv.lines().collect();
+    assert_eq!(regels.len(), 6, "kop en 5 resultaten");
+    assert!(regels[0].starts_with("item_id,titel,beschrijving,prijs_centen,prijs_type,stad,afstand_meters,link,"));
+    assert!(regels.iter().any(|r| r.starts_with("m2100000001,AMD Radeon RX 6600 8GB,") && r.contains(",14000,FIXED,")));
+
+    let (status, json) = verstuur(reqwest::Client::new().get(omgeving.url("/api/v1/export?formaat=json&zoekwoord=steam%20deck"))).await;
+    assert_eq!(status, StatusCode::OK);
+    let json = json.as_array().unwrap();
+    assert_eq!(json.len(), 2);
+    assert!(json.iter().all(|r| r["zoekwoord"] == "steam deck" && r["tijdstempel"].is_string() && r["vip_url"].is_string()));
+    assert_eq!(json[0]["max_prijs"], Value::Null);
+
+    let ndjson = reqwest::get(omgeving.url("/api/v1/export?formaat=ndjson&prijs_type=FREE")).await.unwrap().text().await.unwrap();
+    let regels: Vec<Value> = ndjson.lines().map(|r| serde_json::from_str(r).unwrap()).collect();
+    assert_eq!(regels.len(), 1);
+    assert_eq!(regels[0]["titel"], "Houten stoel gratis af te halen");
+    assert_eq!(regels[0]["max_prijs"], 0);
+
+    let (status, fout) = verstuur(reqwest::Client::new().get(omgeving.url("/api/v1/export?formaat=xlsx"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(fout["fout"], "ongeldige_query");
+
+    // Ook vanaf de opdrachtregel, naar een bestand of naar stdout
+    let uitvoer = omgeving.voer_uit(&["export", "ndjson", "--zoekwoord", "rx 6600", "--uitvoer", "export.ndjson"]);
+    assert!(uitvoer.status.success(), "{}", String::from_utf8_lossy(&uitvoer.stderr));
+    assert_eq!(omgeving.lees("export.ndjson").lines().count(), 2);
+    let uitvoer = omgeving.voer_uit(&["export", "csv"]);
+    let csv = String::from_utf8(uitvoer.stdout).unwrap();
+    assert!(csv.starts_with("item_id,titel,"));
+    assert_eq!(csv.lines().count(), 6);
+    assert!(!omgeving.voer_uit(&["export", "xlsx"]).status.success());
+
+    // Dezelfde prijsfilters als de API, in euro's
+    let uitvoer = omgeving.voer_uit(&["results", "export", "ndjson", "--prijs-min", "100", "--prijs-max", "150"]);
+    assert!(uitvoer.status.success(), "{}", String::from_utf8_lossy(&uitvoer.stderr));
+    let regels: Vec<Value> = String::from_utf8(uitvoer.stdout).unwrap().lines().map(|r| serde_json::from_str(r).unwrap()).collect();
+    assert_eq!(regels.len(), 1);
+    assert_eq!(regels[0]["item_id"], "m2100000001");
+    assert!(!omgeving.voer_uit(&["results", "export", "csv", "--prijs-max", "veel"]).status.success());
+}
+
+#[tokio::test]
+async fn export_met_verkoper_uit_de_details() {
+    let mut omgeving = Omgeving::met_advertenties("lamp;-1\n", "haal_details_op = true\n", json!([{
+        "itemId": "m3100000001",
+        "title": "Lamp",
+        "description": "Staande lamp",
+        "priceInfo": { "priceCents": 1500, "priceType": "FIXED" },
+        "location": { "cityName": "Rotterdam", "distanceMeters": 1000 },
+        "vipUrl": "/v/huis/m3100000001-lamp",
+        "sellerInformation": { "sellerId": 77, "sellerName": "Fatima" },
+        "alleenOpPagina": ["sellerInformation"],
+    }]));
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+
+    // Net als in de web interface, de zoek API gaf geen verkoper
+    let resultaten = omgeving.get_json("/resultaten").await;
+    assert_eq!(resultaten[0]["verkoper_naam"], "Fatima");
+    let uitvoer = omgeving.voer_uit(&["results", "export", "ndjson"]);
+    let regel: Value = serde_json::from_slice(&uitvoer.stdout).unwrap();
+    assert_eq!(regel["verkoper_id"], "77");
+    assert_eq!(regel["verkoper_naam"], "Fatima");
+    let csv = reqwest::get(omgeving.url("/api/v1/export?formaat=csv")).await.unwrap().text().await.unwrap();
+    assert!(csv.lines().nth(1).unwrap().contains(",77,Fatima,lamp,"), "{}", csv);
+}
@@ -0,0 +1,238 @@
+// Gedeelde hulpmiddelen voor de integratietests: start de mock server en de monitor
+// in een eigen tijdelijke map, en ruimt alles op als de test klaar is.
+
+#![allow(dead_code)] // Niet elke test gebruikt elke hulpfunctie
+
+use std::io::{BufRead, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+static TELLER: AtomicUsize = AtomicUsize::new(0);
+
+pub fn vrije_poort() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+fn wacht_op_poort(poort: u16) {
+    let begin = Instant::now();
+    while TcpStream::connect(("127.0.0.1", poort)).is_err() {
+        assert!(begin.elapsed() < Duration::from_secs(10), "poort {} kwam niet op", poort);
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+pub struct Omgeving {
+    pub map: PathBuf,
+    pub web_poort: u16,
+    pub mock_poort: u16,
+    pub argumenten: Vec<String>, // Voor de monitor, bijv. --config
+    mock: Child,
+    monitor: Option<Child>,
+    uitvoer: Option<Receiver<String>>,
+}
+
+impl Omgeving {
+    // `extra_config` wordt achter de standaard testconfig geplakt, dus kan velden overschrijven
+    pub fn nieuw(wenslijst: &str, extra_config: &str) -> Self {
+        Omgeving::maak(wenslijst, extra_config, None)
+    }
+
+    // Zelfde als `nieuw`, maar de mock server geeft deze advertenties terug i.p.v. de standaard set
+    pub fn met_advertenties(wenslijst: &str, extra_config: &str, advertenties: serde_json::Value) -> Self {
+        Omgeving::maak(wenslijst, extra_config, Some(advertenties))
+    }
+
+    fn maak(wenslijst: &str, extra_config: &str, advertenties: Option<serde_json::Value>) -> Self {
+        let map = std::env::temp_dir().join(format!(
+            "mpm-test-{}-{}",
+            std::process::id(),
+            TELLER.fetch_add(1, Ordering::SeqCst)
+        ));
+        std::fs::create_dir_all(&map).unwrap();
+
+        let mock_poort = vrije_poort();
+        let mut mock = Command::new(env!("CARGO_BIN_EXE_mock_server"));
+        mock.arg(mock_poort.to_string());
+        if let Some(advertenties) = advertenties {
+            let pad = map.join("advertenties.json");
+            std::fs::write(&pad, serde_json::json!({ "listings": advertenties }).to_string()).unwrap();
+            mock.arg(pad);
+        }
+        let mock = mock
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+        wacht_op_poort(mock_poort);
+
+        let web_poort = vrije_poort();
+        let mut config: toml::Table = toml::from_str(&format!(
+            "postcode = \"3032SG\"\ncheck_interval_seconden = 3600\nweb_poort = {}\napi_basis_url = \"http://127.0.0.1:{}\"\nlink_basis_url = \"https://www.marktplaats.nl\"\n",
+            web_poort, mock_poort
+        )).unwrap();
+        config.extend(toml::from_str::<toml::Table>(extra_config).unwrap());
+        std::fs::write(map.join("config.toml"), toml::to_string(&config).unwrap()).unwrap();
+        std::fs::write(map.join("wishlist.txt"), wenslijst).unwrap();
+
+        Omgeving { map, web_poort, mock_poort, argumenten: Vec::new(), mock, monitor: None, uitvoer: None }
+    }
+
+    pub fn start_monitor(&mut self) {
+        self.start_proces();
+        wacht_op_poort(self.web_poort);
+    }
+
+    // Zonder te wachten tot de web interface bereikbaar is
+    pub fn start_proces(&mut self) {
+        let mut monitor = Command::new(env!("CARGO_BIN_EXE_marktplaats-monitor"))
+            .current_dir(&self.map)
+            .args(&self.argumenten)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        // Stdout en stderr komen samen in één kanaal
+        let (zender, ontvanger) = mpsc::channel();
+        let stdout = monitor.stdout.take().unwrap();
+        let stderr = monitor.stderr.take().unwrap();
+        let stderr_zender = zender.clone();
+        std::thread::spawn(move || {
+            for lijn in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if zender.send(lijn).is_err() {
+                    break;
+                }
+            }
+        });
+        std::thread::spawn(move || {
+            for lijn in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if stderr_zender.send(lijn).is_err() {
+                    break;
+                }
+            }
+        });
+
+        self.monitor = Some(monitor);
+        self.uitvoer = Some(ontvanger);
+    }
+
+    pub fn herstart_monitor(&mut self) {
+        if let Some(mut monitor) = self.monitor.take() {
+            monitor.kill().ok();
+            monitor.wait().ok();
+        }
+        // Even wachten tot de web poort weer vrij is
+        let begin = Instant::now();
+        while TcpStream::connect(("127.0.0.1", self.web_poort)).is_ok() && begin.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        self.start_monitor();
+    }
+
+    // Wacht tot de monitor een volledige ronde door de wenslijst heeft gedaan
+    pub fn wacht_op_ronde(&self) {
+        self.wacht_op_regel("Volgende check in");
+    }
+
+    // Wacht op een regel uitvoer die met `begin` begint en geeft die terug
+    pub fn wacht_op_regel(&self, begin: &str) -> String {
+        let ontvanger = self.uitvoer.as_ref().expect("monitor niet gestart");
+        let start = Instant::now();
+        loop {
+            let over = Duration::from_secs(30).saturating_sub(start.elapsed());
+            match ontvanger.recv_timeout(over) {
+                Ok(lijn) if lijn.starts_with(begin) => return lijn,
+                Ok(_) => continue,
+                Err(_) => panic!("geen regel die begint met '{}'", begin),
+            }
+        }
+    }
+
+    // Alle uitvoer tot en met de `aantal`-ste ronde die nog moet komen
+    pub fn uitvoer_van_rondes(&self, aantal: usize) -> Vec<String> {
+        let ontvanger = self.uitvoer.as_ref().expect("monitor niet gestart");
+        let start = Instant::now();
+        let mut lijnen = Vec::new();
+        let mut rondes = 0;
+        while rondes < aantal {
+            let over = Duration::from_secs(30).saturating_sub(start.elapsed());
+            let lijn = ontvanger.recv_timeout(over).unwrap_or_else(|_| panic!("maar {} van de {} rondes", rondes, aantal));
+            if lijn.starts_with("Volgende check in") {
+                rondes += 1;
+            }
+            lijnen.push(lijn);
+        }
+        lijnen
+    }
+
+    pub fn start_en_wacht(wenslijst: &str, extra_config: &str) -> Self {
+        let mut omgeving = Omgeving::nieuw(wenslijst, extra_config);
+        omgeving.start_monitor();
+        omgeving.wacht_op_ronde();
+        omgeving
+    }
+
+    // De monitor als opdracht in de testmap draaien, bijv. `export csv`, en wachten tot hij klaar is
+    pub fn voer_uit(&self, argumenten: &[&str]) -> std::process::Output {
+        Command::new(env!("CARGO_BIN_EXE_marktplaats-monitor"))
+            .current_dir(&self.map)
+            .args(argumenten)
+            .output()
+            .unwrap()
+    }
+
+    pub fn lees(&self, bestand: &str) -> String {
+        std::fs::read_to_string(self.map.join(bestand)).unwrap_or_default()
+    }
+
+    pub fn schrijf(&self, bestand: &str, inhoud: &str) {
+        std::fs::write(self.map.join(bestand), inhoud).unwrap();
+    }
+
+    pub fn url(&self, pad: &str) -> String {
+        format!("http://127.0.0.1:{}{}", self.web_poort, pad)
+    }
+
+    pub async fn get_json(&self, pad: &str) -> serde_json::Value {
+        reqwest::get(self.url(pad)).await.unwrap().json().await.unwrap()
+    }
+
+    pub async fn post_json(&self, pad: &str, body: serde_json::Value) -> serde_json::Value {
+        reqwest::Client::new()
+            .post(self.url(pad))
+            .json(&body)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap()
+    }
+
+    // Titels van alles wat de web interface als resultaat teruggeeft
+    pub async fn titels(&self) -> Vec<String> {
+        let mut titels: Vec<String> = self.get_json("/resultaten").await
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["titel"].as_str().unwrap().to_string())
+            .collect();
+        titels.sort();
+        titels
+    }
+}
+
+impl Drop for Omgeving {
+    fn drop(&mut self) {
+        if let Some(monitor) = self.monitor.as_mut() {
+            monitor.kill().ok();
+            monitor.wait().ok();
+        }
+        self.mock.kill().ok();
+        self.mock.wait().ok();
+        std::fs::remove_dir_all(&self.map).ok();
+    }
+}
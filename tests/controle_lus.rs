@@ -0,0 +1,775 @@
+// Integratietests voor de volledige check lus: monitor + web interface tegen de mock zoek API.
+
+mod common;
+
+use common::Omgeving;
+use serde_json::json;
+
+const WENSLIJST: &str = "# Test wenslijst\nrx 6600;150\nstoel;0\nsteam deck;-1\n";
+
+#[tokio::test]
+async fn ronde_bewaart_overeenkomende_advertenties() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "");
+
+    assert_eq!(omgeving.titels().await, vec![
+        "AMD Radeon RX 6600 8GB",         // €140 <= €150
+        "Houten stoel gratis af te halen", // gratis, max 0
+        "RX 6600 bieden",                 // bieden, max 150
+        "Steam Deck OLED 512GB",          // onbeperkt
+        "Steamdeck 256GB LCD",            // bieden, onbeperkt
+    ]);
+
+    let bestand = omgeving.lees("results.txt");
+    assert!(bestand.contains("Gevonden: 'rx 6600' (max €150)"));
+    assert!(bestand.contains("Gevonden: 'steam deck' (max €onbeperkt)"));
+    assert!(bestand.contains("Prijs: €140.00"));
+    assert!(bestand.contains("Prijs: Bieden [BIEDEN]"));
+    assert!(bestand.contains("Prijs: Gratis [GRATIS]"));
+    assert!(bestand.contains("Link: https://www.marktplaats.nl/v/computers-en-software/videokaarten/m2100000001-amd-radeon-rx-6600-8gb"));
+    assert!(!bestand.contains("Sapphire Pulse"), "€195 is boven het budget");
+}
+
+#[tokio::test]
+async fn prijs_nul_accepteert_alleen_gratis() {
+    let omgeving = Omgeving::start_en_wacht("stoel;0\n", "");
+
+    // "Bureaustoel zwart" is SEE_DESCRIPTION en "Eetkamer stoel eiken" kost €25
+    assert_eq!(omgeving.titels().await, vec!["Houten stoel gratis af te halen"]);
+}
+
+#[tokio::test]
+async fn prijs_min_een_accepteert_alles() {
+    let omgeving = Omgeving::start_en_wacht("stoel;-1\n", "");
+
+    assert_eq!(omgeving.titels().await, vec![
+        "Bureaustoel zwart",
+        "Eetkamer stoel eiken",
+        "Houten stoel gratis af te halen",
+    ]);
+}
+
+#[tokio::test]
+async fn toon_vlaggen_filteren_prijstypes() {
+    let omgeving = Omgeving::start_en_wacht(
+        "stoel;-1\nrx 6600;150\n",
+        "toon_bieden = false\ntoon_gratis = false\ntoon_zie_beschrijving = false\n",
+    );
+
+    assert_eq!(omgeving.titels().await, vec![
+        "AMD Radeon RX 6600 8GB",
+        "Eetkamer stoel eiken",
+    ]);
+}
+
+#[tokio::test]
+async fn ongeldige_wenslijst_regels_worden_overgeslagen() {
+    let omgeving = Omgeving::start_en_wacht("# commentaar\nzonder puntkomma\nstoel;abc\nstoel;-5\nsteam deck;-1\n", "");
+
+    assert_eq!(omgeving.titels().await, vec!["Steam Deck OLED 512GB", "Steamdeck 256GB LCD"]);
+}
+
+#[tokio::test]
+async fn advertenties_worden_maar_een_keer_bewaard() {
+    let mut omgeving = Omgeving::nieuw("steam deck;-1\n", "");
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+
+    // Zelfde map, nieuwe monitor: bestaande resultaten tellen als gezien
+    let eerste = omgeving.lees("results.txt");
+    omgeving.herstart_monitor();
+    omgeving.wacht_op_ronde();
+    assert_eq!(omgeving.lees("results.txt"), eerste);
+    assert_eq!(omgeving.titels().await.len(), 2);
+}
+
+#[tokio::test]
+async fn advertenties_komen_terug_na_wissen() {
+    let mut omgeving = Omgeving::nieuw("steam deck;-1;1\n", "");
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+    assert_eq!(omgeving.titels().await.len(), 2);
+
+    // Eerst via de API, dan via de knop in de web interface. De ronde die al bezig was telt niet mee
+    let antwoord = reqwest::Client::new().delete(omgeving.url("/api/v1/resultaten")).send().await.unwrap();
+    assert_eq!(antwoord.status(), reqwest::StatusCode::NO_CONTENT);
+    omgeving.uitvoer_van_rondes(2);
+    assert_eq!(omgeving.titels().await, vec!["Steam Deck OLED 512GB", "Steamdeck 256GB LCD"]);
+
+    let antwoord = omgeving.post_json("/wis_resultaten", json!({})).await;
+    assert_eq!(antwoord["status"], "ok");
+    omgeving.uitvoer_van_rondes(2);
+    assert_eq!(omgeving.titels().await, vec!["Steam Deck OLED 512GB", "Steamdeck 256GB LCD"]);
+    assert!(omgeving.lees("results.txt").contains("Steam Deck OLED 512GB"));
+}
+
+#[tokio::test]
+async fn resultaten_zoekfilter() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "");
+
+    let gevonden = omgeving.get_json("/resultaten?q=OLED").await;
+    let gevonden = gevonden.as_array().unwrap();
+    assert_eq!(gevonden.len(), 1);
+    assert_eq!(gevonden[0]["zoekwoord"], "steam deck");
+    assert_eq!(gevonden[0]["prijs"], "€425.00");
+    assert_eq!(gevonden[0]["locatie"], "Delft");
+    assert_eq!(gevonden[0]["afstand"], "7.9 km");
+}
+
+#[tokio::test]
+async fn check_lus_en_api_lezen_de_wenslijst_hetzelfde() {
+    let mut omgeving = Omgeving::nieuw("stoel;-1\n;-1\nrx 6600;150;5m;ma-vr 08-23;extra\nsteam deck;p101\nfiets\n", "");
+    omgeving.start_monitor();
+    let uitvoer = omgeving.uitvoer_van_rondes(1);
+    for lijn in [2, 3, 4, 5] {
+        assert!(uitvoer.iter().any(|regel| regel.starts_with(&format!("Probleem op lijn {}:", lijn))), "{:?}", uitvoer);
+    }
+
+    let status = omgeving.get_json("/status").await;
+    let gecheckt: Vec<&str> = status["wenslijst"].as_array().unwrap().iter().map(|i| i["zoekwoord"].as_str().unwrap()).collect();
+    let wenslijst = omgeving.get_json("/api/v1/wenslijst").await;
+    let api: Vec<&str> = wenslijst.as_array().unwrap().iter().map(|r| r["zoekwoord"].as_str().unwrap()).collect();
+    assert_eq!(gecheckt, ["stoel"]);
+    assert_eq!(api, gecheckt);
+}
+
+#[tokio::test]
+async fn wenslijst_endpoint() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "");
+
+    assert_eq!(omgeving.get_json("/wenslijst").await, json!([
+        { "zoekwoord": "rx 6600", "max_prijs": "150" },
+        { "zoekwoord": "stoel", "max_prijs": "0" },
+        { "zoekwoord": "steam deck", "max_prijs": "-1" },
+    ]));
+
+    let antwoord = omgeving.post_json("/wenslijst", json!({
+        "artikelen": [{ "zoekwoord": "bureaustoel", "max_prijs": "-1" }]
+    })).await;
+    assert_eq!(antwoord["status"], "ok");
+    assert!(omgeving.lees("wishlist.txt").contains("\nbureaustoel;-1\n"));
+    assert_eq!(omgeving.get_json("/wenslijst").await, json!([
+        { "zoekwoord": "bureaustoel", "max_prijs": "-1" },
+    ]));
+}
+
+#[tokio::test]
+async fn config_endpoint() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "");
+
+    let config = omgeving.get_json("/config").await;
+    assert_eq!(config["web_poort"], omgeving.web_poort);
+    assert_eq!(config["api_basis_url"], format!("http://127.0.0.1:{}", omgeving.mock_poort));
+
+    let antwoord = omgeving.post_json("/config", json!({
+        "postcode": "1012AB",
+        "afstand_km": 25,
+        "check_interval_seconden": 600,
+        "max_advertenties_per_zoekopdracht": 10,
+        "toon_bieden": false,
+        "toon_gratis": true,
+        "toon_zie_beschrijving": true,
+    })).await;
+    assert_eq!(antwoord["status"], "ok");
+
+    let config = omgeving.get_json("/config").await;
+    assert_eq!(config["postcode"], "1012AB");
+    assert_eq!(config["afstand_km"], 25);
+    let bestand: toml::Table = toml::from_str(&omgeving.lees("config.toml")).unwrap();
+    assert_eq!(bestand["postcode"].as_str(), Some("1012AB"));
+    assert_eq!(bestand["toon_bieden"].as_bool(), Some(false));
+
+    // Wat de API weigert mag hier ook niet
+    let basis = json!({ "postcode": "3032SG", "afstand_km": 25, "check_interval_seconden": 600, "max_advertenties_per_zoekopdracht": 10, "toon_bieden": true, "toon_gratis": true, "toon_zie_beschrijving": true });
+    for ongeldig in [json!({ "check_interval_seconden": 0 }), json!({ "min_interval_seconden": 5000, "max_interval_seconden": 100 })] {
+        let mut body = basis.clone();
+        body.as_object_mut().unwrap().extend(ongeldig.as_object().unwrap().clone());
+        let antwoord = reqwest::Client::new().post(omgeving.url("/config")).json(&body).send().await.unwrap();
+        assert_eq!(antwoord.status(), 400, "{}", body);
+        assert_eq!(antwoord.json::<serde_json::Value>().await.unwrap()["status"], "error");
+    }
+    assert_eq!(omgeving.get_json("/config").await["postcode"], "1012AB");
+    assert!(omgeving.lees("config.toml").contains("check_interval_seconden = 600"));
+}
+
+#[tokio::test]
+async fn details_worden_opgehaald() {
+    let omgeving = Omgeving::start_en_wacht("steam deck;-1\n", "haal_details_op = true\n");
+
+    let resultaten = omgeving.get_json("/resultaten?q=OLED").await;
+    let details = &resultaten[0]["details"];
+    assert_eq!(details["beschrijving"], "Steam Deck OLED in nieuwstaat, inclusief hoes en lader.\nGekocht in maart, bon aanwezig. Alleen ophalen.");
+    assert_eq!(details["afbeelding_urls"].as_array().unwrap().len(), 2);
+    assert_eq!(details["verkoper_naam"], "Sanne");
+    assert_eq!(details["verkoper_id"], "1006");
+    assert_eq!(details["geplaatst_op"], "2025-10-05T16:45:00Z");
+    assert_eq!(details["bezorging"], "Ophalen");
+    assert_eq!(details["aantal_bekeken"], 1033);
+    assert_eq!(details["kenmerken"][0], json!({ "naam": "Conditie", "waarde": "Zo goed als nieuw" }));
+
+    // De zoek API geeft maar een stukje van de beschrijving
+    assert_eq!(resultaten[0]["beschrijving"], "Steam Deck OLED in nieuwstaat, inclusief hoes en lader.");
+}
+
+#[tokio::test]
+async fn zonder_details_optie_geen_details() {
+    let omgeving = Omgeving::start_en_wacht("steam deck;-1\n", "");
+
+    let resultaten = omgeving.get_json("/resultaten").await;
+    assert!(resultaten[0]["details"].is_null());
+}
+
+#[tokio::test]
+async fn oude_tekst_resultaten_worden_overgezet() {
+    let mut omgeving = Omgeving::nieuw("steam deck;-1\n", "");
+    omgeving.schrijf("results.txt", "Marktplaats Monitor Resultaten - Begonnen 2025-01-01 10:00:00\n\n\
+[2025-01-02 11:00:00] Gevonden: 'rx 6600' (max €150)\n  Titel: Oude RX 6600\n  Prijs: Bieden [BIEDEN]\n  Locatie: Gouda (12.5 km)\n  \
+Link: https://www.marktplaats.nl/v/computers-en-software/videokaarten/m1999999999-oude-rx-6600\n  Afbeelding: Geen afbeelding\n  Beschrijving: Oud\n\
+============================================================\n\n");
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+
+    let resultaten = omgeving.get_json("/resultaten?q=oude").await;
+    assert_eq!(resultaten[0]["item_id"], "m1999999999");
+    assert_eq!(resultaten[0]["prijs"], "Bieden [BIEDEN]");
+    assert_eq!(resultaten[0]["locatie"], "Gouda");
+    assert_eq!(resultaten[0]["afstand"], "12.5 km");
+    assert_eq!(resultaten[0]["tijdstempel"], "2025-01-02 11:00:00");
+    assert_eq!(omgeving.lees("results.jsonl").lines().count(), 3);
+}
+
+#[tokio::test]
+async fn geblokkeerde_verkopers_worden_overgeslagen() {
+    // Kees (id 1004) heeft beide stoelen, Sanne heeft id 1006
+    let omgeving = Omgeving::start_en_wacht(
+        "stoel;-1\nsteam deck;-1\n",
+        "geblokkeerde_verkopers = [\"kees\", \"1006\"]\n",
+    );
+
+    assert_eq!(omgeving.titels().await, vec!["Bureaustoel zwart", "Steamdeck 256GB LCD"]);
+}
+
+#[tokio::test]
+async fn alleen_vertrouwde_verkopers() {
+    let omgeving = Omgeving::start_en_wacht(
+        "stoel;-1\nrx 6600;-1\n",
+        "vertrouwde_verkopers = [\"PC Shop Zuid\", \"Kantoorinrichting BV\"]\nalleen_vertrouwde_verkopers = true\n",
+    );
+
+    let resultaten = omgeving.get_json("/resultaten").await;
+    assert_eq!(omgeving.titels().await, vec!["Bureaustoel zwart", "Sapphire Pulse RX 6600"]);
+    assert!(resultaten.as_array().unwrap().iter().all(|r| r["vertrouwde_verkoper"] == true));
+}
+
+#[tokio::test]
+async fn verkoper_blokkeren_via_web() {
+    let omgeving = Omgeving::start_en_wacht("stoel;-1\n", "");
+
+    let antwoord = omgeving.post_json("/blokkeer_verkoper", json!({ "verkoper": "1004" })).await;
+    assert_eq!(antwoord["status"], "ok");
+
+    // Bestaande resultaten van die verkoper verdwijnen meteen uit de lijst
+    assert_eq!(omgeving.titels().await, vec!["Bureaustoel zwart"]);
+    assert_eq!(omgeving.get_json("/config").await["geblokkeerde_verkopers"], json!(["1004"]));
+    let bestand: toml::Table = toml::from_str(&omgeving.lees("config.toml")).unwrap();
+    assert_eq!(bestand["geblokkeerde_verkopers"].as_array().unwrap().len(), 1);
+
+    // Namen zonder op hoofdletters te letten, en niemand op beide lijsten of twee keer op dezelfde
+    omgeving.post_json("/vertrouw_verkoper", json!({ "verkoper": "Kees" })).await;
+    omgeving.post_json("/blokkeer_verkoper", json!({ "verkoper": "kees" })).await;
+    omgeving.post_json("/blokkeer_verkoper", json!({ "verkoper": "KEES" })).await;
+    let config = omgeving.get_json("/config").await;
+    assert_eq!(config["geblokkeerde_verkopers"], json!(["1004", "kees"]));
+    assert_eq!(config["vertrouwde_verkopers"], json!([]));
+}
+
+fn advertentie(item_id: &str, titel: &str, centen: i32) -> serde_json::Value {
+    json!({
+        "itemId": item_id,
+        "title": titel,
+        "description": "Test advertentie",
+        "priceInfo": { "priceCents": centen, "priceType": "FIXED" },
+        "location": { "cityName": "Rotterdam", "distanceMeters": 1000 },
+        "vipUrl": format!("/v/test/{}-test", item_id),
+        "imageUrls": ["//images.marktplaats.com/test.jpg"],
+        "sellerInformation": { "sellerId": 42, "sellerName": "Herplaatser" },
+    })
+}
+
+#[tokio::test]
+async fn herplaatsingen_worden_gekoppeld() {
+    let mut omgeving = Omgeving::met_advertenties("fiets;-1\n", "", json!([
+        advertentie("m1", "Gazelle fiets 28 inch", 15000),
+        advertentie("m2", "Gazelle fiets, 28 inch!", 15000), // Zelfde advertentie opnieuw geplaatst
+        advertentie("m3", "Gazelle fiets 28 inch", 12500),   // Andere prijs, telt als nieuw
+    ]));
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+
+    let resultaten = omgeving.get_json("/resultaten").await;
+    let herplaatsing_van = |id: &str| resultaten.as_array().unwrap().iter()
+        .find(|r| r["item_id"] == id).unwrap()["herplaatsing_van"].clone();
+    assert_eq!(herplaatsing_van("m1"), serde_json::Value::Null);
+    assert_eq!(herplaatsing_van("m2"), "m1");
+    assert_eq!(herplaatsing_van("m3"), serde_json::Value::Null);
+    assert!(omgeving.lees("results.txt").contains("Herplaatsing van: m1"));
+}
+
+#[tokio::test]
+async fn herplaatsingen_onderdrukken() {
+    let mut omgeving = Omgeving::met_advertenties("fiets;-1\n", "onderdruk_herplaatsingen = true\n", json!([
+        advertentie("m1", "Gazelle fiets 28 inch", 15000),
+        advertentie("m2", "Gazelle fiets 28 inch", 15000),
+    ]));
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+
+    let resultaten = omgeving.get_json("/resultaten").await;
+    assert_eq!(resultaten.as_array().unwrap().len(), 1);
+    assert_eq!(resultaten[0]["item_id"], "m1");
+    assert_eq!(resultaten[0]["herplaatsingen"], json!(["https://www.marktplaats.nl/v/test/m2-test"]));
+
+    // Na een herstart wordt de herplaatsing niet opnieuw gemeld
+    omgeving.herstart_monitor();
+    omgeving.wacht_op_ronde();
+    assert_eq!(omgeving.get_json("/resultaten").await.as_array().unwrap().len(), 1);
+    assert_eq!(omgeving.lees("results.txt").matches("Gevonden:").count(), 1);
+}
+
+#[tokio::test]
+async fn advertentie_krijgt_alle_zoekwoorden() {
+    let omgeving = Omgeving::start_en_wacht("steam deck;-1\nsteamdeck;-1\nlcd;100\n", "");
+
+    let resultaten = omgeving.get_json("/resultaten").await;
+    let resultaten = resultaten.as_array().unwrap();
+    assert_eq!(resultaten.len(), 2, "geen dubbele resultaten");
+
+    let zoekwoorden = |titel: &str| resultaten.iter().find(|r| r["titel"] == titel).unwrap()["zoekwoorden"].clone();
+    assert_eq!(zoekwoorden("Steam Deck OLED 512GB"), json!(["steam deck", "steamdeck"]));
+    // Bieden voldoet ook aan "lcd" met een max prijs
+    assert_eq!(zoekwoorden("Steamdeck 256GB LCD"), json!(["steam deck", "steamdeck", "lcd"]));
+
+    let gefilterd = omgeving.get_json("/resultaten?zoekwoord=steamdeck").await;
+    assert_eq!(gefilterd.as_array().unwrap().len(), 2);
+    let gefilterd = omgeving.get_json("/resultaten?zoekwoord=lcd").await;
+    assert_eq!(gefilterd.as_array().unwrap().len(), 1);
+    assert_eq!(gefilterd[0]["zoekwoord"], "steam deck");
+}
+
+#[tokio::test]
+async fn status_per_artikel_op_de_server() {
+    let omgeving = Omgeving::start_en_wacht("stoel;-1\n", "");
+
+    let nieuw = omgeving.get_json("/resultaten?staat=nieuw").await;
+    assert_eq!(nieuw.as_array().unwrap().len(), 3);
+    assert!(nieuw.as_array().unwrap().iter().all(|r| r["staat"] == "nieuw" && r["favoriet"] == false));
+
+    let antwoord = omgeving.post_json("/status", json!({ "item_ids": ["m2100000004"], "staat": "gezien", "favoriet": true })).await;
+    assert_eq!(antwoord["status"], "ok");
+    omgeving.post_json("/status", json!({ "item_ids": ["m2100000005"], "staat": "verborgen" })).await;
+
+    let nieuw = omgeving.get_json("/resultaten?staat=nieuw").await;
+    assert_eq!(nieuw.as_array().unwrap().len(), 1);
+    assert_eq!(nieuw[0]["item_id"], "m2100000006");
+
+    // Verborgen artikelen vallen standaard weg, maar zijn apart op te vragen
+    assert_eq!(omgeving.titels().await, vec!["Eetkamer stoel eiken", "Houten stoel gratis af te halen"]);
+    let verborgen = omgeving.get_json("/resultaten?staat=verborgen").await;
+    assert_eq!(verborgen[0]["item_id"], "m2100000005");
+
+    // Alleen favoriet aanpassen laat de staat staan
+    omgeving.post_json("/status", json!({ "item_ids": ["m2100000004"], "favoriet": false })).await;
+    let gezien = omgeving.get_json("/resultaten?staat=gezien").await;
+    assert_eq!(gezien[0]["item_id"], "m2100000004");
+    assert_eq!(gezien[0]["favoriet"], false);
+
+    // De oude route met links werkt nog
+    let link = nieuw[0]["link"].clone();
+    omgeving.post_json("/markeer_gezien", json!({ "links": [link] })).await;
+    assert_eq!(omgeving.get_json("/resultaten?staat=nieuw").await.as_array().unwrap().len(), 0);
+    assert!(omgeving.lees("status.json").contains("m2100000006"));
+    assert!(!omgeving.map.join("gezien.txt").exists());
+}
+
+#[tokio::test]
+async fn favorieten_met_notitie_en_status() {
+    let omgeving = Omgeving::start_en_wacht("rx 6600;-1\n", "");
+
+    omgeving.post_json("/status", json!({ "item_ids": ["m2100000001", "m2100000002"], "favoriet": true })).await;
+    omgeving.post_json("/status", json!({ "item_ids": ["m2100000001"], "staat": "onderhandelen" })).await;
+    let antwoord = omgeving.post_json("/notitie", json!({ "item_id": "m2100000001", "notitie": "bericht gestuurd, reageert morgen" })).await;
+    assert_eq!(antwoord["status"], "ok");
+
+    let favorieten = omgeving.get_json("/resultaten?favoriet=true").await;
+    assert_eq!(favorieten.as_array().unwrap().len(), 2);
+
+    let onderhandelen = omgeving.get_json("/resultaten?favoriet=true&staat=onderhandelen").await;
+    assert_eq!(onderhandelen.as_array().unwrap().len(), 1);
+    assert_eq!(onderhandelen[0]["item_id"], "m2100000001");
+    assert_eq!(onderhandelen[0]["notitie"], "bericht gestuurd, reageert morgen");
+
+    omgeving.post_json("/status", json!({ "item_ids": ["m2100000002"], "staat": "afgezien" })).await;
+    let afgezien = omgeving.get_json("/resultaten?staat=afgezien").await;
+    assert_eq!(afgezien[0]["item_id"], "m2100000002");
+    assert_eq!(afgezien[0]["notitie"], "");
+}
+
+#[tokio::test]
+async fn resultaten_filteren_sorteren_en_pagineren() {
+    let omgeving = Omgeving::start_en_wacht("rx 6600;-1\nstoel;-1\nsteam deck;-1\n", "");
+    let item_ids = |resultaten: serde_json::Value| -> Vec<String> {
+        resultaten.as_array().unwrap().iter().map(|r| r["item_id"].as_str().unwrap().to_string()).collect()
+    };
+
+    let prijs = omgeving.get_json("/resultaten?prijs_min=100&prijs_max=200&sorteer=prijs").await;
+    assert_eq!(item_ids(prijs), vec!["m2100000001", "m2100000002"]);
+
+    // Zonder prijs komt achteraan, ook bij aflopend sorteren
+    let gesorteerd = item_ids(omgeving.get_json("/resultaten?sorteer=prijs&richting=af").await);
+    assert_eq!(&gesorteerd[..5], ["m2100000007", "m2100000002", "m2100000001", "m2100000006", "m2100000004"]);
+    assert_eq!(gesorteerd.len(), 8);
+
+    let dichtbij = omgeving.get_json("/resultaten?afstand_max=3&sorteer=afstand").await;
+    assert_eq!(item_ids(dichtbij), vec!["m2100000004", "m2100000006"]);
+
+    let bieden = omgeving.get_json("/resultaten?prijs_type=bid").await;
+    assert_eq!(bieden.as_array().unwrap().len(), 2);
+
+    let vandaag = chrono::Local::now().format("%Y-%m-%d").to_string();
+    assert_eq!(omgeving.get_json(&format!("/resultaten?van={}", vandaag)).await.as_array().unwrap().len(), 8);
+    assert_eq!(omgeving.get_json("/resultaten?tot=2000-01-01").await.as_array().unwrap().len(), 0);
+
+    // Pagina's sluiten op elkaar aan, het totaal staat in een header
+    let antwoord = reqwest::get(omgeving.url("/resultaten?sorteer=prijs&limit=3&offset=3")).await.unwrap();
+    assert_eq!(antwoord.headers()["X-Totaal"], "8");
+    let pagina = item_ids(antwoord.json().await.unwrap());
+    let alles = item_ids(omgeving.get_json("/resultaten?sorteer=prijs").await);
+    assert_eq!(pagina, alles[3..6]);
+}
+
+#[tokio::test]
+async fn zoeken_via_de_index() {
+    let met_beschrijving = |item_id: &str, titel: &str, beschrijving: &str| {
+        let mut advertentie = advertentie(item_id, titel, 5000);
+        advertentie["description"] = json!(beschrijving);
+        advertentie
+    };
+    let mut omgeving = Omgeving::met_advertenties("stoel;-1\n", "check_interval_seconden = 1\n", json!([
+        met_beschrijving("a1", "Houten stoeltjes voor kinderen", "Twee stoeltjes"),
+        met_beschrijving("a2", "Eetkamerstoelen 4 stuks", "Café stoelen, zwarte zitting"),
+        met_beschrijving("a3", "Bureaustoel zwart", "Stoel met wieltjes"),
+        met_beschrijving("a4", "Crème kast", "Ruime kast"),
+    ]));
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+
+    let zoek = |q: &str| {
+        let url = format!("/resultaten?q={}", urlencoding::encode(q));
+        let omgeving = &omgeving;
+        async move {
+            omgeving.get_json(&url).await.as_array().unwrap().iter()
+                .map(|r| r["item_id"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // Meervoud en verkleinwoord vinden hetzelfde, met de titel als beste match
+    let stoelen = zoek("stoelen").await;
+    assert_eq!(stoelen.len(), 3);
+    assert_eq!(stoelen[0], "a1");
+
+    // Alle termen moeten voorkomen
+    assert_eq!(zoek("zwarte stoel").await, vec!["a3", "a2"]);
+    assert_eq!(zoek("cafe").await, vec!["a2"]);
+    assert_eq!(zoek("bureau").await, vec!["a3"]);
+    assert!(zoek("fiets").await.is_empty());
+
+    // Nieuwe resultaten komen er direct bij, zonder herstart
+    omgeving.schrijf("wishlist.txt", "stoel;-1\nkast;-1\n");
+    omgeving.wacht_op_ronde();
+    omgeving.wacht_op_ronde();
+    assert_eq!(zoek("creme").await, vec!["a4"]);
+}
+
+#[tokio::test]
+async fn prometheus_metrieken() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "");
+
+    let metrieken = reqwest::get(omgeving.url("/metrics")).await.unwrap().text().await.unwrap();
+    for regel in [
+        "mpm_zoekopdrachten_totaal 4", // rx 6600 heeft een maximum prijs en wordt voor de prijzen nog een keer gezocht
+        "mpm_rondes_totaal 1",
+        "mpm_ronde_seconden_count 1",
+        "mpm_api_verzoek_seconden_count 4",
+        "mpm_gezien_advertenties 5",
+        "mpm_nieuwe_treffers_totaal{zoekwoord=\"rx 6600\"} 2",
+        "mpm_nieuwe_treffers_totaal{zoekwoord=\"stoel\"} 1",
+        "mpm_nieuwe_treffers_totaal{zoekwoord=\"steam deck\"} 2",
+    ] {
+        assert!(metrieken.lines().any(|l| l == regel), "{} ontbreekt in:\n{}", regel, metrieken);
+    }
+    assert!(metrieken.contains("# TYPE mpm_ronde_seconden histogram"));
+
+    // Marktplaats niet bereikbaar
+    let omgeving = Omgeving::start_en_wacht("rx 6600;150\n", "api_basis_url = \"http://127.0.0.1:1\"\n");
+    let metrieken = reqwest::get(omgeving.url("/metrics")).await.unwrap().text().await.unwrap();
+    assert!(metrieken.lines().any(|l| l == "mpm_api_fouten_totaal{status=\"verbinding\"} 1"), "{}", metrieken);
+    assert!(metrieken.lines().any(|l| l == "mpm_nieuwe_treffers_totaal{zoekwoord=\"rx 6600\"} 0"), "{}", metrieken);
+}
+
+#[tokio::test]
+async fn status_van_de_check_lus() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "");
+
+    let status = omgeving.get_json("/status").await;
+    assert_eq!(status["versie"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(status["gezond"], true);
+    assert_eq!(status["ronde"]["bezig"], false);
+    assert!(status["ronde"]["laatste_einde"].is_string());
+    assert!(status["ronde"]["laatste_duur_seconden"].is_number());
+    assert!(status["ronde"]["laatste_fout"].is_null());
+    assert!(status["volgende_check"].is_string());
+    assert_eq!(status["treffers_vandaag"], 5);
+    let zoekwoorden: Vec<&str> = status["wenslijst"].as_array().unwrap().iter().map(|i| i["zoekwoord"].as_str().unwrap()).collect();
+    assert_eq!(zoekwoorden, ["rx 6600", "steam deck", "stoel"]);
+    assert!(status["wenslijst"].as_array().unwrap().iter().all(|i| i["laatste_fout"].is_null() && i["laatst_gelukt"].is_string()));
+    // Dezelfde samenvatting onder de API
+    let alias = omgeving.get_json("/api/v1/status").await;
+    assert_eq!(alias["versie"], status["versie"]);
+    assert_eq!(alias["treffers_vandaag"], 5);
+    assert_eq!(alias["wenslijst"].as_array().unwrap().len(), 3);
+
+    let healthz = reqwest::get(omgeving.url("/healthz")).await.unwrap();
+    assert_eq!(healthz.status(), reqwest::StatusCode::OK);
+    assert_eq!(healthz.json::<serde_json::Value>().await.unwrap()["status"], "ok");
+
+    // Marktplaats niet bereikbaar, de fout staat bij het zoekwoord
+    let omgeving = Omgeving::start_en_wacht("rx 6600;150\n", "api_basis_url = \"http://127.0.0.1:1\"\n");
+    let status = omgeving.get_json("/status").await;
+    assert_eq!(status["treffers_vandaag"], 0);
+    let item = &status["wenslijst"][0];
+    assert_eq!(item["zoekwoord"], "rx 6600");
+    assert!(item["laatste_fout"].is_string());
+    assert!(item["laatst_gelukt"].is_null());
+}
+
+#[tokio::test]
+async fn eigen_interval_per_zoekwoord() {
+    let mut omgeving = Omgeving::nieuw("steam deck;-1;1\nstoel;0\n", "");
+    omgeving.start_monitor();
+
+    // Steam deck elke seconde, stoel alleen in de eerste ronde (standaard interval is een uur)
+    let uitvoer = omgeving.uitvoer_van_rondes(3);
+    let gezocht = |zoekwoord: &str| uitvoer.iter().filter(|l| l.starts_with(&format!("Aan het zoeken voor: '{}'", zoekwoord))).count();
+    assert_eq!(gezocht("steam deck"), 3);
+    assert_eq!(gezocht("stoel"), 1);
+
+    let status = omgeving.get_json("/status").await;
+    let volgende = |zoekwoord: &str| {
+        let item = status["wenslijst"].as_array().unwrap().iter().find(|i| i["zoekwoord"] == zoekwoord).unwrap();
+        chrono::NaiveDateTime::parse_from_str(item["volgende_check"].as_str().unwrap(), "%Y-%m-%d %H:%M:%S").unwrap()
+    };
+    assert!(volgende("stoel") - volgende("steam deck") > chrono::Duration::minutes(50));
+}
+
+#[tokio::test]
+async fn venster_per_zoekwoord() {
+    // Alleen morgen, dus de eerste ronde slaat steam deck over
+    let dagen = ["ma", "di", "wo", "do", "vr", "za", "zo"];
+    let morgen = chrono::Local::now() + chrono::Duration::days(1);
+    let dag = dagen[chrono::Datelike::weekday(&morgen).num_days_from_monday() as usize];
+    let omgeving = Omgeving::start_en_wacht(&format!("steam deck;-1;;{}\nstoel;0\n", dag), "");
+
+    assert_eq!(omgeving.titels().await, vec!["Houten stoel gratis af te halen"]);
+    let status = omgeving.get_json("/status").await;
+    let item = status["wenslijst"].as_array().unwrap().iter().find(|i| i["zoekwoord"] == "steam deck").unwrap();
+    assert!(item["laatste_check"].is_null());
+    assert_eq!(item["volgende_check"], format!("{} 00:00:00", morgen.format("%Y-%m-%d")));
+
+    // Een ongeldig venster maakt de regel ongeldig
+    let omgeving = Omgeving::start_en_wacht("steam deck;-1;;maandag\nstoel;0\n", "");
+    assert_eq!(omgeving.titels().await, vec!["Houten stoel gratis af te halen"]);
+}
+
+#[tokio::test]
+async fn adaptief_interval() {
+    let omgeving = Omgeving::start_en_wacht(
+        "steam deck;-1\nfiets;-1\nstoel;0;7u\n",
+        "check_interval_seconden = 100\nadaptief_interval = true\nmin_interval_seconden = 10\nmax_interval_seconden = 1000\n",
+    );
+
+    let status = omgeving.get_json("/status").await;
+    let item = |zoekwoord: &str| status["wenslijst"].as_array().unwrap().iter().find(|i| i["zoekwoord"] == zoekwoord).unwrap().clone();
+    // Twee nieuwe advertenties: vaker checken, niks nieuws: minder vaak, een eigen interval blijft vast
+    assert_eq!(item("steam deck")["interval_seconden"], 50);
+    assert_eq!(item("steam deck")["treffers_per_check"], 2.0);
+    assert_eq!(item("fiets")["interval_seconden"], 150);
+    assert_eq!(item("fiets")["treffers_per_check"], 0.0);
+    assert_eq!(item("stoel")["interval_seconden"], 25200);
+    assert!(item("stoel")["treffers_per_check"].is_null());
+
+    // Zonder adaptief geldt gewoon het interval uit de config
+    let omgeving = Omgeving::start_en_wacht("steam deck;-1\n", "check_interval_seconden = 100\n");
+    let status = omgeving.get_json("/status").await;
+    assert_eq!(status["wenslijst"][0]["interval_seconden"], 100);
+    assert!(status["wenslijst"][0]["treffers_per_check"].is_null());
+}
+
+fn tien_videokaarten() -> serde_json::Value {
+    // €100 tot €280, mediaan €190 en 25e percentiel €145
+    let advertenties: Vec<_> = (0..10)
+        .map(|i| advertentie(&format!("m{}", i), &format!("RX 6600 nummer {}", i), 10000 + i * 2000))
+        .collect();
+    json!(advertenties)
+}
+
+// Dezelfde prijzen als tien_videokaarten, van een eerdere check
+fn eerdere_prijzen() -> String {
+    let waarnemingen: Vec<_> = (0..10)
+        .map(|i| json!({ "item_id": format!("oud{}", i), "centen": 10000 + i * 2000, "datum": "2024-01-01" }))
+        .collect();
+    json!({ "rx 6600": waarnemingen }).to_string()
+}
+
+#[tokio::test]
+async fn deal_score_tegen_eerdere_prijzen() {
+    let mut omgeving = Omgeving::met_advertenties("rx 6600;-1\n", "", tien_videokaarten());
+    omgeving.schrijf("prices.json", &eerdere_prijzen());
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+
+    let resultaten = omgeving.get_json("/resultaten?sorteer=deal").await;
+    let resultaten = resultaten.as_array().unwrap();
+    assert_eq!(resultaten.len(), 10);
+    assert!(resultaten.iter().all(|r| r["deal"]["mediaan_centen"] == 19000 && r["deal"]["aantal_prijzen"] == 10));
+
+    assert_eq!(resultaten[0]["titel"], "RX 6600 nummer 0");
+    assert_eq!(resultaten[0]["deal"]["percentiel"], 0.0);
+    assert_eq!(resultaten[0]["deal"]["verschil_procent"], -47.4);
+    assert_eq!(resultaten[9]["titel"], "RX 6600 nummer 9");
+    assert_eq!(resultaten[9]["deal"]["percentiel"], 90.0);
+
+    assert!(omgeving.lees("results.txt").contains("Deal: 47% onder de mediaan (€190.00)"));
+    assert!(omgeving.lees("prices.json").contains("\"m9\""));
+
+    // Met een maximum prijs ook een score, en de duurdere advertenties tellen toch mee voor de prijzen
+    let mut omgeving = Omgeving::met_advertenties("rx 6600;150\n", "", tien_videokaarten());
+    omgeving.schrijf("prices.json", &eerdere_prijzen());
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+    let resultaten = omgeving.get_json("/resultaten?sorteer=deal").await;
+    let resultaten = resultaten.as_array().unwrap();
+    assert_eq!(resultaten.len(), 3);
+    assert!(resultaten.iter().all(|r| r["deal"]["mediaan_centen"] == 19000 && r["deal"]["aantal_prijzen"] == 10));
+    assert_eq!(resultaten[0]["deal"]["verschil_procent"], -47.4);
+    assert!(omgeving.lees("prices.json").contains("\"m9\""));
+
+    // Zonder eerdere prijzen nog geen score
+    let mut omgeving = Omgeving::met_advertenties("rx 6600;150\n", "", tien_videokaarten());
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+    let resultaten = omgeving.get_json("/resultaten").await;
+    assert!(resultaten.as_array().unwrap().iter().all(|r| r["deal"].is_null()));
+}
+
+#[tokio::test]
+async fn wenslijst_met_percentiel() {
+    let mut omgeving = Omgeving::met_advertenties("rx 6600;p25\n", "", tien_videokaarten());
+    omgeving.schrijf("prices.json", &eerdere_prijzen());
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+
+    assert_eq!(omgeving.titels().await, vec!["RX 6600 nummer 0", "RX 6600 nummer 1", "RX 6600 nummer 2"]);
+
+    let wenslijst = omgeving.get_json("/api/v1/wenslijst").await;
+    assert_eq!(wenslijst[0]["max_prijs"], -1);
+    assert_eq!(wenslijst[0]["max_percentiel"], 25);
+    let wenslijst = omgeving.get_json("/wenslijst").await;
+    assert_eq!(wenslijst[0]["max_prijs"], "p25");
+}
+
+#[tokio::test]
+async fn statistieken_per_zoekwoord() {
+    let omgeving = Omgeving::start_en_wacht("rx 6600;-1\nstoel;0\n", "");
+
+    let statistieken = omgeving.get_json("/api/v1/statistieken").await;
+    let statistieken = statistieken.as_array().unwrap();
+    assert_eq!(statistieken.len(), 2);
+
+    let rx = &statistieken[0];
+    assert_eq!(rx["zoekwoord"], "rx 6600");
+    assert_eq!(rx["alleen_treffers"], true);
+    assert_eq!(rx["aantal"], 3);
+    assert_eq!(rx["mediaan_centen"], 16750);
+    assert_eq!(rx["per_dag"].as_array().unwrap().len(), 1);
+    assert_eq!(rx["per_dag"][0]["aantal"], 3);
+    assert_eq!(rx["mediaan_per_week"][0]["mediaan_centen"], 16750);
+    assert_eq!(rx["mediaan_per_week"][0]["aantal"], 2);
+    // €140 tot €195 in staven van €10
+    let histogram = rx["histogram"].as_array().unwrap();
+    assert_eq!(histogram.len(), 6);
+    assert_eq!(histogram[0], json!({ "van_centen": 14000, "tot_centen": 15000, "aantal": 1 }));
+    assert_eq!(histogram[5], json!({ "van_centen": 19000, "tot_centen": 20000, "aantal": 1 }));
+    assert_eq!(rx["prijs_types"], json!([
+        { "prijs_type": "FIXED", "aantal": 2, "aandeel_procent": 66.7 },
+        { "prijs_type": "BID", "aantal": 1, "aandeel_procent": 33.3 },
+    ]));
+
+    let stoel = &statistieken[1];
+    assert_eq!(stoel["aantal"], 1);
+    assert_eq!(stoel["mediaan_centen"], serde_json::Value::Null);
+    assert_eq!(stoel["prijs_types"], json!([{ "prijs_type": "FREE", "aantal": 1, "aandeel_procent": 100.0 }]));
+
+    let onbekend = omgeving.get_json("/api/v1/statistieken?zoekwoord=fiets").await;
+    assert_eq!(onbekend[0]["aantal"], 0);
+    assert_eq!(onbekend[0]["histogram"], json!([]));
+}
+
+#[tokio::test]
+async fn atom_feeds() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "");
+
+    let antwoord = reqwest::get(omgeving.url("/feed")).await.unwrap();
+    assert_eq!(antwoord.headers()["content-type"], "application/atom+xml; charset=utf-8");
+    let feed = antwoord.text().await.unwrap();
+    assert!(feed.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">"));
+    assert!(feed.contains(&format!("<link rel=\"self\" href=\"http://127.0.0.1:{}/feed\"/>", omgeving.web_poort)));
+    assert_eq!(feed.matches("<entry>").count(), 5);
+    assert!(feed.contains("<id>urn:marktplaats:m2100000007</id>"));
+    assert!(feed.contains("<title>Steam Deck OLED 512GB - €425.00</title>"));
+    // De inhoud is HTML, dus ge-escaped in de XML
+    assert!(feed.contains("&lt;b&gt;Locatie:&lt;/b&gt; Delft (7.9 km)"));
+    assert!(feed.contains("&lt;img src=&quot;https://images.marktplaats.com/"));
+
+    let feed = reqwest::get(omgeving.url("/feed/steam%20deck")).await.unwrap().text().await.unwrap();
+    assert!(feed.contains("<title>Marktplaats Monitor: steam deck</title>"));
+    assert_eq!(feed.matches("<entry>").count(), 2);
+    assert!(feed.contains("<category term=\"steam deck\"/>"));
+
+    // Verborgen advertenties komen niet in de feed
+    omgeving.post_json("/status", json!({ "item_ids": ["m2100000007"], "staat": "verborgen" })).await;
+    let feed = reqwest::get(omgeving.url("/feed/steam%20deck")).await.unwrap().text().await.unwrap();
+    assert_eq!(feed.matches("<entry>").count(), 1);
+
+    // Een verzonnen Host header komt niet in de links
+    let feed = reqwest::Client::new().get(omgeving.url("/feed")).header("Host", "aanvaller.example").send().await.unwrap().text().await.unwrap();
+    assert!(!feed.contains("aanvaller.example"));
+    assert!(feed.contains(&format!("<link rel=\"self\" href=\"http://127.0.0.1:{}/feed\"/>", omgeving.web_poort)));
+
+    // Net als /resultaten zonder geblokkeerde verkopers
+    let resultaten = omgeving.get_json("/resultaten").await;
+    let resultaat = &resultaten[0];
+    assert!(feed.contains(&format!("<id>urn:marktplaats:{}</id>", resultaat["item_id"].as_str().unwrap())));
+    let verkoper = resultaat["verkoper_id"].as_str().or(resultaat["verkoper_naam"].as_str()).unwrap();
+    omgeving.post_json("/blokkeer_verkoper", json!({ "verkoper": verkoper })).await;
+    let feed = reqwest::get(omgeving.url("/feed")).await.unwrap().text().await.unwrap();
+    assert!(!feed.contains(&format!("<id>urn:marktplaats:{}</id>", resultaat["item_id"].as_str().unwrap())));
+    assert!(feed.contains("<entry>"));
+}
+
+#[tokio::test]
+async fn feed_met_publieke_url() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "publieke_url = \"https://monitor.example.nl/\"\n");
+
+    let feed = reqwest::get(omgeving.url("/feed/steam%20deck")).await.unwrap().text().await.unwrap();
+    assert!(feed.contains("<link rel=\"self\" href=\"https://monitor.example.nl/feed/steam%20deck\"/>"), "{}", feed);
+}
@@ -0,0 +1,193 @@
+// Tests voor de optionele login van de web interface (sessies, CSRF tokens, API tokens voor scripts) en voor het adres en HTTPS.
+
+mod common;
+
+use common::Omgeving;
+use reqwest::StatusCode;
+use serde_json::json;
+
+const WENSLIJST: &str = "rx 6600;150\n";
+
+fn client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap()
+}
+
+// Logt in en geeft de sessie cookie terug
+async fn log_in(omgeving: &Omgeving, body: serde_json::Value) -> Option<String> {
+    let antwoord = client().post(omgeving.url("/login")).json(&body).send().await.unwrap();
+    if antwoord.status() != StatusCode::OK {
+        return None;
+    }
+    let cookie = antwoord.headers()["set-cookie"].to_str().unwrap();
+    Some(cookie.split(';').next().unwrap().to_string())
+}
+
+async fn csrf_token(omgeving: &Omgeving, cookie: &str) -> String {
+    let html = client().get(omgeving.url("/")).header("Cookie", cookie).send().await.unwrap().text().await.unwrap();
+    let begin = html.find("const CSRF_TOKEN = '").unwrap() + "const CSRF_TOKEN = '".len();
+    html[begin..].split('\'').next().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn zonder_login_alles_open() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "");
+
+    let antwoord = client().get(omgeving.url("/")).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::OK);
+    assert!(antwoord.text().await.unwrap().contains("const CSRF_TOKEN = '';"));
+    let antwoord = client().post(omgeving.url("/status"))
+        .json(&json!({ "item_ids": ["m2100000001"], "favoriet": true }))
+        .send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn sessie_met_gedeeld_token_en_csrf() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "web_token = \"geheim\"\n");
+
+    assert_eq!(client().get(omgeving.url("/resultaten")).send().await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    let antwoord = client().get(omgeving.url("/")).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::SEE_OTHER);
+    assert_eq!(antwoord.headers()["location"], "/login");
+    assert_eq!(client().get(omgeving.url("/login")).send().await.unwrap().status(), StatusCode::OK);
+    assert_eq!(client().get(omgeving.url("/status")).send().await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(client().get(omgeving.url("/healthz")).send().await.unwrap().status(), StatusCode::OK);
+
+    assert!(log_in(&omgeving, json!({ "token": "fout" })).await.is_none());
+    let cookie = log_in(&omgeving, json!({ "token": "geheim" })).await.unwrap();
+
+    let antwoord = client().get(omgeving.url("/resultaten")).header("Cookie", &cookie).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::OK);
+
+    // Zonder CSRF token mag een sessie niets aanpassen
+    let status = json!({ "item_ids": ["m2100000001"], "favoriet": true });
+    let antwoord = client().post(omgeving.url("/status")).header("Cookie", &cookie).json(&status).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::FORBIDDEN);
+    let antwoord = client().post(omgeving.url("/status")).header("Cookie", &cookie).header("X-CSRF-Token", "verzonnen").json(&status).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::FORBIDDEN);
+
+    let csrf = csrf_token(&omgeving, &cookie).await;
+    assert_eq!(csrf.len(), 64);
+    let antwoord = client().post(omgeving.url("/status")).header("Cookie", &cookie).header("X-CSRF-Token", &csrf).json(&status).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::OK);
+
+    // Het token zelf komt niet terug in /config
+    let config: serde_json::Value = client().get(omgeving.url("/config")).header("Cookie", &cookie).send().await.unwrap().json().await.unwrap();
+    assert_eq!(config["web_token"], "");
+
+    let antwoord = client().post(omgeving.url("/logout")).header("Cookie", &cookie).header("X-CSRF-Token", &csrf).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::OK);
+    let antwoord = client().get(omgeving.url("/resultaten")).header("Cookie", &cookie).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn api_token_voor_scripts() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "web_token = \"geheim\"\napi_tokens = [\"script-token\"]\n");
+
+    let antwoord = client().get(omgeving.url("/resultaten")).bearer_auth("script-token").send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::OK);
+    assert_eq!(antwoord.json::<serde_json::Value>().await.unwrap().as_array().unwrap().len(), 2);
+
+    // Geen sessie, dus ook geen CSRF token nodig
+    let antwoord = client().post(omgeving.url("/status")).bearer_auth("script-token")
+        .json(&json!({ "item_ids": ["m2100000001"], "staat": "gezien" }))
+        .send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::OK);
+
+    // Het login token is geen API token
+    let antwoord = client().get(omgeving.url("/resultaten")).bearer_auth("geheim").send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn wachtwoord_wordt_gehasht() {
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "web_gebruiker = \"anne\"\nweb_wachtwoord = \"hunter2\"\n");
+
+    let config: toml::Table = toml::from_str(&omgeving.lees("config.toml")).unwrap();
+    assert_eq!(config["web_wachtwoord"].as_str(), Some(""));
+    assert!(config["web_wachtwoord_hash"].as_str().unwrap().starts_with("$argon2"));
+    assert!(!omgeving.lees("config.toml").contains("hunter2"));
+
+    assert!(log_in(&omgeving, json!({ "gebruiker": "anne", "wachtwoord": "fout" })).await.is_none());
+    assert!(log_in(&omgeving, json!({ "gebruiker": "bert", "wachtwoord": "hunter2" })).await.is_none());
+    assert!(log_in(&omgeving, json!({ "gebruiker": "anne", "wachtwoord": "hunter2" })).await.is_some());
+}
+
+#[tokio::test]
+async fn netwerk_adres_alleen_met_login() {
+    let mut omgeving = Omgeving::nieuw(WENSLIJST, "web_adres = \"0.0.0.0\"\n");
+    omgeving.start_proces();
+    omgeving.wacht_op_regel("Web interface niet gestart");
+    // De monitor zelf draait gewoon door
+    omgeving.wacht_op_ronde();
+    assert!(std::net::TcpStream::connect(("127.0.0.1", omgeving.web_poort)).is_err());
+
+    let mut omgeving = Omgeving::nieuw(WENSLIJST, "web_adres = \"0.0.0.0\"\nweb_token = \"geheim\"\nfeed_token = \"lezer\"\n");
+    omgeving.start_monitor();
+    omgeving.wacht_op_regel("Let op: de links in de feeds gaan naar localhost");
+    omgeving.wacht_op_ronde();
+    let antwoord = client().get(omgeving.url("/resultaten")).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::UNAUTHORIZED);
+
+    // Zonder publieke_url wijzen de feeds niet naar 0.0.0.0
+    let feed = client().get(omgeving.url("/feed?token=lezer")).send().await.unwrap().text().await.unwrap();
+    assert!(feed.contains(&format!("<link rel=\"self\" href=\"http://localhost:{}/feed\"/>", omgeving.web_poort)), "{}", feed);
+}
+
+#[tokio::test]
+async fn https_met_zelf_ondertekend_certificaat() {
+    let mut omgeving = Omgeving::nieuw(WENSLIJST, "web_tls = true\nweb_token = \"geheim\"\n");
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+
+    let certificaat = omgeving.lees("cert.pem");
+    assert!(certificaat.starts_with("-----BEGIN CERTIFICATE-----"));
+    assert!(omgeving.lees("key.pem").contains("PRIVATE KEY"));
+
+    let https = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let https_url = |pad: &str| format!("https://127.0.0.1:{}{}", omgeving.web_poort, pad);
+
+    let antwoord = https.post(https_url("/login")).json(&json!({ "token": "geheim" })).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::OK);
+    let cookie = antwoord.headers()["set-cookie"].to_str().unwrap().to_string();
+    assert!(cookie.contains("; Secure"));
+
+    let sessie = cookie.split(';').next().unwrap();
+    let resultaten: serde_json::Value = https.get(https_url("/resultaten")).header("Cookie", sessie).send().await.unwrap().json().await.unwrap();
+    assert_eq!(resultaten.as_array().unwrap().len(), 2);
+
+    // Gewoon http werkt niet
+    assert!(client().get(omgeving.url("/resultaten")).send().await.is_err());
+
+    // Bij een herstart wordt het bestaande certificaat gebruikt
+    omgeving.herstart_monitor();
+    assert_eq!(omgeving.lees("cert.pem"), certificaat);
+}
+
+#[tokio::test]
+async fn feeds_met_feed_token() {
+    // Met de login aan en zonder feed_token staan de feeds dicht
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "web_token = \"geheim\"\n");
+    assert_eq!(client().get(omgeving.url("/feed")).send().await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    drop(omgeving);
+
+    let omgeving = Omgeving::start_en_wacht(WENSLIJST, "web_token = \"geheim\"\nfeed_token = \"lezer\"\napi_tokens = [\"script-token\"]\n");
+    assert_eq!(client().get(omgeving.url("/feed")).send().await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(client().get(omgeving.url("/feed?token=fout")).send().await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    let antwoord = client().get(omgeving.url("/feed/rx%206600?token=lezer")).send().await.unwrap();
+    assert_eq!(antwoord.status(), StatusCode::OK);
+    assert!(antwoord.text().await.unwrap().contains("<title>Marktplaats Monitor: rx 6600</title>"));
+
+    // Het token is geheim, net als de andere tokens
+    let config = client().get(omgeving.url("/api/v1/config")).bearer_auth("script-token").send().await.unwrap();
+    assert_eq!(config.status(), StatusCode::OK);
+    assert_eq!(config.json::<serde_json::Value>().await.unwrap()["feed_token"], "");
+}
@@ -0,0 +1,263 @@
+// De opdrachten naast `run`: zoeken, de wenslijst, resultaten en de config vanaf de opdrachtregel
+
+mod common;
+
+use common::Omgeving;
+use serde_json::json;
+use std::process::Command;
+
+const WENSLIJST: &str = "# Test wenslijst\nrx 6600;150\nstoel;0\n";
+
+fn stdout(uitvoer: &std::process::Output) -> String {
+    assert!(uitvoer.status.success(), "{}", String::from_utf8_lossy(&uitvoer.stderr));
+    String::from_utf8(uitvoer.stdout.clone()).unwrap()
+}
+
+#[test]
+fn zoeken_en_wenslijst() {
+    let omgeving = Omgeving::nieuw(WENSLIJST, "");
+
+    let tabel = stdout(&omgeving.voer_uit(&["search", "rx", "6600", "--max-prijs", "150"]));
+    let regels: Vec<&str> = tabel.lines().collect();
+    assert!(regels[0].starts_with("Titel"), "{}", tabel);
+    assert!(regels[1].starts_with("-----"));
+    assert!(tabel.contains("AMD Radeon RX 6600 8GB"));
+    assert!(tabel.contains("https://www.marktplaats.nl/v/"));
+    assert!(!tabel.contains("Sapphire"), "boven de max prijs");
+
+    let json: serde_json::Value = serde_json::from_str(&stdout(&omgeving.voer_uit(&["search", "steam deck", "--json"]))).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 2);
+    assert_eq!(json[0]["titel"], "Steam Deck OLED 512GB");
+
+    stdout(&omgeving.voer_uit(&["wishlist", "add", "steam deck", "p25", "--interval", "5m", "--venster", "ma-vr 08-23"]));
+    assert!(omgeving.lees("wishlist.txt").ends_with("steam deck;p25;300;ma-vr 08-23\n"));
+    assert!(!omgeving.voer_uit(&["wishlist", "add", "lamp", "p100"]).status.success());
+    let fout = omgeving.voer_uit(&["wishlist", "add", "lamp", "-1", "--interval", "99999999999999999m"]);
+    assert!(!fout.status.success());
+    assert!(!omgeving.voer_uit(&["wishlist", "add", "lamp", "-1", "--interval", "721u"]).status.success());
+    assert!(!omgeving.lees("wishlist.txt").contains("lamp"));
+
+    let lijst: serde_json::Value = serde_json::from_str(&stdout(&omgeving.voer_uit(&["wishlist", "list", "--json"]))).unwrap();
+    assert_eq!(lijst.as_array().unwrap().len(), 3);
+    assert_eq!(lijst[2]["id"], 3);
+    assert_eq!(lijst[2]["max_percentiel"], 25);
+
+    // Op id of op zoekwoord, het commentaar blijft staan
+    stdout(&omgeving.voer_uit(&["wishlist", "remove", "1"]));
+    stdout(&omgeving.voer_uit(&["wishlist", "remove", "Steam Deck"]));
+    assert_eq!(omgeving.lees("wishlist.txt"), "# Test wenslijst\nstoel;0\n");
+    assert!(stdout(&omgeving.voer_uit(&["wishlist", "list"])).contains("stoel"));
+    assert!(!omgeving.voer_uit(&["wishlist", "remove", "fiets"]).status.success());
+
+    let fout = omgeving.voer_uit(&["wishlist", "opruimen"]);
+    assert!(!fout.status.success());
+    assert!(String::from_utf8_lossy(&fout.stderr).contains("Onbekende opdracht"));
+}
+
+#[test]
+fn resultaten_en_config() {
+    let mut omgeving = Omgeving::nieuw(WENSLIJST, "");
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+
+    let tabel = stdout(&omgeving.voer_uit(&["results", "list", "--zoekwoord", "rx 6600", "--sorteer", "prijs"]));
+    let regels: Vec<&str> = tabel.lines().collect();
+    assert_eq!(regels.len(), 2 + 2, "{}", tabel);
+    assert!(regels[0].starts_with("Id"));
+    assert!(regels[2].starts_with("m2100000001") && regels[2].contains("AMD Radeon RX 6600 8GB"), "{}", tabel);
+    let json: serde_json::Value = serde_json::from_str(&stdout(&omgeving.voer_uit(&["results", "list", "--limit", "1", "--json"]))).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 1);
+    assert!(!omgeving.voer_uit(&["results", "list", "--sorteer", "gewicht"]).status.success());
+
+    assert_eq!(stdout(&omgeving.voer_uit(&["results", "export", "ndjson"])).lines().count(), 3);
+
+    assert!(!omgeving.voer_uit(&["results", "clear"]).status.success(), "zonder --ja");
+    stdout(&omgeving.voer_uit(&["results", "clear", "--ja"]));
+    assert_eq!(omgeving.lees("results.jsonl"), "");
+
+    // Config lezen en aanpassen, ook in een ander bestand met --config
+    assert_eq!(stdout(&omgeving.voer_uit(&["config", "get", "postcode"])), "3032SG\n");
+    stdout(&omgeving.voer_uit(&["config", "set", "afstand_km", "25"]));
+    stdout(&omgeving.voer_uit(&["config", "set", "geblokkeerde_verkopers", "1004,Henk"]));
+    assert_eq!(stdout(&omgeving.voer_uit(&["config", "get", "afstand_km"])), "25\n");
+    assert_eq!(stdout(&omgeving.voer_uit(&["config", "get", "geblokkeerde_verkopers"])), "[\"1004\",\"Henk\"]\n");
+    assert!(!omgeving.voer_uit(&["config", "set", "afstand_km", "ver"]).status.success());
+    assert!(!omgeving.voer_uit(&["config", "set", "min_interval_seconden", "0"]).status.success());
+    assert!(!omgeving.voer_uit(&["config", "set", "kleur", "blauw"]).status.success());
+    assert!(stdout(&omgeving.voer_uit(&["config", "validate"])).contains("config.toml is geldig"));
+
+    omgeving.schrijf("ander.toml", "postcode = \"1012AB\"\nkleur = \"blauw\"\ncheck_interval_seconden = 0\n");
+    assert_eq!(stdout(&omgeving.voer_uit(&["--config", "ander.toml", "config", "get", "postcode"])), "1012AB\n");
+    let fout = omgeving.voer_uit(&["config", "validate", "--config", "ander.toml"]);
+    assert!(!fout.status.success());
+    let fout = String::from_utf8_lossy(&fout.stderr);
+    assert!(fout.contains("Onbekende instelling kleur"), "{}", fout);
+    assert!(fout.contains("check_interval_seconden moet minstens 1 zijn"), "{}", fout);
+}
+
+#[tokio::test]
+async fn ander_config_bestand_en_data_map() {
+    let mut omgeving = Omgeving::nieuw(WENSLIJST, "data_map = \"data\"\n");
+    std::fs::create_dir_all(omgeving.map.join("instellingen")).unwrap();
+    std::fs::create_dir_all(omgeving.map.join("data")).unwrap();
+    std::fs::rename(omgeving.map.join("config.toml"), omgeving.map.join("instellingen/mpm.toml")).unwrap();
+    std::fs::rename(omgeving.map.join("wishlist.txt"), omgeving.map.join("data/wishlist.txt")).unwrap();
+    omgeving.argumenten = vec!["--config".to_string(), "instellingen/mpm.toml".to_string()];
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+
+    // Alles komt in de data map, niet in de map waar de monitor gestart is
+    assert_eq!(omgeving.lees("data/results.jsonl").lines().count(), 3);
+    assert!(!omgeving.map.join("results.jsonl").exists());
+
+    // Aanpassingen gaan naar het bestand dat geladen is
+    let client = reqwest::Client::new();
+    let antwoord = client.patch(omgeving.url("/api/v1/config")).json(&json!({ "afstand_km": 25 })).send().await.unwrap();
+    assert_eq!(antwoord.status(), 200);
+    assert!(omgeving.lees("instellingen/mpm.toml").contains("afstand_km = 25"));
+    assert!(!omgeving.map.join("config.toml").exists());
+    let antwoord = client.patch(omgeving.url("/api/v1/config")).json(&json!({ "data_map": "/tmp" })).send().await.unwrap();
+    assert_eq!(antwoord.status(), 400);
+
+    // MPM_CONFIG in plaats van --config, een uitvoer bestand blijft relatief aan waar de opdracht gegeven is
+    let uitvoer = Command::new(env!("CARGO_BIN_EXE_marktplaats-monitor"))
+        .current_dir(&omgeving.map)
+        .env("MPM_CONFIG", "instellingen/mpm.toml")
+        .args(["results", "export", "csv", "--uitvoer", "export.csv"])
+        .output()
+        .unwrap();
+    assert!(uitvoer.status.success(), "{}", String::from_utf8_lossy(&uitvoer.stderr));
+    assert_eq!(omgeving.lees("export.csv").lines().count(), 4);
+}
+
+#[test]
+fn xdg_mappen_als_er_geen_config_is() {
+    let omgeving = Omgeving::nieuw(WENSLIJST, "");
+    let leeg = omgeving.map.join("leeg");
+    std::fs::create_dir_all(&leeg).unwrap();
+
+    let uitvoer = Command::new(env!("CARGO_BIN_EXE_marktplaats-monitor"))
+        .current_dir(&leeg)
+        .env_remove("MPM_CONFIG")
+        .env("XDG_CONFIG_HOME", omgeving.map.join("xdg-config"))
+        .env("XDG_DATA_HOME", omgeving.map.join("xdg-data"))
+        .args(["wishlist", "add", "lamp", "20"])
+        .output()
+        .unwrap();
+    assert!(uitvoer.status.success(), "{}", String::from_utf8_lossy(&uitvoer.stderr));
+
+    let config = omgeving.lees("xdg-config/marktplaats-monitor/config.toml");
+    assert!(config.contains(&format!("data_map = \"{}\"", omgeving.map.join("xdg-data/marktplaats-monitor").display())), "{}", config);
+    assert!(omgeving.lees("xdg-data/marktplaats-monitor/wishlist.txt").ends_with("lamp;20\n"));
+    assert_eq!(std::fs::read_dir(&leeg).unwrap().count(), 0);
+}
+
+#[test]
+fn lege_data_map_naast_een_ander_config_bestand() {
+    let omgeving = Omgeving::nieuw(WENSLIJST, "");
+    std::fs::create_dir_all(omgeving.map.join("instellingen")).unwrap();
+    std::fs::rename(omgeving.map.join("config.toml"), omgeving.map.join("instellingen/mpm.toml")).unwrap();
+    let monitor = |argumenten: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_marktplaats-monitor"))
+            .current_dir(&omgeving.map)
+            .env("XDG_DATA_HOME", omgeving.map.join("xdg-data"))
+            .args(["--config", "instellingen/mpm.toml"])
+            .args(argumenten)
+            .output()
+            .unwrap()
+    };
+
+    // Niet naast de config en niet in de map waar de opdracht gegeven is
+    stdout(&monitor(&["wishlist", "add", "lamp", "20"]));
+    assert!(omgeving.lees("xdg-data/marktplaats-monitor/wishlist.txt").ends_with("lamp;20\n"));
+    assert!(!omgeving.lees("wishlist.txt").contains("lamp"));
+    assert_eq!(stdout(&monitor(&["config", "get", "wenslijst_bestand"])).trim(), omgeving.map.join("xdg-data/marktplaats-monitor/wishlist.txt").display().to_string());
+
+    // In de config blijven de paden zoals ze ingesteld waren
+    stdout(&monitor(&["config", "set", "afstand_km", "20"]));
+    let config = omgeving.lees("instellingen/mpm.toml");
+    assert!(config.contains("afstand_km = 20"), "{}", config);
+    assert!(config.contains("wenslijst_bestand = \"wishlist.txt\""), "{}", config);
+}
+
+// De regel van een instelling in `config show --effective`, als woorden
+fn effectief<'a>(tabel: &'a str, instelling: &str) -> Vec<&'a str> {
+    tabel.lines()
+        .find(|regel| regel.split_whitespace().next() == Some(instelling))
+        .unwrap_or_else(|| panic!("{} niet in\n{}", instelling, tabel))
+        .split_whitespace()
+        .collect()
+}
+
+#[tokio::test]
+async fn omgevingsvariabelen_en_opties() {
+    let mut omgeving = Omgeving::nieuw(WENSLIJST, "afstand_km = 12\n");
+    let monitor = |argumenten: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_marktplaats-monitor"))
+            .current_dir(&omgeving.map)
+            .env("MPM_POSTCODE", "1012AB")
+            .env("MPM_API_KEY", "api-123")
+            .env("MPM_WEB_TOKEN", "geheim")
+            .env("MPM_GEBLOKKEERDE_VERKOPERS", "1004,Henk")
+            .args(argumenten)
+            .output()
+            .unwrap()
+    };
+
+    // Standaard, dan config.toml, dan MPM_*, dan de opdrachtregel
+    let tabel = stdout(&monitor(&["--afstand-km", "30", "config", "show", "--effective"]));
+    assert_eq!(effectief(&tabel, "postcode"), ["postcode", "1012AB", "MPM_POSTCODE"]);
+    assert_eq!(effectief(&tabel, "afstand_km"), ["afstand_km", "30", "--afstand-km"]);
+    assert_eq!(effectief(&tabel, "check_interval_seconden"), ["check_interval_seconden", "3600", "config.toml"]);
+    assert_eq!(effectief(&tabel, "min_interval_seconden"), ["min_interval_seconden", "60", "standaard"]);
+    assert_eq!(effectief(&tabel, "geblokkeerde_verkopers"), ["geblokkeerde_verkopers", "1004,", "Henk", "MPM_GEBLOKKEERDE_VERKOPERS"]);
+    assert_eq!(effectief(&tabel, "web_token"), ["web_token", "(verborgen)", "MPM_WEB_TOKEN"]);
+    assert_eq!(stdout(&monitor(&["config", "get", "api_key"])), "api-123\n");
+    assert_eq!(stdout(&monitor(&["--web-poort", "7000", "config", "get", "web_poort"])), "7000\n");
+
+    // Opslaan laat de waarden uit de omgeving uit config.toml
+    stdout(&monitor(&["config", "set", "afstand_km", "20"]));
+    let config = omgeving.lees("config.toml");
+    assert!(config.contains("afstand_km = 20"), "{}", config);
+    assert!(config.contains("postcode = \"3032SG\""), "{}", config);
+    assert!(!config.contains("api-123") && !config.contains("geheim") && !config.contains("Henk"), "{}", config);
+
+    let fout = Command::new(env!("CARGO_BIN_EXE_marktplaats-monitor"))
+        .current_dir(&omgeving.map)
+        .env("MPM_WEB_POORT", "hoog")
+        .args(["config", "get", "web_poort"])
+        .output()
+        .unwrap();
+    assert!(!fout.status.success());
+    assert!(String::from_utf8_lossy(&fout.stderr).contains("MPM_WEB_POORT: Ongeldige waarde 'hoog'"));
+    // Een getal of true blijft tekst bij een instelling die tekst is, ook als die nog leeg is
+    let getal = Command::new(env!("CARGO_BIN_EXE_marktplaats-monitor"))
+        .current_dir(&omgeving.map)
+        .env("MPM_API_KEY", "12345")
+        .args(["config", "get", "api_key"])
+        .output()
+        .unwrap();
+    assert_eq!(stdout(&getal), "12345\n");
+    let tabel = stdout(&monitor(&["--api-key", "true", "--web-gebruiker", "null", "config", "show", "--effective"]));
+    assert_eq!(effectief(&tabel, "api_key"), ["api_key", "true", "--api-key"]);
+    assert_eq!(effectief(&tabel, "web_gebruiker"), ["web_gebruiker", "null", "--web-gebruiker"]);
+    assert_eq!(stdout(&monitor(&["--api-key", "null", "config", "get", "api_key"])), "null\n");
+    stdout(&omgeving.voer_uit(&["config", "set", "api_key", "67890"]));
+    assert!(omgeving.lees("config.toml").contains("api_key = \"67890\""));
+    omgeving.schrijf("config.toml", &omgeving.lees("config.toml").replace("api_key = \"67890\"\n", ""));
+
+    let fout = monitor(&["--min-interval-seconden", "0", "config", "validate"]);
+    assert!(!fout.status.success());
+    assert!(String::from_utf8_lossy(&fout.stderr).contains("min_interval_seconden moet minstens 1 zijn"));
+
+    // Ook de monitor zelf, en een aanpassing via de API schrijft de opdrachtregel waarde niet weg
+    omgeving.argumenten = vec!["--postcode".to_string(), "9999ZZ".to_string()];
+    omgeving.start_monitor();
+    omgeving.wacht_op_ronde();
+    assert_eq!(omgeving.get_json("/api/v1/config").await["postcode"], "9999ZZ");
+    let client = reqwest::Client::new();
+    let antwoord = client.patch(omgeving.url("/api/v1/config")).json(&json!({ "afstand_km": 25 })).send().await.unwrap();
+    assert_eq!(antwoord.status(), 200);
+    let config = omgeving.lees("config.toml");
+    assert!(config.contains("afstand_km = 25") && config.contains("postcode = \"3032SG\""), "{}", config);
+}